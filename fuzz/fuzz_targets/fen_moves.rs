@@ -0,0 +1,12 @@
+#![no_main]
+
+// A FEN line followed by bytes selecting moves, see `check_fuzz_input`.
+// Run with `cargo fuzz run fen_moves`.
+use libfuzzer_sys::fuzz_target;
+use rookbot::roundtrip::check_fuzz_input;
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(e) = check_fuzz_input(data) {
+        panic!("{}", e);
+    }
+});
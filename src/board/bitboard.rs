@@ -1,5 +1,7 @@
 use super::piece::PieceColor;
-use crate::movegen::constants::{A_FILE, H_FILE, RANK_2, RANK_7}; // Assuming these constants are correctly imported
+use super::square::{File, Rank, Square};
+use crate::movegen::constants::{A_FILE, H_FILE, RANK_1, RANK_2, RANK_7}; // Assuming these constants are correctly imported
+use crate::movegen::precomputed::{BETWEEN, LINE};
 use derive_more::{Add, AddAssign, BitAnd, BitOr, BitAndAssign, BitOrAssign, BitXor, BitXorAssign, Mul, Not, Sub, SubAssign, Shr};
 #[derive(
     Copy,
@@ -23,6 +25,19 @@ use derive_more::{Add, AddAssign, BitAnd, BitOr, BitAndAssign, BitOrAssign, BitX
 #[derive(Debug)]
 pub struct Bitboard(u64);
 
+/// Compass direction for shifting a whole bitboard by one square.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
 impl PartialEq<u64> for Bitboard {
     fn eq(&self, other: &u64) -> bool {
         self.0 == *other
@@ -59,8 +74,8 @@ impl Bitboard {
         self.0 |= bit;
     }
 
-    pub fn set_square(&mut self, square: u8) {
-        let bit:u64 = 1u64 << square;
+    pub fn set_square(&mut self, square: Square) {
+        let bit:u64 = 1u64 << square.to_u8();
         self.set(bit);
     }
 
@@ -68,8 +83,8 @@ impl Bitboard {
     pub fn clear(&mut self, bit: u64) {
         self.0 &= !bit;
     }
-    pub fn clear_square(&mut self, square: u8) {
-        let bit:u64 = 1u64 << square;
+    pub fn clear_square(&mut self, square: Square) {
+        let bit:u64 = 1u64 << square.to_u8();
         self.clear(bit);
     }
 
@@ -81,9 +96,8 @@ impl Bitboard {
             Bitboard(self.0 >> 8)
         }
     }
-    pub fn create_from_square(square: u8) -> Bitboard {
-        let bit:u64=(1u64<<square) ;
-        Bitboard(bit)
+    pub fn create_from_square(square: Square) -> Bitboard {
+        Bitboard(1u64 << square.to_u8())
     }
     /// Perform a double pawn push, ensuring there are no blockers.
     pub fn pawn_double_push(self, color: &PieceColor, blockers: Bitboard) -> Bitboard {
@@ -94,8 +108,8 @@ impl Bitboard {
             .pawn_push(color)
             .bitand(!blockers)
     }
-    pub fn get_single_set_bit(self) -> u8 {
-          self.0.trailing_zeros() as u8
+    pub fn get_single_set_bit(self) -> Square {
+          Square::new(self.0.trailing_zeros() as u8)
     }
     pub fn pop_count(self) -> u8 {
         self.0.count_ones() as u8
@@ -110,44 +124,211 @@ impl Bitboard {
             (PieceColor::BLACK,true)=>!H_FILE,
             (PieceColor::BLACK,false)=>!A_FILE,
         };
-        let attacks=match(color,attack_left){
+        match(color,attack_left){
             (PieceColor::WHITE,true)=>(pawn_mask << 7) & opponent,
             (PieceColor::WHITE,false)=>(pawn_mask << 9) & opponent,
             (PieceColor::BLACK,true)=>(pawn_mask >> 7) & opponent,
             (PieceColor::BLACK,false)=>(pawn_mask >> 9) & opponent,
-        };
-        attacks
+        }
     }
 
-    pub fn pop_lsb(&mut self) -> u8 {
+    pub fn pop_lsb(&mut self) -> Square {
         let lsb = self.0 & (!self.0 + 1);
         self.0 ^= lsb;
-        lsb.trailing_zeros() as u8
-    }
-    pub fn bitboard_to_set_vec(&self) -> Vec<u8> {
-        let mut set_vec = Vec::new();
-        let bitboard = self.0;
-        for i in 0..64 {
-            if bitboard >> i & 1 == 1 {
-                set_vec.push(i);
-            }
-        }
-        set_vec
+        Square::new(lsb.trailing_zeros() as u8)
     }
-    pub fn contains_square(&self, square: u8) -> bool {
-        self.0 & (1u64 << square) != 0
+    pub fn bitboard_to_set_vec(&self) -> Vec<Square> {
+        self.iter().collect()
+    }
+    pub fn contains_square(&self, square: Square) -> bool {
+        self.0 & (1u64 << square.to_u8()) != 0
     }
 
     pub fn get_bitboard(self) -> u64 {
         self.0
     }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over the set squares from a1 to h8.
+    pub fn iter(self) -> BitboardIter {
+        BitboardIter(self.0)
+    }
+
+    /// Lowest set square, `None` if the bitboard is empty.
+    pub fn lsb(self) -> Option<Square> {
+        (self.0 != 0).then(|| Square::new(self.0.trailing_zeros() as u8))
+    }
+
+    /// Highest set square, `None` if the bitboard is empty.
+    pub fn msb(self) -> Option<Square> {
+        (self.0 != 0).then(|| Square::new(63 - self.0.leading_zeros() as u8))
+    }
+
+    /// Returns true if at least two squares are set.
+    pub fn more_than_one(self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    /// Shifts every square one step in `direction`, dropping squares that
+    /// would wrap around the board edge.
+    pub fn shift(self, direction: Direction) -> Bitboard {
+        match direction {
+            Direction::North => Bitboard(self.0 << 8),
+            Direction::South => Bitboard(self.0 >> 8),
+            Direction::East => Bitboard((self & !H_FILE).0 << 1),
+            Direction::West => Bitboard((self & !A_FILE).0 >> 1),
+            Direction::NorthEast => Bitboard((self & !H_FILE).0 << 9),
+            Direction::NorthWest => Bitboard((self & !A_FILE).0 << 7),
+            Direction::SouthEast => Bitboard((self & !H_FILE).0 >> 7),
+            Direction::SouthWest => Bitboard((self & !A_FILE).0 >> 9),
+        }
+    }
+    pub fn north(self) -> Bitboard {
+        self.shift(Direction::North)
+    }
+    pub fn south(self) -> Bitboard {
+        self.shift(Direction::South)
+    }
+    pub fn east(self) -> Bitboard {
+        self.shift(Direction::East)
+    }
+    pub fn west(self) -> Bitboard {
+        self.shift(Direction::West)
+    }
+    pub fn north_east(self) -> Bitboard {
+        self.shift(Direction::NorthEast)
+    }
+    pub fn north_west(self) -> Bitboard {
+        self.shift(Direction::NorthWest)
+    }
+    pub fn south_east(self) -> Bitboard {
+        self.shift(Direction::SouthEast)
+    }
+    pub fn south_west(self) -> Bitboard {
+        self.shift(Direction::SouthWest)
+    }
+
+    /// Every set square together with all squares north of it on its file.
+    pub fn north_fill(self) -> Bitboard {
+        let mut bits = self.0;
+        bits |= bits << 8;
+        bits |= bits << 16;
+        bits |= bits << 32;
+        Bitboard(bits)
+    }
+    /// Every set square together with all squares south of it on its file.
+    pub fn south_fill(self) -> Bitboard {
+        let mut bits = self.0;
+        bits |= bits >> 8;
+        bits |= bits >> 16;
+        bits |= bits >> 32;
+        Bitboard(bits)
+    }
+    /// The whole files of the set squares.
+    pub fn file_fill(self) -> Bitboard {
+        self.north_fill() | self.south_fill()
+    }
+    /// Fill towards the promotion rank of `color`.
+    pub fn forward_fill(self, color: PieceColor) -> Bitboard {
+        match color {
+            PieceColor::WHITE => self.north_fill(),
+            PieceColor::BLACK => self.south_fill(),
+        }
+    }
+    /// Squares attacked by pawns of `color` standing on the set squares.
+    pub fn pawn_attacks(self, color: PieceColor) -> Bitboard {
+        match color {
+            PieceColor::WHITE => self.north_east() | self.north_west(),
+            PieceColor::BLACK => self.south_east() | self.south_west(),
+        }
+    }
+
+    /// Mask of the whole file.
+    pub fn file_mask(file: File) -> Bitboard {
+        Bitboard(A_FILE.0 << file.index())
+    }
+    /// Mask of the whole rank.
+    pub fn rank_mask(rank: Rank) -> Bitboard {
+        Bitboard(RANK_1.0 << (8 * rank.index()))
+    }
+    /// Mask of the file containing `square`.
+    pub fn file_of(square: Square) -> Bitboard {
+        Bitboard::file_mask(square.file())
+    }
+    /// Mask of the rank containing `square`.
+    pub fn rank_of(square: Square) -> Bitboard {
+        Bitboard::rank_mask(square.rank())
+    }
+
+    /// Squares strictly between `a` and `b`, empty if they are not on a common line.
+    pub fn between(a: Square, b: Square) -> Bitboard {
+        BETWEEN[a][b]
+    }
+    /// The full rank, file or diagonal through `a` and `b`, empty if they are not aligned.
+    pub fn line(a: Square, b: Square) -> Bitboard {
+        LINE[a][b]
+    }
+}
+
+/// Iterator over the set squares of a bitboard, lowest first.
+pub struct BitboardIter(u64);
+
+impl Iterator for BitboardIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = Square::new(self.0.trailing_zeros() as u8);
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for BitboardIter {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = BitboardIter;
+
+    fn into_iter(self) -> BitboardIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(squares: I) -> Self {
+        let mut bitboard = Bitboard::new(0);
+        for square in squares {
+            bitboard.set_square(square);
+        }
+        bitboard
+    }
 }
 
 use std::fmt;
 use std::ops::Shl;
 
+/// Prints the board from white's side, rank 8 at the top.
 impl fmt::Display for Bitboard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        for rank in Rank::ALL.into_iter().rev() {
+            write!(f, "{} ", rank)?;
+            for file in File::ALL {
+                let c = if self.contains_square(Square::from_file_rank(file, rank)) { '1' } else { '.' };
+                write!(f, " {}", c)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "   a b c d e f g h")
     }
-}
\ No newline at end of file
+}
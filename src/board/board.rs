@@ -1,12 +1,45 @@
-use crate::board::castling::types::{AllowedCastling, CastlingSide};
+use std::fmt;
+use std::sync::Arc;
+use crate::board::castling::types::{AllowedCastling, CastlingConfig};
 use crate::board::piece::PieceType;
+use crate::eval::nnue::{Network, NnueState};
 use crate::movegen::movedata::MoveData;
+use super::square::{File, Rank, Square};
+use super::zobrist::ZOBRIST;
 use super::{
     bitboard::Bitboard,
     gamestate::GameState,
     piece::{Piece, PieceColor},
 };
 
+/// Reason a FEN string could not be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FenError {
+    MissingFields,
+    InvalidPlacement,
+    InvalidPiece(char),
+    InvalidColor,
+    InvalidCastling,
+    InvalidEnPassant,
+    InvalidClock,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingFields => write!(f, "insufficient parts"),
+            FenError::InvalidPlacement => write!(f, "piece placement does not describe 8 ranks of 8 squares"),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{}'", c),
+            FenError::InvalidColor => write!(f, "invalid active color"),
+            FenError::InvalidCastling => write!(f, "invalid castling field"),
+            FenError::InvalidEnPassant => write!(f, "invalid en passant field"),
+            FenError::InvalidClock => write!(f, "invalid move clock"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
 #[derive( Clone)]
 pub struct Board {
     pub squares: [Option<Piece>; 64],
@@ -15,96 +48,193 @@ pub struct Board {
      piece_bitboards: [[Bitboard; 6]; 2],
      all_pieces_bitboard: Bitboard,
     pub game_state: GameState,
+    pub castling_config: CastlingConfig,
+    /// Whether castling is written as king takes rook, set by the UCI
+    /// option or a Shredder-FEN castling field
+    pub chess960: bool,
     pub is_check: bool,
     pub is_double_check: bool,
     pub attacked_square:Bitboard,
-    pub curr_king:u8,
+    pub curr_king:Square,
     pub check_ray:Bitboard,
     pub pinned_ray:Bitboard,
-    history:Vec<GameState>
+    history:Vec<GameState>,
+    hash: u64,
+    pawn_hash: u64,
+    nnue: Option<NnueState>,
 }
 
 impl Board {
 
 
-    fn remove_piece(&mut self, square: u8, piece: Piece) {
-        self.squares[square as usize] = None;
+    fn remove_piece(&mut self, square: Square, piece: Piece) {
+        self.squares[square] = None;
         self.get_color_bitboard_mut(piece.piece_color).clear_square(square);
         self.get_piece_bitboard_mut(piece.piece_color, piece.piece_type).clear_square(square);
         self.all_pieces_bitboard.clear_square(square);
+        self.hash ^= ZOBRIST.piece(piece, square);
+        if piece.piece_type == PieceType::PAWN {
+            self.pawn_hash ^= ZOBRIST.piece(piece, square);
+        }
+        self.update_nnue(piece, square, false);
     }
-    fn add_piece(&mut self, square: u8, piece: Piece) {
+    fn add_piece(&mut self, square: Square, piece: Piece) {
       
 
-        self.squares[square as usize] = Some(piece);
+        self.squares[square] = Some(piece);
         self.get_color_bitboard_mut(piece.piece_color).set_square(square);
         self.get_piece_bitboard_mut(piece.piece_color, piece.piece_type).set_square(square);
         self.all_pieces_bitboard.set_square(square);
+        self.hash ^= ZOBRIST.piece(piece, square);
+        if piece.piece_type == PieceType::PAWN {
+            self.pawn_hash ^= ZOBRIST.piece(piece, square);
+        }
+        self.update_nnue(piece, square, true);
+    }
+
+    fn update_nnue(&mut self, piece: Piece, square: Square, added: bool) {
+        if let Some(nnue) = &mut self.nnue {
+            let kings = [self.piece_bitboards[0][PieceType::KING as usize], self.piece_bitboards[1][PieceType::KING as usize]];
+            nnue.update(piece, square, kings, added);
+        }
+    }
+
+    /// Evaluates with `network` from now on, its accumulators following the
+    /// moves made, or stops with `None`.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| NnueState::new(network, self));
+    }
+
+    pub fn nnue(&self) -> Option<&NnueState> {
+        self.nnue.as_ref()
     }
 
 
+    fn empty(turn: PieceColor, game_state: GameState) -> Self {
+        Board {
+            squares: [None; 64],
+            turn,
+            color_bitboards: [Bitboard::new(0), Bitboard::new(0)],
+            piece_bitboards: [[Bitboard::new(0); 6]; 2],
+            all_pieces_bitboard: Bitboard::new(0),
+            game_state,
+            castling_config: CastlingConfig::STANDARD,
+            chess960: false,
+            is_check: false,
+            is_double_check: false,
+            attacked_square: Bitboard::new(0),
+            curr_king: Square::A1,
+            check_ray: Bitboard::new(u64::MAX),
+            pinned_ray: Bitboard::new(0),
+            history:Vec::new(),
+            hash: 0,
+            pawn_hash: 0,
+            nnue: None,
+        }
+    }
+
+    /// Builds a position from a list of pieces, without castling rights or
+    /// en passant square. Like `try_from_fen`, legality is not checked.
+    pub fn from_pieces(pieces: &[(Piece, Square)], turn: PieceColor) -> Self {
+        let game_state = GameState::new(AllowedCastling::None, AllowedCastling::None, 0, 1, None, None);
+        let mut board = Board::empty(turn, game_state);
+        for (piece, square) in pieces {
+            board.add_piece(*square, *piece);
+        }
+        board.hash ^= ZOBRIST.game_state(&board.game_state);
+        if turn == PieceColor::BLACK {
+            board.hash ^= ZOBRIST.side_to_move();
+        }
+        board.update_is_check();
+        board
+    }
+
+    /// Parses a FEN string, panicking if it is malformed.
     pub fn from_fen(fen: &str) -> Self {
+        Board::try_from_fen(fen).unwrap_or_else(|e| panic!("Invalid FEN string: {}", e))
+    }
+
+    /// Parses a FEN string of standard chess. The resulting position is not
+    /// checked for legality, see `validate`.
+    pub fn try_from_fen(fen: &str) -> Result<Self, FenError> {
+        Board::try_parse_fen(fen, false)
+    }
+
+    /// Parses a FEN string, in Chess960 mode if `chess960` is set. In that
+    /// mode `KQkq` name the outermost rook on each side of the king, as in
+    /// X-FEN; otherwise they name the standard squares. Shredder-FEN rook
+    /// files always select Chess960 mode.
+    pub fn try_parse_fen(fen: &str, chess960: bool) -> Result<Self, FenError> {
         let parts: Vec<&str> = fen.split_whitespace().collect();
 
         // Validate that the FEN has the minimum required parts
         if parts.len() < 6 {
-            panic!("Invalid FEN string: insufficient parts");
+            return Err(FenError::MissingFields);
         }
 
         // Parse piece placement string (first field of FEN)
         let piece_placement = parts[0];
         let active_color = parts[1]; // Second field (active color)
+        let castling_field = parts[2];
         let game_state_fen = parts[2..].join(" "); // Remaining fields (castling, en passant, clocks)
 
-        let mut squares = [None; 64];
-        let mut board = Board {
-            squares,
-            turn: if active_color == "w" { PieceColor::WHITE } else { PieceColor::BLACK },
-            color_bitboards: [Bitboard::new(0), Bitboard::new(0)],
-            piece_bitboards: [[Bitboard::new(0); 6]; 2],
-            all_pieces_bitboard: Bitboard::new(0),
-            game_state: GameState::from_fen(&game_state_fen),
-            is_check: false,
-            is_double_check: false,
-            attacked_square: Bitboard::new(0),
-            curr_king: 0,
-            check_ray: Bitboard::new(u64::MAX),
-            pinned_ray: Bitboard::new(0),
-            history:Vec::new()
+        let turn = match active_color {
+            "w" => PieceColor::WHITE,
+            "b" => PieceColor::BLACK,
+            _ => return Err(FenError::InvalidColor),
         };
+        if !castling_field.chars().all(|c| c == '-' || matches!(c.to_ascii_uppercase(), 'K' | 'Q' | 'A'..='H')) {
+            return Err(FenError::InvalidCastling);
+        }
 
-        let mut rank = 7;
-        let mut file = 0;
+        let mut board = Board::empty(turn, GameState::from_fen(&game_state_fen, &CastlingConfig::STANDARD)?);
+
+        let ranks: Vec<&str> = piece_placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPlacement);
+        }
 
         // Parse piece placement into the board squares
-        for c in piece_placement.chars() {
-            match c {
-                '/' => {
-                    rank -= 1;
-                    file = 0;
-                }
-                '1'..='8' => {
-                    file += c.to_digit(10).unwrap() as usize;
-                }
-                _ => {
-                    if let Some(piece) = Piece::from_fen(&c.to_string()) {
-                        board.add_piece((rank * 8 + file) as u8, piece);
+        for (rank, rank_str) in Rank::ALL.into_iter().rev().zip(ranks) {
+            let mut file = 0;
+            for c in rank_str.chars() {
+                match c {
+                    '1'..='8' => file += c as u8 - b'0',
+                    _ => {
+                        let piece = Piece::from_fen(&c.to_string()).ok_or(FenError::InvalidPiece(c))?;
+                        let square_file = File::from_index(file).ok_or(FenError::InvalidPlacement)?;
+                        board.add_piece(Square::from_file_rank(square_file, rank), piece);
+                        file += 1;
                     }
-                    file += 1;
                 }
+                if file > 8 {
+                    return Err(FenError::InvalidPlacement);
+                }
+            }
+            if file != 8 {
+                return Err(FenError::InvalidPlacement);
             }
         }
+        // Castling rights in X-FEN and Shredder-FEN depend on the piece placement
+        board.chess960 = chess960 || castling_field.chars().any(|c| File::from_char(c).is_some());
+        board.castling_config = CastlingConfig::from_fen(castling_field, &board.squares, board.chess960);
+        board.game_state = GameState::from_fen(&game_state_fen, &board.castling_config)?;
+        board.hash ^= ZOBRIST.game_state(&board.game_state);
+        if board.turn == PieceColor::BLACK {
+            board.hash ^= ZOBRIST.side_to_move();
+        }
+        board.update_is_check();
 
-        board
+        Ok(board)
     }
 
     pub fn to_fen(&self) -> String {
         let mut fen = String::new();
 
-        for rank in (0..8).rev() {
+        for rank in Rank::ALL.into_iter().rev() {
             let mut empty_count = 0;
-            for file in 0..8 {
-                if let Some(piece) = self.squares[rank * 8 + file] {
+            for file in File::ALL {
+                if let Some(piece) = self.squares[Square::from_file_rank(file, rank)] {
                     if empty_count > 0 {
                         fen.push_str(&empty_count.to_string());
                         empty_count = 0;
@@ -117,7 +247,7 @@ impl Board {
             if empty_count > 0 {
                 fen.push_str(&empty_count.to_string());
             }
-            if rank > 0 {
+            if rank != Rank::First {
                 fen.push('/');
             }
         }
@@ -128,71 +258,84 @@ impl Board {
             "b"
         };
 
-        fen.push_str(&format!(" {} {}", active_color, self.game_state.to_fen()));
+        fen.push_str(&format!(" {} {}", active_color, self.game_state.to_fen(&self.castling_config)));
         fen
     }
     pub fn make_move(&mut self, mv:&MoveData)
     {
         let mut new_game_state =self.game_state;
         let moved_piece = mv.piece_to_move;
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push();
+        }
         if mv.is_capture() {
             self.remove_piece(mv.get_capture_square().unwrap(),mv.get_captured_piece().unwrap());
             self.disallow_castling_if_needed(mv.get_capture_square().unwrap(), mv.get_captured_piece().unwrap(), &mut new_game_state);
 
         } 
-        if(mv.is_promotion()){
+        if mv.is_promotion() {
             self.remove_piece(mv.from,moved_piece);
             self.add_piece(mv.to,mv.get_promoted_piece().unwrap());
         }
-        else{
-            self.remove_piece(mv.from,moved_piece);
-            self.add_piece(mv.to,moved_piece);
-        }
-        if mv.is_castling(){
+        else if mv.is_castling(){
+            // Lift both pieces first, in Chess960 either may start on the other's destination
             let rook_start = mv.get_rook_start().unwrap();
             let rook_end = mv.get_rook_end().unwrap();
-            let rook = self.squares[rook_start as usize].unwrap();
+            let rook = self.squares[rook_start].unwrap();
+            self.remove_piece(mv.from,moved_piece);
             self.remove_piece(rook_start,rook);
+            self.add_piece(mv.to,moved_piece);
             self.add_piece(rook_end,rook);
             new_game_state.disallow_castling_both(moved_piece.piece_color);
-            
-            
         }
-        if(moved_piece.piece_type==PieceType::KING)
+        else{
+            self.remove_piece(mv.from,moved_piece);
+            self.add_piece(mv.to,moved_piece);
+        }
+        if moved_piece.piece_type==PieceType::KING
         {
             new_game_state.disallow_castling_both(moved_piece.piece_color);
          
         }
        self.disallow_castling_if_needed(mv.from, moved_piece, &mut new_game_state);
-        if (moved_piece.piece_type == PieceType::PAWN && mv.is_double_push()) {
-                let new_en_passent_square= if moved_piece.piece_color == PieceColor::WHITE {
-                    mv.to - 8
-                } else {
-                    mv.to + 8
-                };
-                new_game_state.en_passant_file = Some(mv.to % 8);
+        if moved_piece.piece_type == PieceType::PAWN && mv.is_double_push() {
+                let new_en_passent_square = mv.from.forward(moved_piece.piece_color).unwrap();
+                new_game_state.en_passant_file = Some(mv.to.file());
                 new_game_state.en_passant_square = Some(new_en_passent_square );
             } else {
                 new_game_state.en_passant_file = None;
                 new_game_state.en_passant_square = None;
             }
+        new_game_state.advance_clocks(mv.is_capture() || moved_piece.piece_type == PieceType::PAWN, moved_piece.piece_color);
 
-        
-        
         self.turn = self.turn.opposite();
+        self.hash ^= ZOBRIST.game_state(&self.game_state) ^ ZOBRIST.game_state(&new_game_state) ^ ZOBRIST.side_to_move();
         self.history.push(self.game_state);
         self.game_state = new_game_state;
-        
-        
-        
+        if let Some(mut nnue) = self.nnue.take() {
+            nnue.refresh_stale(self);
+            self.nnue = Some(nnue);
+        }
+        debug_assert_eq!(self.validate(), Ok(()), "after make_move {}", mv.to_uci(self.chess960));
     }
 
     pub fn unmake_move(&mut self, mv: &MoveData) {
         let moved_piece = mv.piece_to_move;
+        // The previous accumulators are restored as a whole
+        let nnue = self.nnue.take();
         if mv.is_promotion() {
             self.remove_piece(mv.to, mv.get_promoted_piece().unwrap());
             self.add_piece(mv.from, moved_piece);
         }
+        else if mv.is_castling() {
+            let rook_start = mv.get_rook_start().unwrap();
+            let rook_end = mv.get_rook_end().unwrap();
+            let rook = self.squares[rook_end].unwrap();
+            self.remove_piece(mv.to, moved_piece);
+            self.remove_piece(rook_end, rook);
+            self.add_piece(mv.from, moved_piece);
+            self.add_piece(rook_start, rook);
+        }
         else {
             // Restore the piece to its original position
             self.remove_piece(mv.to, moved_piece);
@@ -204,51 +347,70 @@ impl Board {
             self.add_piece(mv.get_capture_square().unwrap(), mv.get_captured_piece().unwrap());
         }
 
-        // Handle promotion
+        // Restore game state
+        let previous_state = self.history.pop().unwrap();
+        self.hash ^= ZOBRIST.game_state(&self.game_state) ^ ZOBRIST.game_state(&previous_state) ^ ZOBRIST.side_to_move();
+        self.game_state = previous_state;
+        self.turn = self.turn.opposite();
+        self.nnue = nnue.map(|mut nnue| {
+            nnue.pop();
+            nnue
+        });
+        debug_assert_eq!(self.validate(), Ok(()), "after unmake_move {}", mv.to_uci(self.chess960));
+    }
 
+    /// Whether the side to move is in check, worked out from the attacks.
+    /// `is_check` is only refreshed by move generation, so it is stale
+    /// right after `make_move` and `unmake_move`.
+    pub fn in_check(&self) -> bool {
+        let king = self.get_piece_bitboard(self.turn, PieceType::KING);
+        !king.is_empty() && self.is_square_attacked_by(king.get_single_set_bit(), self.turn.opposite())
+    }
 
-        // Handle castling
-        if mv.is_castling() {
-            let rook_start = mv.get_rook_start().unwrap();
-            let rook_end = mv.get_rook_end().unwrap();
-            let rook = self.squares[rook_end as usize].unwrap();
-            self.remove_piece(rook_end, rook);
-            self.add_piece(rook_start, rook);
+    fn update_is_check(&mut self) {
+        self.is_check = self.in_check();
+    }
 
+    /// Passes the turn without moving. Returns false, leaving the board
+    /// unchanged, when in check. `is_check` must be up to date, i.e. moves
+    /// were generated for the current position.
+    pub fn make_null_move(&mut self) -> bool {
+        if self.is_check {
+            return false;
         }
+        let mut new_game_state = self.game_state;
+        new_game_state.en_passant_file = None;
+        new_game_state.en_passant_square = None;
 
-        // Restore game state
-        self.game_state = self.history.pop().unwrap();
+        self.turn = self.turn.opposite();
+        self.hash ^= ZOBRIST.game_state(&self.game_state) ^ ZOBRIST.game_state(&new_game_state) ^ ZOBRIST.side_to_move();
+        self.history.push(self.game_state);
+        self.game_state = new_game_state;
+        true
+    }
+
+    pub fn unmake_null_move(&mut self) {
+        let previous_state = self.history.pop().unwrap();
+        self.hash ^= ZOBRIST.game_state(&self.game_state) ^ ZOBRIST.game_state(&previous_state) ^ ZOBRIST.side_to_move();
+        self.game_state = previous_state;
         self.turn = self.turn.opposite();
     }
 
-    fn disallow_castling_if_needed(&mut self, square: u8, piece: Piece, game_state: &mut GameState) {
+    fn disallow_castling_if_needed(&mut self, square: Square, piece: Piece, game_state: &mut GameState) {
         if piece.piece_type != PieceType::ROOK {
             return;
         }
-        match (square, piece.piece_color) {
-            (0, PieceColor::WHITE) if game_state.castle_white.is_allowed(&CastlingSide::Queenside) => {
-                game_state.disallow_castling(AllowedCastling::from(CastlingSide::Queenside), piece.piece_color);
-            }
-            (7, PieceColor::WHITE) if game_state.castle_white.is_allowed(&CastlingSide::Kingside) => {
-                game_state.disallow_castling(AllowedCastling::from(CastlingSide::Kingside), piece.piece_color);
-            }
-            (56, PieceColor::BLACK) if game_state.castle_black.is_allowed(&CastlingSide::Queenside) => {
-                game_state.disallow_castling(AllowedCastling::from(CastlingSide::Queenside), piece.piece_color);
-            }
-            (63, PieceColor::BLACK) if game_state.castle_black.is_allowed(&CastlingSide::Kingside) => {
-                game_state.disallow_castling(AllowedCastling::from(CastlingSide::Kingside), piece.piece_color);
-            }
-            _ => {}
+        if let Some(side) = self.castling_config.side_of_rook(square, piece.piece_color) {
+            game_state.disallow_castling(AllowedCastling::from(side), piece.piece_color);
         }
     }
 
     pub fn to_stockfish_string(&self) -> String {
         let mut stockfish_str = String::new();
 
-        for rank in (0..8).rev() {
-            for file in 0..8 {
-                if let Some(piece) = self.squares[rank * 8 + file] {
+        for rank in Rank::ALL.into_iter().rev() {
+            for file in File::ALL {
+                if let Some(piece) = self.squares[Square::from_file_rank(file, rank)] {
                     stockfish_str.push_str(&piece.to_fen());
                 } else {
                     stockfish_str.push('.');
@@ -257,9 +419,13 @@ impl Board {
             stockfish_str.push('\n');
         }
 
-        stockfish_str.push_str(&self.game_state.to_stockfish_string());
+        stockfish_str.push_str(&self.game_state.to_stockfish_string(&self.castling_config));
         stockfish_str
     }
+    /// The piece standing on `square`, if any.
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.squares[square]
+    }
     pub  fn get_piece_bitboard(&self, color: PieceColor, piece: PieceType) -> Bitboard {
         self.piece_bitboards[color as usize][piece as usize]
     }
@@ -276,4 +442,34 @@ fn get_color_bitboard_mut(&mut self, color: PieceColor) -> &mut Bitboard {
     pub fn get_all_pieces_bitboard(&self) -> Bitboard {
         self.all_pieces_bitboard
     }
+    /// Knights, bishops, rooks and queens of `color`.
+    pub fn non_pawn_pieces(&self, color: PieceColor) -> Bitboard {
+        self.get_color_bitboard(color) & !(self.get_piece_bitboard(color, PieceType::PAWN) | self.get_piece_bitboard(color, PieceType::KING))
+    }
+    /// Zobrist hash of the position, updated incrementally.
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+    /// Recomputes the Zobrist hash from scratch.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = ZOBRIST.game_state(&self.game_state);
+        for square in Square::all() {
+            if let Some(piece) = self.squares[square] {
+                hash ^= ZOBRIST.piece(piece, square);
+            }
+        }
+        if self.turn == PieceColor::BLACK {
+            hash ^= ZOBRIST.side_to_move();
+        }
+        hash
+    }
+    /// Zobrist hash of the pawns alone, updated incrementally.
+    pub fn get_pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+    /// Recomputes the pawn hash from scratch.
+    pub fn compute_pawn_hash(&self) -> u64 {
+        let pawns = self.get_piece_bitboard(PieceColor::WHITE, PieceType::PAWN) | self.get_piece_bitboard(PieceColor::BLACK, PieceType::PAWN);
+        pawns.iter().fold(0, |hash, square| hash ^ ZOBRIST.piece(self.squares[square].unwrap(), square))
+    }
 }
@@ -1,33 +1,21 @@
-use crate::board::bitboard::Bitboard;
+use crate::board::square::Square;
 
-pub const WHITE_KINGSIDE_ROOK_START: u8 = 7; // h1
-pub const WHITE_KINGSIDE_ROOK_END: u8 = 5; // f1
-pub const WHITE_KINGSIDE_KING_START: u8 = 4; // e1
-pub const WHITE_KINGSIDE_KING_END: u8 = 6; // g1;
+pub const WHITE_KINGSIDE_ROOK_START: Square = Square::H1;
+pub const WHITE_KINGSIDE_ROOK_END: Square = Square::F1;
+pub const WHITE_KINGSIDE_KING_END: Square = Square::G1;
 
-pub const WHITE_QUEENSIDE_ROOK_START: u8 = 0; // a1
-pub const WHITE_QUEENSIDE_ROOK_END: u8 = 3; // d1
-pub const WHITE_QUEENSIDE_KING_START: u8 = 4; // e1
-pub const WHITE_QUEENSIDE_KING_END: u8 = 2; // c1;
+pub const WHITE_QUEENSIDE_ROOK_START: Square = Square::A1;
+pub const WHITE_QUEENSIDE_ROOK_END: Square = Square::D1;
+pub const WHITE_QUEENSIDE_KING_END: Square = Square::C1;
 
-pub const BLACK_KINGSIDE_ROOK_START: u8 = 63; // h8
-pub const BLACK_KINGSIDE_ROOK_END: u8 = 61; // f8
-pub const BLACK_KINGSIDE_KING_START: u8 = 60; // e8
-pub const BLACK_KINGSIDE_KING_END: u8 = 62; // g8;
+pub const WHITE_KING_START: Square = Square::E1;
 
-pub const BLACK_QUEENSIDE_ROOK_START: u8 = 56; // a8
-pub const BLACK_QUEENSIDE_ROOK_END: u8 = 59; // d8
-pub const BLACK_QUEENSIDE_KING_START: u8 = 60; // e8
-pub const BLACK_QUEENSIDE_KING_END: u8 = 58; // c8;
-pub const WHITE_KINGSIDE_REQUIRED_EMPTY: Bitboard = Bitboard::new(
-    0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_01100000); // f1, g1
-pub const WHITE_QUEENSIDE_REQUIRED_EMPTY: Bitboard =Bitboard::new(
-    0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00001110); // b1, c1, d1
-pub const BLACK_KINGSIDE_REQUIRED_EMPTY: Bitboard =Bitboard::new(
-    0b01100000_00000000_00000000_00000000_00000000_00000000_00000000_00000000); // f8, g8
-pub const BLACK_QUEENSIDE_REQUIRED_EMPTY: Bitboard = Bitboard::new(
-    0b00001110_00000000_00000000_00000000_00000000_00000000_00000000_00000000); // b8, c8, d8
-pub const WHITE_KINGSIDE_KING_MOVES_TROUGH:Bitboard =Bitboard::new( 112);
-pub const WHITE_QUEENSIDE_KING_MOVES_TROUGH:Bitboard =Bitboard::new( 28);
-pub const BLACK_KINGSIDE_KING_MOVES_TROUGH:Bitboard =Bitboard::new( 8070450532247928832);
-pub const BLACK_QUEENSIDE_KING_MOVES_TROUGH:Bitboard =Bitboard::new( 2017612633061982208);
+pub const BLACK_KINGSIDE_ROOK_START: Square = Square::H8;
+pub const BLACK_KINGSIDE_ROOK_END: Square = Square::F8;
+pub const BLACK_KINGSIDE_KING_END: Square = Square::G8;
+
+pub const BLACK_QUEENSIDE_ROOK_START: Square = Square::A8;
+pub const BLACK_QUEENSIDE_ROOK_END: Square = Square::D8;
+pub const BLACK_QUEENSIDE_KING_END: Square = Square::C8;
+
+pub const BLACK_KING_START: Square = Square::E8;
@@ -1,5 +1,6 @@
 use crate::board::bitboard::Bitboard;
-use crate::board::piece::PieceColor;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::{File, Rank, Square};
 
 use super::constants::*;
 
@@ -9,16 +10,7 @@ pub enum CastlingSide {
     Queenside,
 }
 impl CastlingSide {
-    pub const fn rook_start(&self, color: PieceColor) -> u8 {
-        match (color, self) {
-            (PieceColor::WHITE, CastlingSide::Kingside) => WHITE_KINGSIDE_ROOK_START,
-            (PieceColor::WHITE, CastlingSide::Queenside) => WHITE_QUEENSIDE_ROOK_START,
-            (PieceColor::BLACK, CastlingSide::Kingside) => BLACK_KINGSIDE_ROOK_START,
-            (PieceColor::BLACK, CastlingSide::Queenside) => BLACK_QUEENSIDE_ROOK_START,
-        }
-    }
-
-    pub const fn rook_end(&self, color: PieceColor) -> u8 {
+    pub const fn rook_end(&self, color: PieceColor) -> Square {
         match (color, self) {
             (PieceColor::WHITE, CastlingSide::Kingside) => WHITE_KINGSIDE_ROOK_END,
             (PieceColor::WHITE, CastlingSide::Queenside) => WHITE_QUEENSIDE_ROOK_END,
@@ -27,16 +19,7 @@ impl CastlingSide {
         }
     }
 
-    pub const fn king_start(&self, color: PieceColor) -> u8 {
-        match (color, self) {
-            (PieceColor::WHITE, CastlingSide::Kingside)
-            | (PieceColor::WHITE, CastlingSide::Queenside) => WHITE_KINGSIDE_KING_START,
-            (PieceColor::BLACK, CastlingSide::Kingside)
-            | (PieceColor::BLACK, CastlingSide::Queenside) => BLACK_KINGSIDE_KING_START,
-        }
-    }
-
-    pub const fn king_end(&self, color: PieceColor) -> u8 {
+    pub const fn king_end(&self, color: PieceColor) -> Square {
         match (color, self) {
             (PieceColor::WHITE, CastlingSide::Kingside) => WHITE_KINGSIDE_KING_END,
             (PieceColor::WHITE, CastlingSide::Queenside) => WHITE_QUEENSIDE_KING_END,
@@ -44,24 +27,102 @@ impl CastlingSide {
             (PieceColor::BLACK, CastlingSide::Queenside) => BLACK_QUEENSIDE_KING_END,
         }
     }
+}
 
-    pub const fn required_empty(&self, color: PieceColor) -> Bitboard {
-        match (color, self) {
-            (PieceColor::WHITE, CastlingSide::Kingside) => WHITE_KINGSIDE_REQUIRED_EMPTY,
-            (PieceColor::WHITE, CastlingSide::Queenside) => WHITE_QUEENSIDE_REQUIRED_EMPTY,
-            (PieceColor::BLACK, CastlingSide::Kingside) => BLACK_KINGSIDE_REQUIRED_EMPTY,
-            (PieceColor::BLACK, CastlingSide::Queenside) => BLACK_QUEENSIDE_REQUIRED_EMPTY,
+/// Start squares of the king and castling rooks. Fixed for a game; differs
+/// from the standard setup in Chess960.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CastlingConfig {
+    king_start: [Square; 2],
+    rook_start: [[Square; 2]; 2],
+}
+
+impl CastlingConfig {
+    pub const STANDARD: CastlingConfig = CastlingConfig {
+        king_start: [WHITE_KING_START, BLACK_KING_START],
+        rook_start: [
+            [WHITE_KINGSIDE_ROOK_START, WHITE_QUEENSIDE_ROOK_START],
+            [BLACK_KINGSIDE_ROOK_START, BLACK_QUEENSIDE_ROOK_START],
+        ],
+    };
+
+    /// Reads the king and rook files from a castling field in standard,
+    /// X-FEN or Shredder-FEN notation. Outside Chess960 `KQkq` always mean
+    /// the standard squares. In Chess960 `K`/`Q` select the outermost rook
+    /// on that side of the king, and file letters select the rook on that
+    /// file.
+    pub fn from_fen(fen: &str, squares: &[Option<Piece>; 64], chess960: bool) -> Self {
+        let mut config = CastlingConfig::STANDARD;
+        if !chess960 {
+            return config;
         }
-    }
-    pub const fn king_moves_trough(&self, color: PieceColor) -> Bitboard {
-        match (color, self) {
-            (PieceColor::WHITE, CastlingSide::Kingside) => WHITE_KINGSIDE_KING_MOVES_TROUGH,
-            (PieceColor::WHITE, CastlingSide::Queenside) => WHITE_QUEENSIDE_KING_MOVES_TROUGH,
-            (PieceColor::BLACK, CastlingSide::Kingside) => BLACK_KINGSIDE_KING_MOVES_TROUGH,
-            (PieceColor::BLACK, CastlingSide::Queenside) => BLACK_QUEENSIDE_KING_MOVES_TROUGH,
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            let back_rank = Rank::First.relative(color);
+            let king_file = File::ALL.into_iter().find(|&file| squares[Square::from_file_rank(file, back_rank)] == Some(Piece::new(color, PieceType::KING)));
+            let Some(king_file) = king_file else {
+                continue;
+            };
+            config.king_start[color as usize] = Square::from_file_rank(king_file, back_rank);
+            let is_rook = |file: &File| squares[Square::from_file_rank(*file, back_rank)] == Some(Piece::new(color, PieceType::ROOK));
+
+            for c in fen.chars().filter(|c| is_color_char(*c, color)) {
+                let rook_file = match c {
+                    'K' | 'k' => File::ALL.into_iter().rev().take_while(|file| *file > king_file).find(is_rook),
+                    'Q' | 'q' => File::ALL.into_iter().take_while(|file| *file < king_file).find(is_rook),
+                    _ => File::from_char(c),
+                };
+                if let Some(rook_file) = rook_file {
+                    let side = if rook_file > king_file { CastlingSide::Kingside } else { CastlingSide::Queenside };
+                    config.rook_start[color as usize][side as usize] = Square::from_file_rank(rook_file, back_rank);
+                }
+            }
         }
+        config
+    }
+
+    pub fn king_start(&self, color: PieceColor) -> Square {
+        self.king_start[color as usize]
+    }
+
+    pub fn rook_start(&self, side: CastlingSide, color: PieceColor) -> Square {
+        self.rook_start[color as usize][side as usize]
+    }
+
+    /// Returns the side whose castling rook starts on `square`, if any.
+    pub fn side_of_rook(&self, square: Square, color: PieceColor) -> Option<CastlingSide> {
+        [CastlingSide::Kingside, CastlingSide::Queenside]
+            .into_iter()
+            .find(|side| self.rook_start(*side, color) == square)
+    }
+
+    /// Squares that must be empty apart from the castling king and rook.
+    pub fn required_empty(&self, side: CastlingSide, color: PieceColor) -> Bitboard {
+        let king_start = self.king_start(color);
+        let rook_start = self.rook_start(side, color);
+        let mut empty = rank_span(king_start, side.king_end(color)) | rank_span(rook_start, side.rook_end(color));
+        empty.clear_square(king_start);
+        empty.clear_square(rook_start);
+        empty
+    }
+
+    /// Squares the king stands on or passes through, none may be attacked.
+    pub fn king_moves_trough(&self, side: CastlingSide, color: PieceColor) -> Bitboard {
+        rank_span(self.king_start(color), side.king_end(color))
     }
 }
+
+fn is_color_char(c: char, color: PieceColor) -> bool {
+    match color {
+        PieceColor::WHITE => c.is_ascii_uppercase(),
+        PieceColor::BLACK => c.is_ascii_lowercase(),
+    }
+}
+
+// Squares between `a` and `b` on one rank, both included.
+fn rank_span(a: Square, b: Square) -> Bitboard {
+    Bitboard::between(a, b) | a.bitboard() | b.bitboard()
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AllowedCastling {
     Kingside,
@@ -91,12 +152,12 @@ impl AllowedCastling {
 
     /// Check if castling is allowed.
     pub fn is_allowed(&self, side: &CastlingSide) -> bool {
-        match (self, side) {
-            (AllowedCastling::Kingside, CastlingSide::Kingside) => true,
-            (AllowedCastling::Queenside, CastlingSide::Queenside) => true,
-            (AllowedCastling::Both, _) => true,
-            _ => false,
-        }
+        matches!(
+            (self, side),
+            (AllowedCastling::Kingside, CastlingSide::Kingside)
+                | (AllowedCastling::Queenside, CastlingSide::Queenside)
+                | (AllowedCastling::Both, _)
+        )
     }
 
     pub fn disallow_castling(&self, side: AllowedCastling) -> AllowedCastling {
@@ -112,34 +173,44 @@ impl AllowedCastling {
         }
     }
 
-    pub fn from_fen(fen: &str, color: PieceColor) -> Self {
-        let kingside = match color {
-            PieceColor::WHITE => fen.contains('K'),
-            PieceColor::BLACK => fen.contains('k'),
-        };
-        let queenside = match color {
-            PieceColor::WHITE => fen.contains('Q'),
-            PieceColor::BLACK => fen.contains('q'),
-        };
-
-        match (kingside, queenside) {
-            (true, true) => AllowedCastling::Both,
-            (true, false) => AllowedCastling::Kingside,
-            (false, true) => AllowedCastling::Queenside,
-            (false, false) => AllowedCastling::None,
+    pub fn from_fen(fen: &str, color: PieceColor, config: &CastlingConfig) -> Self {
+        let king_file = config.king_start(color).file();
+        let mut castling = AllowedCastling::None;
+        for c in fen.chars().filter(|c| is_color_char(*c, color)) {
+            let side = match (c.to_ascii_uppercase(), File::from_char(c)) {
+                ('K', _) => CastlingSide::Kingside,
+                ('Q', _) => CastlingSide::Queenside,
+                (_, Some(file)) if file > king_file => CastlingSide::Kingside,
+                (_, Some(_)) => CastlingSide::Queenside,
+                _ => continue,
+            };
+            castling = castling.allow(side);
         }
+        castling
     }
 
-    pub fn to_fen(&self, color: PieceColor) -> String {
-        match (self, color) {
-            (AllowedCastling::Kingside, PieceColor::WHITE) => "K".to_string(),
-            (AllowedCastling::Queenside, PieceColor::WHITE) => "Q".to_string(),
-            (AllowedCastling::Both, PieceColor::WHITE) => "KQ".to_string(),
-            (AllowedCastling::None, PieceColor::WHITE) => "".to_string(),
-            (AllowedCastling::Kingside, PieceColor::BLACK) => "k".to_string(),
-            (AllowedCastling::Queenside, PieceColor::BLACK) => "q".to_string(),
-            (AllowedCastling::Both, PieceColor::BLACK) => "kq".to_string(),
-            (AllowedCastling::None, PieceColor::BLACK) => "".to_string(),
+    /// Writes `K`/`Q` for rooks on their standard squares and the rook file
+    /// (Shredder-FEN) otherwise, so Chess960 positions round-trip.
+    pub fn to_fen(&self, color: PieceColor, config: &CastlingConfig) -> String {
+        let mut fen = String::new();
+        for (side, letter) in [(CastlingSide::Kingside, 'K'), (CastlingSide::Queenside, 'Q')] {
+            if !self.is_allowed(&side) {
+                continue;
+            }
+            let is_standard = config.king_start(color) == CastlingConfig::STANDARD.king_start(color)
+                && config.rook_start(side, color) == CastlingConfig::STANDARD.rook_start(side, color);
+            let c = if is_standard { letter } else { config.rook_start(side, color).file().to_char().to_ascii_uppercase() };
+            fen.push(if color == PieceColor::WHITE { c } else { c.to_ascii_lowercase() });
+        }
+        fen
+    }
+
+    fn allow(&self, side: CastlingSide) -> AllowedCastling {
+        match (self, side) {
+            (AllowedCastling::None, CastlingSide::Kingside) => AllowedCastling::Kingside,
+            (AllowedCastling::None, CastlingSide::Queenside) => AllowedCastling::Queenside,
+            (AllowedCastling::Kingside, CastlingSide::Queenside) | (AllowedCastling::Queenside, CastlingSide::Kingside) => AllowedCastling::Both,
+            _ => *self,
         }
     }
 }
@@ -1,14 +1,15 @@
-use super::{castling::types::AllowedCastling, piece::PieceColor};
-use crate::board::position::Position;
+use super::{castling::types::{AllowedCastling, CastlingConfig}, piece::PieceColor};
+use crate::board::board::FenError;
+use crate::board::square::{File, Square};
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct GameState {
     pub castle_white: AllowedCastling,
     pub castle_black: AllowedCastling,
     pub halfmove_clock: u8,
     pub fullmove_clock: u8,
-    pub en_passant_file: Option<u8>,
-    pub en_passant_square: Option<u8>,
+    pub en_passant_file: Option<File>,
+    pub en_passant_square: Option<Square>,
 }
 impl GameState {
     pub fn new(
@@ -16,8 +17,8 @@ impl GameState {
         castle_black: AllowedCastling,
         halfmove_clock: u8,
         fullmove_clock: u8,
-        en_passant_file: Option<u8>,
-        en_passant_square: Option<u8>,
+        en_passant_file: Option<File>,
+        en_passant_square: Option<Square>,
     ) -> GameState {
         GameState {
             castle_white,
@@ -43,12 +44,21 @@ impl GameState {
         }
     }
 
-    pub fn from_fen(fen: &str) -> Self {
+    /// Counts a move by `mover`: the halfmove clock restarts after a capture
+    /// or pawn move, the fullmove number goes up after black's move.
+    pub fn advance_clocks(&mut self, zeroing: bool, mover: PieceColor) {
+        self.halfmove_clock = if zeroing { 0 } else { self.halfmove_clock.saturating_add(1) };
+        if mover == PieceColor::BLACK {
+            self.fullmove_clock = self.fullmove_clock.saturating_add(1);
+        }
+    }
+
+    pub fn from_fen(fen: &str, castling_config: &CastlingConfig) -> Result<Self, FenError> {
         let parts: Vec<&str> = fen.split_whitespace().collect();
 
         // Ensure correct length of FEN parts
         if parts.len() < 4 {
-            panic!("Invalid FEN string: insufficient parts");
+            return Err(FenError::MissingFields);
         }
 
         let castle_rights = parts[0];
@@ -57,62 +67,49 @@ impl GameState {
         // Parse en passant field safely
         let (en_passant_file, en_passant_square) = if en_passant == "-" {
             (None, None)
-        } else if en_passant.len() == 2 {
-            let file = en_passant.chars().next().unwrap() as u8 - b'a';
-            let rank = en_passant.chars().nth(1).unwrap().to_digit(10).unwrap() as u8 - 1;
-            (Some(file), Some(rank * 8 + file))
         } else {
-            panic!("Invalid en passant field in FEN string");
+            let square: Square = en_passant.parse().map_err(|_| FenError::InvalidEnPassant)?;
+            (Some(square.file()), Some(square))
         };
 
-        GameState {
-            castle_white: AllowedCastling::from_fen(castle_rights, PieceColor::WHITE),
-            castle_black: AllowedCastling::from_fen(castle_rights, PieceColor::BLACK),
-            halfmove_clock: parts[2]
-                .parse()
-                .unwrap_or_else(|_| panic!("Invalid halfmove clock in FEN string")),
-            fullmove_clock: parts[3]
-                .parse()
-                .unwrap_or_else(|_| panic!("Invalid fullmove clock in FEN string")),
+        Ok(GameState {
+            castle_white: AllowedCastling::from_fen(castle_rights, PieceColor::WHITE, castling_config),
+            castle_black: AllowedCastling::from_fen(castle_rights, PieceColor::BLACK, castling_config),
+            halfmove_clock: parts[2].parse().map_err(|_| FenError::InvalidClock)?,
+            fullmove_clock: parts[3].parse().map_err(|_| FenError::InvalidClock)?,
             en_passant_file,
             en_passant_square,
-        }
+        })
     }
-    pub fn to_fen(&self) -> String {
+    pub fn to_fen(&self, castling_config: &CastlingConfig) -> String {
         // Convert GameState to FEN string
         let mut castling_rights = String::new();
-        castling_rights.push_str(&self.castle_white.to_fen(PieceColor::WHITE));
-        castling_rights.push_str(&self.castle_black.to_fen(PieceColor::BLACK));
+        castling_rights.push_str(&self.castle_white.to_fen(PieceColor::WHITE, castling_config));
+        castling_rights.push_str(&self.castle_black.to_fen(PieceColor::BLACK, castling_config));
         if castling_rights.is_empty() {
             castling_rights.push('-');
         }
 
         // Convert en_passant_square to algebraic notation
-        let en_passant = if let Some(sqr) = self.en_passant_square {
-            Position::from_index(sqr as i8)
-                .and_then(|pos| pos.to_chess_notation())
-                .unwrap_or_else(|| "-".to_string())
-        } else {
-            "-".to_string()
-        };
+        let en_passant = self.en_passant_square.map_or("-".to_string(), |sqr| sqr.to_string());
 
         format!(
             "{} {} {} {}",
             castling_rights, en_passant, self.halfmove_clock, self.fullmove_clock,
         )
     }
-    pub fn to_stockfish_string(&self) -> String {
+    pub fn to_stockfish_string(&self, castling_config: &CastlingConfig) -> String {
         // Convert GameState to Stockfish formatted string
         format!(
             "Castle rights: {}{}\nHalfmove clock: {}\nFullmove clock: {}\nEn passant: {}{}",
-            self.castle_white.to_fen(PieceColor::WHITE),
-            self.castle_black.to_fen(PieceColor::BLACK),
+            self.castle_white.to_fen(PieceColor::WHITE, castling_config),
+            self.castle_black.to_fen(PieceColor::BLACK, castling_config),
             self.halfmove_clock,
             self.fullmove_clock,
             self.en_passant_file
-                .map_or("-".to_string(), |f| ((f + b'a') as char).to_string()),
+                .map_or("-".to_string(), |f| f.to_string()),
             self.en_passant_square
-                .map_or("-".to_string(), |s| s.to_string())
+                .map_or("-".to_string(), |s| s.index().to_string())
         )
     }
 }
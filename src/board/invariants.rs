@@ -0,0 +1,145 @@
+use std::fmt;
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::castling::types::CastlingSide;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::{Rank, Square};
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::PAWN, PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN, PieceType::KING];
+
+/// Inconsistency between the redundant parts of the board state.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InvariantError {
+    MailboxMismatch(Square),
+    ColorOverlap(Square),
+    OccupancyMismatch,
+    KingCount(PieceColor, u8),
+    CastlingPiecesMissing(PieceColor, CastlingSide),
+    InvalidEnPassant,
+    HashMismatch,
+    PawnHashMismatch,
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::MailboxMismatch(sqr) => write!(f, "mailbox and bitboards disagree on {}", sqr),
+            InvariantError::ColorOverlap(sqr) => write!(f, "both colour bitboards contain {}", sqr),
+            InvariantError::OccupancyMismatch => write!(f, "occupancy bitboards are not the union of the piece bitboards"),
+            InvariantError::KingCount(color, count) => write!(f, "{} has {} kings", color_name(*color), count),
+            InvariantError::CastlingPiecesMissing(color, side) => {
+                write!(f, "{} may castle {:?} but king or rook left its start square", color_name(*color), side)
+            }
+            InvariantError::InvalidEnPassant => write!(f, "en passant state does not follow a double push"),
+            InvariantError::HashMismatch => write!(f, "incremental hash differs from recomputed hash"),
+            InvariantError::PawnHashMismatch => write!(f, "incremental pawn hash differs from recomputed pawn hash"),
+        }
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
+fn color_name(color: PieceColor) -> &'static str {
+    match color {
+        PieceColor::WHITE => "white",
+        PieceColor::BLACK => "black",
+    }
+}
+
+impl Board {
+    /// Checks that the mailbox, bitboards, castling rights, en passant state
+    /// and hash agree with each other.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        self.validate_bitboards()?;
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            let kings = self.get_piece_bitboard(color, PieceType::KING).pop_count();
+            if kings != 1 {
+                return Err(InvariantError::KingCount(color, kings));
+            }
+        }
+        self.validate_castling()?;
+        self.validate_en_passant()?;
+        if self.get_hash() != self.compute_hash() {
+            return Err(InvariantError::HashMismatch);
+        }
+        if self.get_pawn_hash() != self.compute_pawn_hash() {
+            return Err(InvariantError::PawnHashMismatch);
+        }
+        Ok(())
+    }
+
+    fn validate_bitboards(&self) -> Result<(), InvariantError> {
+        let white = self.get_color_bitboard(PieceColor::WHITE);
+        let black = self.get_color_bitboard(PieceColor::BLACK);
+        if let Some(sqr) = (white & black).lsb() {
+            return Err(InvariantError::ColorOverlap(sqr));
+        }
+        if white | black != self.get_all_pieces_bitboard() {
+            return Err(InvariantError::OccupancyMismatch);
+        }
+        let mut piece_count = 0;
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            let pieces = PIECE_TYPES.iter().fold(Bitboard::new(0), |bb, piece_type| bb | self.get_piece_bitboard(color, *piece_type));
+            if pieces != self.get_color_bitboard(color) {
+                return Err(InvariantError::OccupancyMismatch);
+            }
+            piece_count += PIECE_TYPES.iter().map(|piece_type| self.get_piece_bitboard(color, *piece_type).pop_count()).sum::<u8>();
+        }
+        // With no square in two piece bitboards, each occupied square is in
+        // exactly the one named by the mailbox
+        if piece_count != self.get_all_pieces_bitboard().pop_count() {
+            return Err(InvariantError::OccupancyMismatch);
+        }
+        for sqr in Square::all() {
+            let consistent = match self.piece_at(sqr) {
+                Some(piece) => self.get_piece_bitboard(piece.piece_color, piece.piece_type).contains_square(sqr),
+                None => !self.get_all_pieces_bitboard().contains_square(sqr),
+            };
+            if !consistent {
+                return Err(InvariantError::MailboxMismatch(sqr));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_castling(&self) -> Result<(), InvariantError> {
+        for (color, rights) in [(PieceColor::WHITE, self.game_state.castle_white), (PieceColor::BLACK, self.game_state.castle_black)] {
+            for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+                if !rights.is_allowed(&side) {
+                    continue;
+                }
+                let king_home = self.piece_at(self.castling_config.king_start(color)) == Some(Piece::new(color, PieceType::KING));
+                let rook_home = self.piece_at(self.castling_config.rook_start(side, color)) == Some(Piece::new(color, PieceType::ROOK));
+                if !king_home || !rook_home {
+                    return Err(InvariantError::CastlingPiecesMissing(color, side));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The en passant square lies behind a pawn that just moved two squares:
+    // it and the pawn's start square are empty, the pawn belongs to the side
+    // that just moved.
+    fn validate_en_passant(&self) -> Result<(), InvariantError> {
+        let Some(ep_square) = self.game_state.en_passant_square else {
+            return match self.game_state.en_passant_file {
+                Some(_) => Err(InvariantError::InvalidEnPassant),
+                None => Ok(()),
+            };
+        };
+        let mover = self.turn.opposite();
+        let pawn_square = ep_square.forward(mover);
+        let start_square = ep_square.forward(self.turn);
+        let is_valid = self.game_state.en_passant_file == Some(ep_square.file())
+            && ep_square.rank() == Rank::Sixth.relative(self.turn)
+            && self.piece_at(ep_square).is_none()
+            && start_square.is_some_and(|sqr| self.piece_at(sqr).is_none())
+            && pawn_square.is_some_and(|sqr| self.piece_at(sqr) == Some(Piece::new(mover, PieceType::PAWN)));
+        if is_valid {
+            Ok(())
+        } else {
+            Err(InvariantError::InvalidEnPassant)
+        }
+    }
+}
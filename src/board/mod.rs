@@ -1,6 +1,10 @@
 pub mod bitboard;
+#[allow(clippy::module_inception)]
 pub mod board;
 pub mod castling;
 pub mod gamestate;
+pub mod invariants;
 pub mod piece;
 pub mod position;
+pub mod square;
+pub mod zobrist;
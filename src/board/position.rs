@@ -1,6 +1,7 @@
 use std::ops::{Add, Sub, Mul};
 use std::fmt;
 use num_traits::AsPrimitive;
+use crate::board::square::Square;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Position {
@@ -16,15 +17,13 @@ impl Position {
 
     /// Converts a linear index (0-63) to a `Position`. Returns `None` if invalid square.
     pub fn from_sqr(sqr: i8) -> Option<Self> {
-        if sqr >= 0 && sqr < 64 {
+        if (0..64).contains(&sqr) {
             Some(Position::new(sqr % 8, sqr / 8))
         } else {
             None
         }
     }
 
-    /// Converts a chess notation string (e.g., "e2") to a `Position`. Panics if invalid notation.
-
     /// Converts a linear index (0-63) to a `Position`. Alias for `from_sqr`.
     pub fn from_index(index: i8) -> Option<Self> {
         Position::from_sqr(index)
@@ -39,6 +38,11 @@ impl Position {
         }
     }
 
+    /// Converts a `Position` to a `Square`. Returns `None` if out of bounds.
+    pub fn to_square(&self) -> Option<Square> {
+        self.to_sqr().map(|sqr| Square::new(sqr as u8))
+    }
+
     pub fn from_chess_notation(notation: &str) -> Option<Self> {
         if notation.len() != 2 {
             return None;
@@ -46,7 +50,7 @@ impl Position {
         let chars: Vec<char> = notation.chars().collect();
         let file = chars[0].to_ascii_lowercase();
         let rank = chars[1];
-        if file < 'a' || file > 'h' || rank < '1' || rank > '8' {
+        if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
             return None;
         }
         let x = (file as i8) - b'a' as i8;
@@ -73,6 +77,12 @@ impl Position {
     }
 }
 
+impl From<Square> for Position {
+    fn from(square: Square) -> Self {
+        Position::new(square.file().index() as i8, square.rank().index() as i8)
+    }
+}
+
 impl Add for Position {
     type Output = Self;
 
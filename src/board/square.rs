@@ -0,0 +1,334 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+use crate::board::bitboard::Bitboard;
+use crate::board::piece::PieceColor;
+
+/// A file of the board, `A` is file index 0.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+/// A rank of the board from white's side, `First` is rank index 0.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Rank {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    Eighth,
+}
+
+/// One of the 64 squares, a1 = 0 and h8 = 63.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+/// Error returned when a file, rank or square cannot be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseSquareError;
+
+impl File {
+    pub const ALL: [File; 8] = [File::A, File::B, File::C, File::D, File::E, File::F, File::G, File::H];
+
+    pub fn from_index(index: u8) -> Option<File> {
+        File::ALL.get(index as usize).copied()
+    }
+
+    pub const fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// Mirrors the file, a <-> h.
+    pub fn flip(self) -> File {
+        File::ALL[7 - self as usize]
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self as u8) as char
+    }
+
+    pub fn from_char(c: char) -> Option<File> {
+        match c.to_ascii_lowercase() {
+            c @ 'a'..='h' => File::from_index(c as u8 - b'a'),
+            _ => None,
+        }
+    }
+}
+
+impl Rank {
+    pub const ALL: [Rank; 8] = [
+        Rank::First,
+        Rank::Second,
+        Rank::Third,
+        Rank::Fourth,
+        Rank::Fifth,
+        Rank::Sixth,
+        Rank::Seventh,
+        Rank::Eighth,
+    ];
+
+    pub fn from_index(index: u8) -> Option<Rank> {
+        Rank::ALL.get(index as usize).copied()
+    }
+
+    pub const fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// Mirrors the rank, 1 <-> 8.
+    pub fn flip(self) -> Rank {
+        Rank::ALL[7 - self as usize]
+    }
+
+    /// The rank as seen from `color`'s side, e.g. the seventh rank for black is `Second`.
+    pub fn relative(self, color: PieceColor) -> Rank {
+        match color {
+            PieceColor::WHITE => self,
+            PieceColor::BLACK => self.flip(),
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self as u8) as char
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        match c {
+            '1'..='8' => Rank::from_index(c as u8 - b'1'),
+            _ => None,
+        }
+    }
+}
+
+impl Square {
+    pub const A1: Square = Square(0);
+    pub const B1: Square = Square(1);
+    pub const C1: Square = Square(2);
+    pub const D1: Square = Square(3);
+    pub const E1: Square = Square(4);
+    pub const F1: Square = Square(5);
+    pub const G1: Square = Square(6);
+    pub const H1: Square = Square(7);
+    pub const A2: Square = Square(8);
+    pub const B2: Square = Square(9);
+    pub const C2: Square = Square(10);
+    pub const D2: Square = Square(11);
+    pub const E2: Square = Square(12);
+    pub const F2: Square = Square(13);
+    pub const G2: Square = Square(14);
+    pub const H2: Square = Square(15);
+    pub const A3: Square = Square(16);
+    pub const B3: Square = Square(17);
+    pub const C3: Square = Square(18);
+    pub const D3: Square = Square(19);
+    pub const E3: Square = Square(20);
+    pub const F3: Square = Square(21);
+    pub const G3: Square = Square(22);
+    pub const H3: Square = Square(23);
+    pub const A4: Square = Square(24);
+    pub const B4: Square = Square(25);
+    pub const C4: Square = Square(26);
+    pub const D4: Square = Square(27);
+    pub const E4: Square = Square(28);
+    pub const F4: Square = Square(29);
+    pub const G4: Square = Square(30);
+    pub const H4: Square = Square(31);
+    pub const A5: Square = Square(32);
+    pub const B5: Square = Square(33);
+    pub const C5: Square = Square(34);
+    pub const D5: Square = Square(35);
+    pub const E5: Square = Square(36);
+    pub const F5: Square = Square(37);
+    pub const G5: Square = Square(38);
+    pub const H5: Square = Square(39);
+    pub const A6: Square = Square(40);
+    pub const B6: Square = Square(41);
+    pub const C6: Square = Square(42);
+    pub const D6: Square = Square(43);
+    pub const E6: Square = Square(44);
+    pub const F6: Square = Square(45);
+    pub const G6: Square = Square(46);
+    pub const H6: Square = Square(47);
+    pub const A7: Square = Square(48);
+    pub const B7: Square = Square(49);
+    pub const C7: Square = Square(50);
+    pub const D7: Square = Square(51);
+    pub const E7: Square = Square(52);
+    pub const F7: Square = Square(53);
+    pub const G7: Square = Square(54);
+    pub const H7: Square = Square(55);
+    pub const A8: Square = Square(56);
+    pub const B8: Square = Square(57);
+    pub const C8: Square = Square(58);
+    pub const D8: Square = Square(59);
+    pub const E8: Square = Square(60);
+    pub const F8: Square = Square(61);
+    pub const G8: Square = Square(62);
+    pub const H8: Square = Square(63);
+
+    pub const fn new(index: u8) -> Square {
+        assert!(index < 64, "square index out of range");
+        Square(index)
+    }
+
+    pub const fn from_index(index: u8) -> Option<Square> {
+        if index < 64 {
+            Some(Square(index))
+        } else {
+            None
+        }
+    }
+
+    pub const fn from_file_rank(file: File, rank: Rank) -> Square {
+        Square(rank as u8 * 8 + file as u8)
+    }
+
+    /// All squares from a1 to h8.
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub const fn to_u8(self) -> u8 {
+        self.0
+    }
+
+    pub fn file(self) -> File {
+        File::ALL[(self.0 % 8) as usize]
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank::ALL[(self.0 / 8) as usize]
+    }
+
+    /// The square `file_delta` files and `rank_delta` ranks away, `None` if off the board.
+    pub fn offset(self, file_delta: i8, rank_delta: i8) -> Option<Square> {
+        let file = (self.0 % 8) as i8 + file_delta;
+        let rank = (self.0 / 8) as i8 + rank_delta;
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Some(Square((rank * 8 + file) as u8))
+        } else {
+            None
+        }
+    }
+
+    /// One step towards the opponent's side for `color`.
+    pub fn forward(self, color: PieceColor) -> Option<Square> {
+        self.offset(0, if color == PieceColor::WHITE { 1 } else { -1 })
+    }
+
+    /// Mirrors the square vertically, a1 <-> a8.
+    pub const fn flip_rank(self) -> Square {
+        Square(self.0 ^ 56)
+    }
+
+    /// Mirrors the square horizontally, a1 <-> h1.
+    pub const fn flip_file(self) -> Square {
+        Square(self.0 ^ 7)
+    }
+
+    /// The square as seen from `color`'s side.
+    pub fn relative(self, color: PieceColor) -> Square {
+        match color {
+            PieceColor::WHITE => self,
+            PieceColor::BLACK => self.flip_rank(),
+        }
+    }
+
+    pub fn file_distance(self, other: Square) -> u8 {
+        (self.0 % 8).abs_diff(other.0 % 8)
+    }
+
+    pub fn rank_distance(self, other: Square) -> u8 {
+        (self.0 / 8).abs_diff(other.0 / 8)
+    }
+
+    /// King-move distance between the squares.
+    pub fn distance(self, other: Square) -> u8 {
+        self.file_distance(other).max(self.rank_distance(other))
+    }
+
+    pub fn manhattan_distance(self, other: Square) -> u8 {
+        self.file_distance(other) + self.rank_distance(other)
+    }
+
+    pub fn bitboard(self) -> Bitboard {
+        Bitboard::create_from_square(self)
+    }
+}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file), Some(rank), None) => {
+                let file = File::from_char(file).ok_or(ParseSquareError)?;
+                let rank = Rank::from_char(rank).ok_or(ParseSquareError)?;
+                Ok(Square::from_file_rank(file, rank))
+            }
+            _ => Err(ParseSquareError),
+        }
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl fmt::Debug for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid square")
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+impl<T> Index<Square> for [T; 64] {
+    type Output = T;
+
+    fn index(&self, square: Square) -> &Self::Output {
+        &self[square.index()]
+    }
+}
+
+impl<T> IndexMut<Square> for [T; 64] {
+    fn index_mut(&mut self, square: Square) -> &mut Self::Output {
+        &mut self[square.index()]
+    }
+}
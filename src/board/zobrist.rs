@@ -0,0 +1,85 @@
+use crate::board::castling::types::CastlingSide;
+use crate::board::gamestate::GameState;
+use crate::board::piece::{Piece, PieceColor};
+use crate::board::square::Square;
+
+pub struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    castling: [[u64; 2]; 2],
+    en_passant_file: [u64; 8],
+    side_to_move: u64,
+}
+
+// Built at compile time, so lookups in make_move skip the lazy
+// initialisation check
+pub static ZOBRIST: ZobristKeys = ZobristKeys::generate();
+
+// xorshift64*, with a fixed seed so hashes are reproducible between runs
+const fn next_key(state: &mut u64) -> u64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+}
+
+impl ZobristKeys {
+    const fn generate() -> ZobristKeys {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            castling: [[0; 2]; 2],
+            en_passant_file: [0; 8],
+            side_to_move: 0,
+        };
+        let mut color = 0;
+        while color < 2 {
+            let mut piece = 0;
+            while piece < 6 {
+                let mut square = 0;
+                while square < 64 {
+                    keys.pieces[color][piece][square] = next_key(&mut state);
+                    square += 1;
+                }
+                piece += 1;
+            }
+            color += 1;
+        }
+        let mut color = 0;
+        while color < 2 {
+            keys.castling[color][0] = next_key(&mut state);
+            keys.castling[color][1] = next_key(&mut state);
+            color += 1;
+        }
+        let mut file = 0;
+        while file < 8 {
+            keys.en_passant_file[file] = next_key(&mut state);
+            file += 1;
+        }
+        keys.side_to_move = next_key(&mut state);
+        keys
+    }
+
+    pub fn piece(&self, piece: Piece, square: Square) -> u64 {
+        self.pieces[piece.piece_color as usize][piece.piece_type as usize][square]
+    }
+
+    pub fn side_to_move(&self) -> u64 {
+        self.side_to_move
+    }
+
+    /// Key of the castling rights and en passant file stored in `game_state`.
+    pub fn game_state(&self, game_state: &GameState) -> u64 {
+        let mut key = 0;
+        for (color, rights) in [(PieceColor::WHITE, game_state.castle_white), (PieceColor::BLACK, game_state.castle_black)] {
+            for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+                if rights.is_allowed(&side) {
+                    key ^= self.castling[color as usize][side as usize];
+                }
+            }
+        }
+        if let Some(file) = game_state.en_passant_file {
+            key ^= self.en_passant_file[file.index() as usize];
+        }
+        key
+    }
+}
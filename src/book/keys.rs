@@ -0,0 +1,51 @@
+use crate::board::board::Board;
+use crate::board::castling::types::CastlingSide;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::Square;
+use super::random64::RANDOM64;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+// Polyglot orders pieces black pawn, white pawn, black knight, ... white king
+fn piece_key(piece: Piece, square: Square) -> u64 {
+    let kind = 2 * piece.piece_type as usize + (piece.piece_color == PieceColor::WHITE) as usize;
+    RANDOM64[64 * kind + square.index()]
+}
+
+/// Hash of the position as used to index Polyglot opening books. Unlike the
+/// board's own hash, the en passant file only counts when a pawn of the side
+/// to move stands next to the pawn that just moved two squares.
+pub fn polyglot_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for square in board.get_all_pieces_bitboard() {
+        if let Some(piece) = board.piece_at(square) {
+            key ^= piece_key(piece, square);
+        }
+    }
+
+    // White kingside, white queenside, black kingside, black queenside
+    for (i, allowed) in [board.game_state.castle_white, board.game_state.castle_black].iter().enumerate() {
+        for (j, side) in [CastlingSide::Kingside, CastlingSide::Queenside].iter().enumerate() {
+            if allowed.is_allowed(side) {
+                key ^= RANDOM64[CASTLING_OFFSET + 2 * i + j];
+            }
+        }
+    }
+
+    if let Some(ep_square) = board.game_state.en_passant_square {
+        let own_pawn = Some(Piece::new(board.turn, PieceType::PAWN));
+        let can_capture = ep_square
+            .forward(board.turn.opposite())
+            .is_some_and(|pushed| [-1, 1].iter().any(|df| pushed.offset(*df, 0).is_some_and(|sqr| board.piece_at(sqr) == own_pawn)));
+        if can_capture {
+            key ^= RANDOM64[EN_PASSANT_OFFSET + ep_square.file().index() as usize];
+        }
+    }
+
+    if board.turn == PieceColor::WHITE {
+        key ^= RANDOM64[TURN_OFFSET];
+    }
+    key
+}
@@ -0,0 +1,3 @@
+pub mod keys;
+pub mod polyglot;
+pub mod random64;
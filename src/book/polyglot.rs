@@ -0,0 +1,182 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::board::board::Board;
+use crate::movegen::movedata::MoveData;
+use super::keys::polyglot_key;
+
+const ENTRY_SIZE: u64 = 16;
+const PROMOTION_PIECES: [&str; 5] = ["", "n", "b", "r", "q"];
+
+/// Reason a book file could not be read.
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    InvalidSize(u64),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "{}", e),
+            BookError::InvalidSize(size) => write!(f, "book size {} is not a multiple of {} bytes", size, ENTRY_SIZE),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<io::Error> for BookError {
+    fn from(e: io::Error) -> Self {
+        BookError::Io(e)
+    }
+}
+
+/// How to choose between several book moves for the same position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BookSelection {
+    BestWeight,
+    WeightedRandom,
+}
+
+/// One 16 byte record of a Polyglot book, stored big-endian.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    fn from_bytes(bytes: &[u8; ENTRY_SIZE as usize]) -> BookEntry {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE as usize] {
+        let mut bytes = [0; ENTRY_SIZE as usize];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+
+    /// The move in UCI notation. Castling is encoded as king takes rook
+    /// ("e1h1"), as Polyglot does.
+    pub fn uci(&self) -> String {
+        let square = |bits: u16| format!("{}{}", (b'a' + (bits & 7) as u8) as char, (b'1' + (bits >> 3 & 7) as u8) as char);
+        let promotion = PROMOTION_PIECES.get((self.raw_move >> 12 & 7) as usize).copied().unwrap_or("");
+        format!("{}{}{}", square(self.raw_move >> 6), square(self.raw_move), promotion)
+    }
+
+    /// Finds the generated move this entry stands for, or `None` if it is not
+    /// legal in `board`, which happens on key collisions.
+    pub fn decode_move(&self, board: &mut Board) -> Option<MoveData> {
+        board.parse_uci_move(&self.uci()).ok()
+    }
+}
+
+/// A Polyglot opening book. Entries are sorted by key, so lookups binary
+/// search the file instead of loading it.
+pub struct PolyglotBook {
+    file: File,
+    entry_count: u64,
+    rng_state: u64,
+}
+
+impl PolyglotBook {
+    pub fn open(path: impl AsRef<Path>) -> Result<PolyglotBook, BookError> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        if size % ENTRY_SIZE != 0 {
+            return Err(BookError::InvalidSize(size));
+        }
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Ok(PolyglotBook {
+            file,
+            entry_count: size / ENTRY_SIZE,
+            rng_state: nanos | 1,
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.entry_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry_count == 0
+    }
+
+    /// Fixes the sequence of weighted random picks.
+    pub fn set_seed(&mut self, seed: u64) {
+        // xorshift64, zero is a fixed point
+        self.rng_state = seed.max(1);
+    }
+
+    fn read_entry(&mut self, index: u64) -> io::Result<BookEntry> {
+        let mut bytes = [0; ENTRY_SIZE as usize];
+        self.file.seek(SeekFrom::Start(index * ENTRY_SIZE))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(BookEntry::from_bytes(&bytes))
+    }
+
+    /// All entries stored under `key`, in file order.
+    pub fn entries(&mut self, key: u64) -> io::Result<Vec<BookEntry>> {
+        // Index of the first entry with a key not less than `key`
+        let (mut low, mut high) = (0, self.entry_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.read_entry(mid)?.key < key {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let mut entries = Vec::new();
+        for index in low..self.entry_count {
+            let entry = self.read_entry(index)?;
+            if entry.key != key {
+                break;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Picks a legal book move for `board`. Entries with zero weight are only
+    /// played when every entry for the position has zero weight.
+    pub fn probe(&mut self, board: &mut Board, selection: BookSelection) -> io::Result<Option<MoveData>> {
+        let candidates: Vec<(MoveData, u16)> = self
+            .entries(polyglot_key(board))?
+            .iter()
+            .filter_map(|entry| entry.decode_move(board).map(|mv| (mv, entry.weight)))
+            .collect();
+        let total: u64 = candidates.iter().map(|(_, weight)| *weight as u64).sum();
+        if selection == BookSelection::WeightedRandom && total > 0 {
+            let mut pick = self.next_random() % total;
+            for (mv, weight) in &candidates {
+                if pick < *weight as u64 {
+                    return Ok(Some(*mv));
+                }
+                pick -= *weight as u64;
+            }
+        }
+        // max_by_key keeps the last maximum, prefer the first one in the file
+        Ok(candidates.iter().rev().max_by_key(|(_, weight)| *weight).map(|(mv, _)| *mv))
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        self.rng_state
+    }
+}
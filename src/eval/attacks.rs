@@ -0,0 +1,60 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use crate::movegen::magic::functions::{get_bishop_attacks, get_rook_attacks};
+use crate::movegen::precomputed::{KING_MOVES, KNIGHT_MOVES};
+
+/// Squares attacked by every piece on the board, built once per evaluation
+/// and shared by the terms that need them.
+pub struct AttackMaps {
+    /// Attacks of the piece on each square, empty for pawns and empty squares
+    pub by_square: [Bitboard; 64],
+    /// Indexed by colour and `PieceType`
+    pub by_type: [[Bitboard; 6]; 2],
+    /// Indexed by colour
+    pub all: [Bitboard; 2],
+    /// Squares attacked at least twice, indexed by colour
+    pub twice: [Bitboard; 2],
+}
+
+impl AttackMaps {
+    pub fn new(board: &Board) -> Self {
+        let mut maps = AttackMaps { by_square: [Bitboard::new(0); 64], by_type: [[Bitboard::new(0); 6]; 2], all: [Bitboard::new(0); 2], twice: [Bitboard::new(0); 2] };
+        let occupancy = board.get_all_pieces_bitboard();
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            let side = color as usize;
+            let pawns = board.get_piece_bitboard(color, PieceType::PAWN);
+            // Two pawns can attack the same square
+            maps.add(side, PieceType::PAWN, pawns.pawn_attacks(color));
+            let (left, right) = match color {
+                PieceColor::WHITE => (pawns.north_west(), pawns.north_east()),
+                PieceColor::BLACK => (pawns.south_west(), pawns.south_east()),
+            };
+            maps.twice[side] |= left & right;
+            for piece_type in [PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN, PieceType::KING] {
+                for square in board.get_piece_bitboard(color, piece_type) {
+                    let attacks = match piece_type {
+                        PieceType::KNIGHT => KNIGHT_MOVES[square],
+                        PieceType::BISHOP => get_bishop_attacks(square, occupancy),
+                        PieceType::ROOK => get_rook_attacks(square, occupancy),
+                        PieceType::QUEEN => get_bishop_attacks(square, occupancy) | get_rook_attacks(square, occupancy),
+                        _ => KING_MOVES[square],
+                    };
+                    maps.by_square[square] = attacks;
+                    maps.add(side, piece_type, attacks);
+                }
+            }
+        }
+        maps
+    }
+
+    fn add(&mut self, side: usize, piece_type: PieceType, attacks: Bitboard) {
+        self.twice[side] |= self.all[side] & attacks;
+        self.all[side] |= attacks;
+        self.by_type[side][piece_type as usize] |= attacks;
+    }
+
+    pub fn of(&self, color: PieceColor, piece_type: PieceType) -> Bitboard {
+        self.by_type[color as usize][piece_type as usize]
+    }
+}
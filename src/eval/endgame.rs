@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::{File, Rank, Square};
+use super::params::EvalParams;
+use super::{kpk, KNOWN_WIN, PIECE_VALUES};
+
+/// Scale factor leaving the evaluation unchanged.
+pub const SCALE_NORMAL: i32 = 64;
+pub const SCALE_DRAW: i32 = 0;
+
+const PAWN_VALUE: i32 = PIECE_VALUES[PieceType::PAWN as usize];
+const ROOK_VALUE: i32 = PIECE_VALUES[PieceType::ROOK as usize];
+const QUEEN_VALUE: i32 = PIECE_VALUES[PieceType::QUEEN as usize];
+
+/// Evaluates an ending from the strong side's point of view.
+type EndgameFn = fn(&Board, PieceColor) -> i32;
+
+/// Number of pieces of each type and colour, indexed by colour and
+/// `PieceType`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MaterialSignature([[u8; 6]; 2]);
+
+impl MaterialSignature {
+    pub fn from_board(board: &Board) -> MaterialSignature {
+        let mut counts = [[0; 6]; 2];
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            for (piece_type, count) in counts[color as usize].iter_mut().enumerate() {
+                *count = board.get_piece_bitboard(color, PIECE_TYPES[piece_type]).pop_count();
+            }
+        }
+        MaterialSignature(counts)
+    }
+
+    /// Parses names like `KBNvK`, white's pieces first.
+    pub fn from_name(name: &str) -> Option<MaterialSignature> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0; 6]; 2];
+        for (color, side) in [(PieceColor::WHITE, white), (PieceColor::BLACK, black)] {
+            for c in side.chars() {
+                counts[color as usize][Piece::from_fen(&c.to_string())?.piece_type as usize] += 1;
+            }
+        }
+        Some(MaterialSignature(counts))
+    }
+
+    pub fn mirrored(self) -> MaterialSignature {
+        MaterialSignature([self.0[1], self.0[0]])
+    }
+
+    pub fn count(&self, color: PieceColor, piece_type: PieceType) -> u8 {
+        self.0[color as usize][piece_type as usize]
+    }
+
+    /// Material of the knights, bishops, rooks and queens of `color`.
+    pub fn non_pawn_material(&self, color: PieceColor) -> i32 {
+        (PieceType::KNIGHT as usize..PieceType::KING as usize).map(|t| PIECE_VALUES[t] * self.0[color as usize][t] as i32).sum()
+    }
+
+    /// Number of knights, bishops, rooks and queens of `color`.
+    pub fn non_pawn_count(&self, color: PieceColor) -> u8 {
+        self.0[color as usize][PieceType::KNIGHT as usize..PieceType::KING as usize].iter().sum()
+    }
+
+    fn is_bare_king(&self, color: PieceColor) -> bool {
+        self.0[color as usize][..PieceType::KING as usize].iter().all(|count| *count == 0)
+    }
+}
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::PAWN, PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN, PieceType::KING];
+
+struct Endgame {
+    strong: PieceColor,
+    evaluate: EndgameFn,
+}
+
+static ENDGAMES: LazyLock<HashMap<MaterialSignature, Endgame>> = LazyLock::new(|| {
+    let mut endgames = HashMap::new();
+    let mut add = |name: &str, evaluate: EndgameFn| {
+        let signature = MaterialSignature::from_name(name).unwrap();
+        endgames.insert(signature, Endgame { strong: PieceColor::WHITE, evaluate });
+        endgames.insert(signature.mirrored(), Endgame { strong: PieceColor::BLACK, evaluate });
+    };
+    add("KPvK", kpk::evaluate);
+    add("KBNvK", kbnk);
+    add("KRvKP", krkp);
+    add("KQvKP", kqkp);
+    add("KRvKB", krkb);
+    add("KRvKN", krkn);
+    endgames
+});
+
+/// Score of a specialised ending from white's point of view, `None` if no
+/// evaluator knows the material.
+pub fn evaluate(board: &Board) -> Option<i32> {
+    let signature = MaterialSignature::from_board(board);
+    let (strong, evaluate) = match ENDGAMES.get(&signature) {
+        Some(endgame) => (endgame.strong, endgame.evaluate),
+        None => {
+            let strong = [PieceColor::WHITE, PieceColor::BLACK]
+                .into_iter()
+                .find(|color| signature.is_bare_king(color.opposite()) && signature.non_pawn_material(*color) >= ROOK_VALUE)?;
+            (strong, kxk as EndgameFn)
+        }
+    };
+    let score = evaluate(board, strong);
+    Some(if strong == PieceColor::WHITE { score } else { -score })
+}
+
+/// Factor out of `SCALE_NORMAL` for the evaluation of `strong`, the side
+/// that is ahead, in endings that are harder to win than material says.
+pub fn scale_factor(board: &Board, strong: PieceColor, params: &EvalParams) -> i32 {
+    let signature = MaterialSignature::from_board(board);
+    let weak = strong.opposite();
+    let bishop = |color| board.get_piece_bitboard(color, PieceType::BISHOP);
+    let only_bishop = |color| signature.non_pawn_count(color) == 1 && signature.count(color, PieceType::BISHOP) == 1;
+
+    // Rook pawns with a bishop that does not control the promotion square
+    let pawns = board.get_piece_bitboard(strong, PieceType::PAWN);
+    if only_bishop(strong) && signature.non_pawn_count(weak) == 0 && !pawns.is_empty() {
+        for file in [File::A, File::H] {
+            if pawns.get_bitboard() & !Bitboard::file_mask(file).get_bitboard() == 0 {
+                let queening = Square::from_file_rank(file, Rank::Eighth.relative(strong));
+                let weak_king = king_square(board, weak);
+                if is_dark(queening) != is_dark(bishop(strong).get_single_set_bit()) && weak_king.distance(queening) <= 1 {
+                    return SCALE_DRAW;
+                }
+            }
+        }
+    }
+    if only_bishop(strong) && only_bishop(weak) && is_dark(bishop(strong).get_single_set_bit()) != is_dark(bishop(weak).get_single_set_bit()) {
+        return params.opposite_bishops_scale;
+    }
+    SCALE_NORMAL
+}
+
+fn king_square(board: &Board, color: PieceColor) -> Square {
+    board.get_piece_bitboard(color, PieceType::KING).get_single_set_bit()
+}
+
+fn piece_square(board: &Board, color: PieceColor, piece_type: PieceType) -> Square {
+    board.get_piece_bitboard(color, piece_type).get_single_set_bit()
+}
+
+fn is_dark(square: Square) -> bool {
+    (square.file().index() + square.rank().index()).is_multiple_of(2)
+}
+
+// From 27 in the centre to 90 in the corners
+fn push_to_edge(square: Square) -> i32 {
+    let edge_distance = |i: u8| i.min(7 - i) as i32;
+    let (file, rank) = (edge_distance(square.file().index()), edge_distance(square.rank().index()));
+    90 - (7 * file * file / 2 + 7 * rank * rank / 2)
+}
+
+// From 0 on the a8-h1 diagonal to 7 in the a1 and h8 corners
+fn push_to_corner(square: Square) -> i32 {
+    (7 - square.rank().index() as i32 - square.file().index() as i32).abs()
+}
+
+fn push_close(a: Square, b: Square) -> i32 {
+    140 - 20 * a.distance(b) as i32
+}
+
+fn push_away(a: Square, b: Square) -> i32 {
+    120 - push_close(a, b)
+}
+
+// Mating material against a bare king: drive the king to the edge
+fn kxk(board: &Board, strong: PieceColor) -> i32 {
+    let signature = MaterialSignature::from_board(board);
+    let (strong_king, weak_king) = (king_square(board, strong), king_square(board, strong.opposite()));
+    let mut score = signature.non_pawn_material(strong)
+        + signature.count(strong, PieceType::PAWN) as i32 * PAWN_VALUE
+        + push_to_edge(weak_king)
+        + push_close(strong_king, weak_king);
+    let bishops = board.get_piece_bitboard(strong, PieceType::BISHOP);
+    let bishop_pair = bishops.iter().any(is_dark) && bishops.iter().any(|sq| !is_dark(sq));
+    if signature.count(strong, PieceType::QUEEN) > 0
+        || signature.count(strong, PieceType::ROOK) > 0
+        || bishop_pair
+        || (signature.count(strong, PieceType::BISHOP) > 0 && signature.count(strong, PieceType::KNIGHT) > 0)
+    {
+        score += KNOWN_WIN;
+    }
+    score
+}
+
+// Mate is only possible in the corners of the bishop's colour
+fn kbnk(board: &Board, strong: PieceColor) -> i32 {
+    let (strong_king, weak_king) = (king_square(board, strong), king_square(board, strong.opposite()));
+    let bishop = piece_square(board, strong, PieceType::BISHOP);
+    let corner_king = if is_dark(bishop) { weak_king } else { weak_king.flip_file() };
+    KNOWN_WIN + 3520 + push_close(strong_king, weak_king) + 420 * push_to_corner(corner_king)
+}
+
+fn krkp(board: &Board, strong: PieceColor) -> i32 {
+    let weak = strong.opposite();
+    let (strong_king, weak_king) = (king_square(board, strong), king_square(board, weak));
+    let rook = piece_square(board, strong, PieceType::ROOK);
+    let pawn = piece_square(board, weak, PieceType::PAWN);
+    let queening = Square::from_file_rank(pawn.file(), Rank::Eighth.relative(weak));
+    let tempo = |color| (board.turn == color) as u8;
+    let in_front = strong_king.file() == pawn.file() && (strong_king.rank().relative(weak) > pawn.rank().relative(weak));
+
+    if in_front || (weak_king.distance(pawn) >= 3 + tempo(weak) && weak_king.distance(rook) >= 3) {
+        // The king stops the pawn, or the defending king is too far away
+        ROOK_VALUE - strong_king.distance(pawn) as i32
+    } else if weak_king.rank().relative(strong) <= Rank::Third
+        && weak_king.distance(pawn) == 1
+        && strong_king.rank().relative(strong) >= Rank::Fourth
+        && strong_king.distance(pawn) > 2 + tempo(strong)
+    {
+        // Advanced pawn supported by its king
+        80 - 8 * strong_king.distance(pawn) as i32
+    } else {
+        let push = pawn.forward(weak).unwrap_or(pawn);
+        200 - 8 * (strong_king.distance(push) as i32 - weak_king.distance(push) as i32 - pawn.distance(queening) as i32)
+    }
+}
+
+// A bishop or rook pawn on the seventh supported by its king often draws
+fn kqkp(board: &Board, strong: PieceColor) -> i32 {
+    let weak = strong.opposite();
+    let (strong_king, weak_king) = (king_square(board, strong), king_square(board, weak));
+    let pawn = piece_square(board, weak, PieceType::PAWN);
+    let mut score = push_close(strong_king, weak_king);
+    if pawn.rank().relative(weak) != Rank::Seventh || weak_king.distance(pawn) != 1 || matches!(pawn.file(), File::B | File::D | File::E | File::G) {
+        score += QUEEN_VALUE - PAWN_VALUE;
+    }
+    score
+}
+
+fn krkb(board: &Board, strong: PieceColor) -> i32 {
+    push_to_edge(king_square(board, strong.opposite()))
+}
+
+fn krkn(board: &Board, strong: PieceColor) -> i32 {
+    let weak = strong.opposite();
+    let weak_king = king_square(board, weak);
+    push_to_edge(weak_king) + push_away(weak_king, piece_square(board, weak, PieceType::KNIGHT))
+}
@@ -0,0 +1,64 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use crate::board::square::{File, Square};
+use crate::movegen::precomputed::KING_MOVES;
+use super::attacks::AttackMaps;
+use super::params::EvalParams;
+use super::Score;
+
+// The danger grows with the square of the attack units, and is only
+// counted once enough pieces join the attack. Neither is in EvalParams:
+// `king_attack_weights` already set the size of the danger, and a divisor
+// stepped to zero would panic; the attackers are a count, not a weight.
+const KING_DANGER_DIVISOR: i32 = 3;
+const MIN_KING_ATTACKERS: u32 = 2;
+
+/// Safety of the king of `color`: pieces attacking the squares around it,
+/// the pawns sheltering it and storming it, and open files next to it.
+pub fn king_safety(board: &Board, color: PieceColor, attacks: &AttackMaps, params: &EvalParams) -> Score {
+    let king = board.get_piece_bitboard(color, PieceType::KING).get_single_set_bit();
+    let mut score = Score::new(shelter(board, color, king, params), 0);
+
+    let zone = KING_MOVES[king] | king.bitboard();
+    let zone = zone | zone.pawn_push(&color);
+    let (mut attackers, mut units) = (0, 0);
+    for piece_type in [PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN] {
+        for square in board.get_piece_bitboard(color.opposite(), piece_type) {
+            let hits = (attacks.by_square[square] & zone).pop_count() as i32;
+            if hits > 0 {
+                attackers += 1;
+                units += params.king_attack_weights[piece_type as usize] * hits;
+            }
+        }
+    }
+    if attackers >= MIN_KING_ATTACKERS {
+        score -= Score::new((units * units / KING_DANGER_DIVISOR).min(params.king_danger_max), units);
+    }
+    score
+}
+
+fn shelter(board: &Board, color: PieceColor, king: Square, params: &EvalParams) -> i32 {
+    let ours = board.get_piece_bitboard(color, PieceType::PAWN);
+    let theirs = board.get_piece_bitboard(color.opposite(), PieceType::PAWN);
+    // Pawns behind the king give no cover
+    let in_front = Bitboard::rank_of(king).forward_fill(color);
+    // Closest to our side of the board
+    let closest = |pawns: Bitboard| match color {
+        PieceColor::WHITE => pawns.lsb(),
+        PieceColor::BLACK => pawns.msb(),
+    };
+    let rank = |square: Option<Square>| square.map_or(0, |square| square.rank().relative(color).index() as usize);
+
+    let center = king.file().index().clamp(File::B.index(), File::G.index());
+    let mut score = 0;
+    for file in (center - 1..=center + 1).filter_map(File::from_index) {
+        let file_mask = Bitboard::file_mask(file);
+        score += params.pawn_shelter[rank(closest(ours & file_mask & in_front))];
+        score += params.pawn_storm[rank(closest(theirs & file_mask & in_front))];
+        if (ours & file_mask).is_empty() {
+            score += if (theirs & file_mask).is_empty() { params.king_open_file } else { params.king_semi_open_file };
+        }
+    }
+    score
+}
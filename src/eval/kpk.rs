@@ -0,0 +1,136 @@
+use std::sync::LazyLock;
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use crate::board::square::Square;
+use crate::movegen::precomputed::{KING_MOVES, PAWN_ATTACKS};
+use super::{KNOWN_WIN, PIECE_VALUES};
+
+// Side to move x black king x white king x pawn on files a-d, ranks 2-7
+const MAX_INDEX: usize = 2 * 64 * 64 * 24;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+/// Win/draw bitbase for king and pawn against king, with the pawn side as
+/// white and the pawn on files a-d. Set bits are wins for white.
+static KPK_BITBASE: LazyLock<Vec<u64>> = LazyLock::new(|| {
+    let mut results: Vec<u8> = (0..MAX_INDEX).map(initial_result).collect();
+    // Propagate results until no unknown position can be decided anymore
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..MAX_INDEX {
+            if results[index] == UNKNOWN {
+                results[index] = classify(&results, index);
+                changed |= results[index] != UNKNOWN;
+            }
+        }
+    }
+    let mut bits = vec![0u64; MAX_INDEX / 64];
+    for (index, result) in results.iter().enumerate() {
+        if *result == WIN {
+            bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bits
+});
+
+fn index(white_to_move: bool, black_king: usize, white_king: usize, pawn: usize) -> usize {
+    (!white_to_move) as usize | black_king << 1 | white_king << 7 | (pawn & 7) << 13 | (6 - pawn / 8) << 15
+}
+
+// Inverse of `index`
+fn squares(index: usize) -> (bool, usize, usize, usize) {
+    let pawn = (6 - (index >> 15)) * 8 + ((index >> 13) & 3);
+    (index & 1 == 0, (index >> 1) & 63, (index >> 7) & 63, pawn)
+}
+
+fn initial_result(position: usize) -> u8 {
+    let (white_to_move, black_king, white_king, pawn) = squares(position);
+    let pawn_attacks = PAWN_ATTACKS[PieceColor::WHITE as usize][pawn].get_bitboard();
+    let white_king_attacks = KING_MOVES[white_king].get_bitboard();
+    if white_king == black_king
+        || pawn == white_king
+        || pawn == black_king
+        || white_king_attacks & 1 << black_king != 0
+        || (white_to_move && pawn_attacks & 1 << black_king != 0)
+    {
+        return INVALID;
+    }
+    // Promotes without the queen being taken
+    let promotion = pawn + 8;
+    if white_to_move
+        && pawn / 8 == 6
+        && promotion != white_king
+        && promotion != black_king
+        && (KING_MOVES[black_king].get_bitboard() & 1 << promotion == 0 || white_king_attacks & 1 << promotion != 0)
+    {
+        return WIN;
+    }
+    // Stalemate, or the pawn is taken
+    let black_king_attacks = KING_MOVES[black_king].get_bitboard();
+    if !white_to_move
+        && (black_king_attacks & !(white_king_attacks | pawn_attacks) == 0 || black_king_attacks & !white_king_attacks & 1 << pawn != 0)
+    {
+        return DRAW;
+    }
+    UNKNOWN
+}
+
+// White wins if any move wins, black draws if any move draws
+fn classify(results: &[u8], position: usize) -> u8 {
+    let (white_to_move, black_king, white_king, pawn) = squares(position);
+    let mut found = 0;
+    if white_to_move {
+        let mut moves = KING_MOVES[white_king].get_bitboard();
+        while moves != 0 {
+            found |= results[index(false, black_king, moves.trailing_zeros() as usize, pawn)];
+            moves &= moves - 1;
+        }
+        let push = pawn + 8;
+        if pawn / 8 < 6 && push != white_king && push != black_king {
+            found |= results[index(false, black_king, white_king, push)];
+            let double = push + 8;
+            if pawn / 8 == 1 && double != white_king && double != black_king {
+                found |= results[index(false, black_king, white_king, double)];
+            }
+        }
+    } else {
+        let mut moves = KING_MOVES[black_king].get_bitboard();
+        while moves != 0 {
+            found |= results[index(true, moves.trailing_zeros() as usize, white_king, pawn)];
+            moves &= moves - 1;
+        }
+    }
+    let (good, bad) = if white_to_move { (WIN, DRAW) } else { (DRAW, WIN) };
+    if found & good != 0 {
+        good
+    } else if found & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+/// Whether the side with the pawn wins. Squares are from the pawn side's
+/// point of view, with the pawn on ranks 2 to 7.
+pub fn probe(strong_king: usize, pawn: usize, weak_king: usize, strong_to_move: bool) -> bool {
+    let (strong_king, pawn, weak_king) = if pawn & 7 > 3 { (strong_king ^ 7, pawn ^ 7, weak_king ^ 7) } else { (strong_king, pawn, weak_king) };
+    let index = index(strong_to_move, weak_king, strong_king, pawn);
+    KPK_BITBASE[index / 64] & 1 << (index % 64) != 0
+}
+
+/// Exact score of king and pawn against king from the strong side's point
+/// of view.
+pub fn evaluate(board: &Board, strong: PieceColor) -> i32 {
+    let relative = |square: Square| square.relative(strong).index();
+    let king = |color| relative(board.get_piece_bitboard(color, PieceType::KING).get_single_set_bit());
+    let pawn = relative(board.get_piece_bitboard(strong, PieceType::PAWN).get_single_set_bit());
+    if probe(king(strong), pawn, king(strong.opposite()), board.turn == strong) {
+        KNOWN_WIN + PIECE_VALUES[PieceType::PAWN as usize] + (pawn / 8) as i32
+    } else {
+        0
+    }
+}
@@ -0,0 +1,173 @@
+pub mod attacks;
+pub mod endgame;
+pub mod king;
+pub mod kpk;
+pub mod nnue;
+pub mod params;
+pub mod pawns;
+pub mod pieces;
+pub mod psqt;
+pub mod trace;
+
+use std::ops::Mul;
+use derive_more::{Add, AddAssign, Neg, Sub, SubAssign};
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use attacks::AttackMaps;
+use params::{EvalParams, DEFAULT_PARAMS};
+use pawns::{PawnEntry, PawnTable};
+
+/// Fixed material values in centipawns, indexed by `PieceType`, for move
+/// ordering, the specialised endgame evaluators and the KPK bitbase, whose
+/// scores must not move when the weights are tuned. The evaluation terms
+/// use `EvalParams::material`.
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+/// Score of a position known to be won, below any mate score.
+pub const KNOWN_WIN: i32 = 10_000;
+
+const PIECE_TYPES: [PieceType; 5] = [PieceType::PAWN, PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN];
+/// Phase weight of each piece type, indexed by `PieceType`.
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+/// Phase of the starting position, everything above counts as middlegame.
+const MAX_PHASE: i32 = 24;
+
+/// A term with separate middlegame and endgame values, blended by how much
+/// material is left.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Add, Sub, AddAssign, SubAssign, Neg)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Score { mg, eg }
+    }
+
+    /// Blends the two values, `phase` going from 0 in a bare endgame to
+    /// `MAX_PHASE` with all pieces on the board.
+    pub fn taper(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, rhs: i32) -> Score {
+        Score::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+/// Static evaluation in centipawns from the side to move's point of view.
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with_params(board, &DEFAULT_PARAMS)
+}
+
+/// Same as `evaluate`, with other weights.
+pub fn evaluate_with_params(board: &Board, params: &EvalParams) -> i32 {
+    relative(board, white_score(board, &pawns::analyse(board, params), params))
+}
+
+/// Same as `evaluate_with_params`, with the pawn structure looked up in
+/// `pawn_table`. A board with a network set is evaluated by it instead,
+/// unless a specialised endgame evaluator applies.
+pub fn evaluate_cached(board: &Board, pawn_table: &mut PawnTable, params: &EvalParams) -> i32 {
+    if let Some(nnue) = board.nnue() {
+        return endgame::evaluate(board).map_or_else(|| nnue.evaluate(board.turn), |score| relative(board, score));
+    }
+    relative(board, white_score(board, &pawn_table.probe(board, params), params))
+}
+
+/// Static evaluation in centipawns from white's point of view.
+pub fn evaluate_white(board: &Board, params: &EvalParams) -> i32 {
+    white_score(board, &pawns::analyse(board, params), params)
+}
+
+fn white_score(board: &Board, pawns: &PawnEntry, params: &EvalParams) -> i32 {
+    endgame::evaluate(board).unwrap_or_else(|| {
+        let score = Terms::new(board, pawns, params).total().taper(phase(board));
+        score * endgame::scale_factor(board, strong_side(score), params) / endgame::SCALE_NORMAL
+    })
+}
+
+fn relative(board: &Board, score: i32) -> i32 {
+    if board.turn == PieceColor::WHITE { score } else { -score }
+}
+
+fn strong_side(score: i32) -> PieceColor {
+    if score > 0 { PieceColor::WHITE } else { PieceColor::BLACK }
+}
+
+/// The evaluation terms of each side before tapering, each indexed by
+/// colour.
+#[derive(Clone, Copy, Debug)]
+pub struct Terms {
+    pub material: [Score; 2],
+    pub psqt: [Score; 2],
+    /// Pawn structure and passed pawns
+    pub pawns: [Score; 2],
+    pub mobility: [Score; 2],
+    pub king_safety: [Score; 2],
+    pub threats: [Score; 2],
+}
+
+impl Terms {
+    pub fn new(board: &Board, pawns: &PawnEntry, params: &EvalParams) -> Self {
+        let attacks = AttackMaps::new(board);
+        let per_side = |term: &dyn Fn(PieceColor) -> Score| [term(PieceColor::WHITE), term(PieceColor::BLACK)];
+        Terms {
+            material: per_side(&|color| {
+                PIECE_TYPES.iter().fold(Score::default(), |total, piece_type| {
+                    total + params.material[*piece_type as usize] * board.get_piece_bitboard(color, *piece_type).pop_count() as i32
+                })
+            }),
+            psqt: per_side(&|color| psqt::psqt(board, color, params)),
+            pawns: per_side(&|color| pawns.score[color as usize] + pawns::passed_pawns(board, color, pawns.passed[color as usize], params)),
+            mobility: per_side(&|color| pieces::mobility(board, color, &attacks, params)),
+            king_safety: per_side(&|color| king::king_safety(board, color, &attacks, params)),
+            threats: per_side(&|color| pieces::threats(board, color, &attacks, params)),
+        }
+    }
+
+    /// Name and value of every term, in a fixed order.
+    pub fn named(&self) -> [(&'static str, [Score; 2]); 6] {
+        [
+            ("material", self.material),
+            ("psqt", self.psqt),
+            ("pawns", self.pawns),
+            ("mobility", self.mobility),
+            ("king_safety", self.king_safety),
+            ("threats", self.threats),
+        ]
+    }
+
+    /// Sum of all terms from white's point of view.
+    pub fn total(&self) -> Score {
+        self.named().iter().fold(Score::default(), |total, (_, [white, black])| total + *white - *black)
+    }
+}
+
+/// Material balance from white's point of view.
+pub fn material(board: &Board) -> i32 {
+    PIECE_TYPES
+        .iter()
+        .map(|piece_type| {
+            let count = |color| board.get_piece_bitboard(color, *piece_type).pop_count() as i32;
+            PIECE_VALUES[*piece_type as usize] * (count(PieceColor::WHITE) - count(PieceColor::BLACK))
+        })
+        .sum()
+}
+
+/// Game phase from the pieces left, `MAX_PHASE` at the start and 0 with
+/// only kings and pawns.
+pub fn phase(board: &Board) -> i32 {
+    let phase: i32 = PIECE_TYPES
+        .iter()
+        .map(|piece_type| {
+            let count = |color| board.get_piece_bitboard(color, *piece_type).pop_count() as i32;
+            PHASE_WEIGHTS[*piece_type as usize] * (count(PieceColor::WHITE) + count(PieceColor::BLACK))
+        })
+        .sum();
+    phase.min(MAX_PHASE)
+}
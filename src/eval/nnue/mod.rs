@@ -0,0 +1,260 @@
+//! Efficiently updatable neural network evaluation.
+//!
+//! The inputs are HalfKA features: from each side's perspective, every
+//! piece, kings included, on every square, for every square of that side's
+//! king. The two perspectives share one feature transformer into `HIDDEN`
+//! accumulated values, which are clipped and fed to a single output, the
+//! side to move's half first.
+
+pub mod simd;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::Square;
+use super::KNOWN_WIN;
+
+/// Size of the feature transformer output for each perspective.
+pub const HIDDEN: usize = 64;
+/// King square times piece, relative to the perspective, times square.
+pub const INPUTS: usize = 64 * 12 * 64;
+/// Clipping bound of the accumulator, which quantises 1.0.
+pub const QA: i32 = 255;
+/// Quantisation of the output weights.
+pub const QB: i32 = 64;
+/// Centipawns per unit of network output.
+pub const SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"RBNN";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 12;
+/// Size of a weights file: the feature weights, the feature biases, the
+/// output weights and the output bias.
+const FILE_SIZE: usize = HEADER_SIZE + 2 * (INPUTS * HIDDEN + HIDDEN + 2 * HIDDEN) + 4;
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::PAWN, PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN, PieceType::KING];
+
+/// Reason a weights file could not be loaded.
+#[derive(Debug)]
+pub enum NnueError {
+    Io(io::Error),
+    InvalidHeader,
+    /// Hidden layer size of a file made for another build
+    UnsupportedSize(u32),
+    /// Length of a file whose header is valid
+    InvalidLength(usize),
+}
+
+impl fmt::Display for NnueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NnueError::Io(e) => write!(f, "{}", e),
+            NnueError::InvalidHeader => write!(f, "not a version {} network file", VERSION),
+            NnueError::UnsupportedSize(size) => write!(f, "hidden layer of {} values, expected {}", size, HIDDEN),
+            NnueError::InvalidLength(length) => write!(f, "file is {} bytes long, expected {}", length, FILE_SIZE),
+        }
+    }
+}
+
+impl std::error::Error for NnueError {}
+
+impl From<io::Error> for NnueError {
+    fn from(e: io::Error) -> Self {
+        NnueError::Io(e)
+    }
+}
+
+/// Index of the input for `piece` on `square` seen from `perspective`,
+/// whose king is on `king`. Black sees the board flipped vertically, so
+/// both perspectives share the weights.
+pub fn feature(perspective: PieceColor, king: Square, piece: Piece, square: Square) -> usize {
+    let relation = if piece.piece_color == perspective { 0 } else { 6 };
+    ((king.relative(perspective).index() * 12 + relation + piece.piece_type as usize) * 64) + square.relative(perspective).index()
+}
+
+/// Quantised weights. A file holds, after the `RBNN` magic, the version and
+/// `HIDDEN` as little-endian `u32`s, every array below in order as
+/// little-endian `i16`s and the output bias as an `i32`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Network {
+    /// Indexed by `feature`
+    pub feature_weights: Vec<[i16; HIDDEN]>,
+    pub feature_bias: [i16; HIDDEN],
+    /// Weights of the side to move's half, then of the other side's
+    pub output_weights: [[i16; HIDDEN]; 2],
+    pub output_bias: i32,
+}
+
+impl Network {
+    /// A network with every weight zero, evaluating everything as a draw.
+    pub fn zeroed() -> Self {
+        Network { feature_weights: vec![[0; HIDDEN]; INPUTS], feature_bias: [0; HIDDEN], output_weights: [[0; HIDDEN]; 2], output_bias: 0 }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, NnueError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC || read_u32(&bytes[4..]) != VERSION {
+            return Err(NnueError::InvalidHeader);
+        }
+        let hidden = read_u32(&bytes[8..]);
+        if hidden as usize != HIDDEN {
+            return Err(NnueError::UnsupportedSize(hidden));
+        }
+        if bytes.len() != FILE_SIZE {
+            return Err(NnueError::InvalidLength(bytes.len()));
+        }
+
+        let mut values = bytes[HEADER_SIZE..FILE_SIZE - 4].chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]));
+        let mut read_row = || {
+            let mut row = [0; HIDDEN];
+            row.iter_mut().zip(&mut values).for_each(|(value, read)| *value = read);
+            row
+        };
+        let feature_weights = (0..INPUTS).map(|_| read_row()).collect();
+        let feature_bias = read_row();
+        let output_weights = [read_row(), read_row()];
+        let output_bias = i32::from_le_bytes(bytes[FILE_SIZE - 4..].try_into().unwrap());
+        Ok(Network { feature_weights, feature_bias, output_weights, output_bias })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(FILE_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(HIDDEN as u32).to_le_bytes());
+        let rows = self.feature_weights.iter().chain([&self.feature_bias]).chain(&self.output_weights);
+        bytes.extend(rows.flatten().flat_map(|value| value.to_le_bytes()));
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Network, NnueError> {
+        Network::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+/// Feature transformer output for both perspectives, indexed by colour.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, align(32))]
+pub struct Accumulator {
+    pub values: [[i16; HIDDEN]; 2],
+}
+
+impl Accumulator {
+    /// Computes both perspectives from scratch.
+    pub fn new(network: &Network, board: &Board) -> Self {
+        let mut accumulator = Accumulator { values: [network.feature_bias; 2] };
+        for perspective in [PieceColor::WHITE, PieceColor::BLACK] {
+            accumulator.refresh(network, board, perspective);
+        }
+        accumulator
+    }
+
+    fn refresh(&mut self, network: &Network, board: &Board, perspective: PieceColor) {
+        let values = &mut self.values[perspective as usize];
+        *values = network.feature_bias;
+        let king = board.get_piece_bitboard(perspective, PieceType::KING);
+        if king.is_empty() {
+            return;
+        }
+        let king = king.get_single_set_bit();
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            for piece_type in PIECE_TYPES {
+                let piece = Piece { piece_color: color, piece_type };
+                for square in board.get_piece_bitboard(color, piece_type) {
+                    simd::add(values, &network.feature_weights[feature(perspective, king, piece, square)]);
+                }
+            }
+        }
+    }
+}
+
+/// Accumulators of a board, one for each move made since the network was
+/// set, so that unmaking a move only drops the last one.
+#[derive(Clone)]
+pub struct NnueState {
+    network: Arc<Network>,
+    stack: Vec<Accumulator>,
+    /// Perspectives whose king moved, refreshed once the move is made
+    stale: [bool; 2],
+}
+
+impl NnueState {
+    pub fn new(network: Arc<Network>, board: &Board) -> Self {
+        let accumulator = Accumulator::new(&network, board);
+        NnueState { network, stack: vec![accumulator], stale: [false; 2] }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn accumulator(&self) -> &Accumulator {
+        self.stack.last().unwrap()
+    }
+
+    /// Score in centipawns from the side to move's point of view, kept
+    /// below `KNOWN_WIN`.
+    pub fn evaluate(&self, turn: PieceColor) -> i32 {
+        let accumulator = self.accumulator();
+        let (us, them) = (turn as usize, turn.opposite() as usize);
+        let output = simd::output(&accumulator.values[us], &self.network.output_weights[0])
+            + simd::output(&accumulator.values[them], &self.network.output_weights[1])
+            + self.network.output_bias;
+        (output as i64 * SCALE as i64 / (QA * QB) as i64).clamp(-(KNOWN_WIN - 1) as i64, (KNOWN_WIN - 1) as i64) as i32
+    }
+
+    /// Copies the accumulator before a move changes it.
+    pub(crate) fn push(&mut self) {
+        self.stack.push(*self.accumulator());
+    }
+
+    /// Goes back to the accumulator before the last move.
+    pub(crate) fn pop(&mut self) {
+        self.stack.pop();
+    }
+
+    /// Adds or removes `piece` on `square`, given the king of each side.
+    /// A perspective whose king moves needs every feature recomputed, it is
+    /// only marked stale.
+    pub(crate) fn update(&mut self, piece: Piece, square: Square, kings: [Bitboard; 2], added: bool) {
+        if piece.piece_type == PieceType::KING {
+            self.stale[piece.piece_color as usize] = true;
+        }
+        let accumulator = self.stack.last_mut().unwrap();
+        for perspective in [PieceColor::WHITE, PieceColor::BLACK] {
+            let king = kings[perspective as usize];
+            if self.stale[perspective as usize] || king.is_empty() {
+                self.stale[perspective as usize] = true;
+                continue;
+            }
+            let weights = &self.network.feature_weights[feature(perspective, king.get_single_set_bit(), piece, square)];
+            if added {
+                simd::add(&mut accumulator.values[perspective as usize], weights);
+            } else {
+                simd::sub(&mut accumulator.values[perspective as usize], weights);
+            }
+        }
+    }
+
+    /// Recomputes the perspectives marked stale by `update`.
+    pub(crate) fn refresh_stale(&mut self, board: &Board) {
+        for perspective in [PieceColor::WHITE, PieceColor::BLACK] {
+            if std::mem::take(&mut self.stale[perspective as usize]) {
+                self.stack.last_mut().unwrap().refresh(&self.network, board, perspective);
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Kernels of the network. The accumulator updates are plain loops over
+//! fixed-size arrays that the compiler vectorises; the output layer uses
+//! AVX2 when the CPU has it and a scalar loop otherwise.
+
+use super::{HIDDEN, QA};
+
+/// Adds the weights of a feature to an accumulator.
+pub fn add(accumulator: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+    for (value, weight) in accumulator.iter_mut().zip(weights) {
+        *value = value.wrapping_add(*weight);
+    }
+}
+
+/// Removes the weights of a feature from an accumulator.
+pub fn sub(accumulator: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+    for (value, weight) in accumulator.iter_mut().zip(weights) {
+        *value = value.wrapping_sub(*weight);
+    }
+}
+
+/// Dot product of the accumulator, clipped to `0..=QA`, with the output
+/// weights of one perspective.
+pub fn output(accumulator: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2
+        return unsafe { output_avx2(accumulator, weights) };
+    }
+    output_scalar(accumulator, weights)
+}
+
+/// Portable version of `output`.
+pub fn output_scalar(accumulator: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    accumulator.iter().zip(weights).map(|(value, weight)| (*value).clamp(0, QA as i16) as i32 * *weight as i32).sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn output_avx2(accumulator: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 16;
+    let zero = _mm256_setzero_si256();
+    let max = _mm256_set1_epi16(QA as i16);
+    let mut sum = _mm256_setzero_si256();
+    for i in (0..HIDDEN).step_by(LANES) {
+        // SAFETY: HIDDEN is a multiple of LANES, so every load is in bounds
+        let (value, weight) = unsafe {
+            (
+                _mm256_loadu_si256(accumulator.as_ptr().add(i) as *const __m256i),
+                _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i),
+            )
+        };
+        let clipped = _mm256_min_epi16(_mm256_max_epi16(value, zero), max);
+        sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, weight));
+    }
+    let sum = _mm_add_epi32(_mm256_castsi256_si128(sum), _mm256_extracti128_si256::<1>(sum));
+    let sum = _mm_add_epi32(sum, _mm_shuffle_epi32::<0b01_00_11_10>(sum));
+    let sum = _mm_add_epi32(sum, _mm_shuffle_epi32::<0b10_11_00_01>(sum));
+    _mm_cvtsi128_si32(sum)
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use super::endgame::SCALE_NORMAL;
+use super::Score;
+
+const PIECE_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+/// Pawns to queens, every piece with a material value
+const MATERIAL: Range<usize> = 0..5;
+/// Knights to queens, the pieces with mobility that attack the king zone
+const PIECES: Range<usize> = 1..5;
+/// Relative ranks a pawn can stand on
+const PAWN_RANKS: Range<usize> = 1..7;
+
+/// Reason a parameter file could not be loaded.
+#[derive(Debug)]
+pub enum ParamsError {
+    Io(io::Error),
+    /// Line number, from 1, of a line that is not `name value`
+    InvalidLine(usize),
+    UnknownParameter(String),
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::Io(e) => write!(f, "{}", e),
+            ParamsError::InvalidLine(line) => write!(f, "line {} is not a name followed by an integer", line),
+            ParamsError::UnknownParameter(name) => write!(f, "unknown parameter {}", name),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+impl From<io::Error> for ParamsError {
+    fn from(e: io::Error) -> Self {
+        ParamsError::Io(e)
+    }
+}
+
+/// Every weight of the hand-crafted evaluation. Tables indexed by
+/// `PieceType` or relative rank keep entries that can never apply, so that
+/// the indexing stays direct.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EvalParams {
+    /// Indexed by `PieceType`
+    pub material: [Score; 6],
+    /// Bonus by file from the a-file to the d-file, mirrored for the other
+    /// half of the board, indexed by `PieceType`
+    pub psqt_file: [[Score; 4]; 6],
+    /// Bonus by relative rank, indexed by `PieceType`
+    pub psqt_rank: [[Score; 8]; 6],
+    pub doubled: Score,
+    pub isolated: Score,
+    pub backward: Score,
+    /// Per pawn defending a pawn
+    pub supported: Score,
+    /// Indexed by relative rank
+    pub phalanx: [Score; 8],
+    /// Indexed by relative rank
+    pub passed: [Score; 8],
+    /// Endgame weight of the distance of the own king to the square in
+    /// front of a passed pawn, multiplied by how far the pawn has advanced
+    pub passed_own_king: i32,
+    /// Same for the enemy king
+    pub passed_enemy_king: i32,
+    /// When the square in front of a passed pawn is occupied, multiplied by
+    /// how far the pawn has advanced
+    pub passed_blocked: Score,
+    /// Value of each safe square beyond the expected number, indexed by
+    /// `PieceType`
+    pub mobility: [Score; 6],
+    /// Per piece attacked and not defended
+    pub hanging: Score,
+    /// Per knight, bishop, rook or queen attacked by a pawn
+    pub threat_by_pawn: Score,
+    /// Per rook or queen attacked by a knight or bishop
+    pub threat_by_minor: Score,
+    /// Weight of each attacked king zone square, indexed by `PieceType`
+    pub king_attack_weights: [i32; 6],
+    /// Middlegame bonus of the closest own pawn in front of the king on
+    /// each file next to it, indexed by its relative rank, 0 when there is
+    /// none
+    pub pawn_shelter: [i32; 8],
+    /// Middlegame penalty of the closest enemy pawn advancing on each file
+    /// next to the king, indexed by its rank relative to the king's side
+    pub pawn_storm: [i32; 8],
+    pub king_semi_open_file: i32,
+    pub king_open_file: i32,
+    /// Cap of the middlegame king danger from pieces attacking the king zone
+    pub king_danger_max: i32,
+    /// Scale factor out of `SCALE_NORMAL` of endings with one bishop each,
+    /// on squares of opposite colours, and nothing else but pawns
+    pub opposite_bishops_scale: i32,
+}
+
+pub const DEFAULT_PARAMS: EvalParams = EvalParams {
+    material: [Score::new(100, 100), Score::new(320, 320), Score::new(330, 330), Score::new(500, 500), Score::new(900, 900), Score::new(0, 0)],
+    psqt_file: [
+        [Score::new(-4, 0), Score::new(0, 0), Score::new(4, 0), Score::new(8, 0)],
+        [Score::new(-20, -16), Score::new(-6, -6), Score::new(4, 2), Score::new(10, 6)],
+        [Score::new(-8, -6), Score::new(0, -2), Score::new(2, 0), Score::new(4, 2)],
+        [Score::new(-4, 0), Score::new(-2, 0), Score::new(2, 0), Score::new(6, 0)],
+        [Score::new(-6, -6), Score::new(-2, -2), Score::new(0, 2), Score::new(2, 4)],
+        [Score::new(20, -24), Score::new(24, -8), Score::new(0, 4), Score::new(-16, 10)],
+    ],
+    psqt_rank: [
+        [Score::new(0, 0), Score::new(0, 0), Score::new(-2, 0), Score::new(4, 2), Score::new(8, 4), Score::new(10, 8), Score::new(12, 12), Score::new(0, 0)],
+        [Score::new(-18, -14), Score::new(-6, -6), Score::new(2, 0), Score::new(8, 6), Score::new(12, 8), Score::new(14, 6), Score::new(4, 0), Score::new(-16, -10)],
+        [Score::new(-6, -6), Score::new(4, -2), Score::new(4, 0), Score::new(4, 2), Score::new(2, 2), Score::new(2, 0), Score::new(-2, -2), Score::new(-8, -6)],
+        [Score::new(0, 0), Score::new(-4, -2), Score::new(-4, -2), Score::new(-2, 0), Score::new(0, 0), Score::new(2, 2), Score::new(14, 8), Score::new(6, 4)],
+        [Score::new(0, -10), Score::new(2, -4), Score::new(0, 0), Score::new(0, 4), Score::new(0, 6), Score::new(0, 6), Score::new(-2, 4), Score::new(-4, 0)],
+        [Score::new(14, -24), Score::new(-4, -8), Score::new(-14, 4), Score::new(-22, 12), Score::new(-28, 14), Score::new(-30, 12), Score::new(-30, 4), Score::new(-30, -12)],
+    ],
+    doubled: Score::new(-11, -26),
+    isolated: Score::new(-8, -14),
+    backward: Score::new(-9, -12),
+    supported: Score::new(9, 6),
+    phalanx: [Score::new(0, 0), Score::new(3, 0), Score::new(5, 2), Score::new(9, 5), Score::new(18, 14), Score::new(32, 28), Score::new(55, 50), Score::new(0, 0)],
+    passed: [Score::new(0, 0), Score::new(4, 12), Score::new(6, 16), Score::new(12, 26), Score::new(28, 48), Score::new(52, 90), Score::new(90, 145), Score::new(0, 0)],
+    passed_own_king: -3,
+    passed_enemy_king: 6,
+    passed_blocked: Score::new(-3, -8),
+    mobility: [Score::new(0, 0), Score::new(6, 6), Score::new(6, 7), Score::new(3, 7), Score::new(2, 4), Score::new(0, 0)],
+    hanging: Score::new(30, 18),
+    threat_by_pawn: Score::new(50, 35),
+    threat_by_minor: Score::new(30, 22),
+    king_attack_weights: [0, 2, 2, 3, 5, 0],
+    pawn_shelter: [-30, 26, 14, 4, -2, -6, -8, 0],
+    pawn_storm: [0, 0, -34, -16, -6, 0, 0, 0],
+    king_semi_open_file: -12,
+    king_open_file: -25,
+    king_danger_max: 600,
+    opposite_bishops_scale: 22,
+};
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        DEFAULT_PARAMS
+    }
+}
+
+// Calls `f` with the name and value of every weight, in a fixed order
+type Visitor<'a> = dyn FnMut(String, &mut i32) + 'a;
+
+fn visit_score(f: &mut Visitor, name: &str, score: &mut Score) {
+    f(format!("{}.mg", name), &mut score.mg);
+    f(format!("{}.eg", name), &mut score.eg);
+}
+
+// Only the entries in `range`, the others are never read
+fn visit_scores(f: &mut Visitor, name: &str, scores: &mut [Score], range: Range<usize>) {
+    for i in range {
+        visit_score(f, &format!("{}.{}", name, i), &mut scores[i]);
+    }
+}
+
+fn visit_values(f: &mut Visitor, name: &str, values: &mut [i32], range: Range<usize>) {
+    for i in range {
+        f(format!("{}.{}", name, i), &mut values[i]);
+    }
+}
+
+impl EvalParams {
+    /// Calls `f` with the name and a reference to every weight the
+    /// evaluation reads, always in the same order. Table entries that can
+    /// never apply, like the king's material or pawns on the last rank, are
+    /// left out.
+    pub fn visit(&mut self, mut f: impl FnMut(String, &mut i32)) {
+        let f: &mut Visitor = &mut f;
+        for piece in MATERIAL {
+            visit_score(f, &format!("material.{}", PIECE_NAMES[piece]), &mut self.material[piece]);
+        }
+        for (piece, name) in PIECE_NAMES.iter().enumerate() {
+            visit_scores(f, &format!("psqt_file.{}", name), &mut self.psqt_file[piece], 0..4);
+            visit_scores(f, &format!("psqt_rank.{}", name), &mut self.psqt_rank[piece], 0..8);
+        }
+        visit_score(f, "doubled", &mut self.doubled);
+        visit_score(f, "isolated", &mut self.isolated);
+        visit_score(f, "backward", &mut self.backward);
+        visit_score(f, "supported", &mut self.supported);
+        visit_scores(f, "phalanx", &mut self.phalanx, PAWN_RANKS);
+        visit_scores(f, "passed", &mut self.passed, PAWN_RANKS);
+        f("passed_own_king".to_string(), &mut self.passed_own_king);
+        f("passed_enemy_king".to_string(), &mut self.passed_enemy_king);
+        visit_score(f, "passed_blocked", &mut self.passed_blocked);
+        for piece in PIECES {
+            visit_score(f, &format!("mobility.{}", PIECE_NAMES[piece]), &mut self.mobility[piece]);
+        }
+        visit_score(f, "hanging", &mut self.hanging);
+        visit_score(f, "threat_by_pawn", &mut self.threat_by_pawn);
+        visit_score(f, "threat_by_minor", &mut self.threat_by_minor);
+        for piece in PIECES {
+            f(format!("king_attack_weights.{}", PIECE_NAMES[piece]), &mut self.king_attack_weights[piece]);
+        }
+        // Index 0 is for a file without pawns
+        visit_values(f, "pawn_shelter", &mut self.pawn_shelter, 0..7);
+        visit_values(f, "pawn_storm", &mut self.pawn_storm, 0..7);
+        f("king_semi_open_file".to_string(), &mut self.king_semi_open_file);
+        f("king_open_file".to_string(), &mut self.king_open_file);
+        f("king_danger_max".to_string(), &mut self.king_danger_max);
+        f("opposite_bishops_scale".to_string(), &mut self.opposite_bishops_scale);
+    }
+
+    /// All weights in `visit` order.
+    pub fn to_vec(&self) -> Vec<i32> {
+        let mut values = Vec::new();
+        self.clone().visit(|_, value| values.push(*value));
+        values
+    }
+
+    /// Sets the weights from a slice in `visit` order, kept within their
+    /// valid ranges.
+    pub fn set_from_slice(&mut self, values: &[i32]) {
+        let mut values = values.iter();
+        self.visit(|_, value| *value = *values.next().expect("too few parameters"));
+        self.clamp();
+    }
+
+    // A negative cap or scale factor would flip the sign of its term
+    fn clamp(&mut self) {
+        self.king_danger_max = self.king_danger_max.max(0);
+        self.opposite_bishops_scale = self.opposite_bishops_scale.clamp(0, SCALE_NORMAL);
+    }
+
+    /// Names of the weights in `visit` order.
+    pub fn names() -> Vec<String> {
+        let mut names = Vec::new();
+        EvalParams::default().visit(|name, _| names.push(name));
+        names
+    }
+
+    /// One `name value` line per weight.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.clone().visit(|name, value| text.push_str(&format!("{} {}\n", name, value)));
+        text
+    }
+
+    /// Parses `name value` lines, as written by `to_text`. Weights that are
+    /// not listed keep their default value; blank lines and lines starting
+    /// with `#` are skipped.
+    pub fn from_text(text: &str) -> Result<EvalParams, ParamsError> {
+        let mut values = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(name), Some(Ok(value)), None) = (fields.next(), fields.next().map(str::parse::<i32>), fields.next()) else {
+                return Err(ParamsError::InvalidLine(i + 1));
+            };
+            values.push((name.to_string(), value));
+        }
+        let mut params = DEFAULT_PARAMS;
+        let mut unknown: Vec<&String> = values.iter().map(|(name, _)| name).collect();
+        params.visit(|name, value| {
+            if let Some((_, new)) = values.iter().rev().find(|(listed, _)| *listed == name) {
+                *value = *new;
+                unknown.retain(|listed| **listed != name);
+            }
+        });
+        params.clamp();
+        match unknown.first() {
+            Some(name) => Err(ParamsError::UnknownParameter(name.to_string())),
+            None => Ok(params),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<EvalParams, ParamsError> {
+        EvalParams::from_text(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use crate::board::square::Square;
+use super::params::EvalParams;
+use super::Score;
+
+const PAWN_TABLE_ENTRIES: usize = 1 << 14;
+
+/// What the pawns alone decide, shared by every position with the same
+/// pawns.
+#[derive(Clone, Copy, Debug)]
+pub struct PawnEntry {
+    key: u64,
+    /// Structure score of each side, indexed by colour
+    pub score: [Score; 2],
+    /// Passed pawns, indexed by colour
+    pub passed: [Bitboard; 2],
+}
+
+/// Pawn structure evaluations indexed by the pawn hash, always replaced.
+/// Entries are only valid for the parameters they were computed with.
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        PawnTable { entries: vec![None; PAWN_TABLE_ENTRIES] }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Structure of the pawns on `board`, analysed only when not stored yet.
+    pub fn probe(&mut self, board: &Board, params: &EvalParams) -> PawnEntry {
+        let key = board.get_pawn_hash();
+        let slot = &mut self.entries[key as usize % PAWN_TABLE_ENTRIES];
+        match slot {
+            Some(entry) if entry.key == key => *entry,
+            _ => *slot.insert(analyse(board, params)),
+        }
+    }
+}
+
+/// Scores doubled, isolated, backward, connected and passed pawns.
+pub fn analyse(board: &Board, params: &EvalParams) -> PawnEntry {
+    let mut entry = PawnEntry { key: board.get_pawn_hash(), score: [Score::default(); 2], passed: [Bitboard::new(0); 2] };
+    for color in [PieceColor::WHITE, PieceColor::BLACK] {
+        (entry.score[color as usize], entry.passed[color as usize]) = analyse_side(board, color, params);
+    }
+    entry
+}
+
+fn analyse_side(board: &Board, color: PieceColor, params: &EvalParams) -> (Score, Bitboard) {
+    let ours = board.get_piece_bitboard(color, PieceType::PAWN);
+    let theirs = board.get_piece_bitboard(color.opposite(), PieceType::PAWN);
+    let their_attacks = theirs.pawn_attacks(color.opposite());
+    let mut score = Score::default();
+    let mut passed = Bitboard::new(0);
+    for square in ours {
+        let pawn = square.bitboard();
+        let rank = square.rank().relative(color).index() as usize;
+        let adjacent_files = (pawn.east() | pawn.west()).file_fill();
+        // Squares ahead of the pawn on its own and the adjacent files
+        let ahead = pawn.forward_fill(color) & !pawn;
+        let front_span = ahead | ahead.east() | ahead.west();
+        let stop = pawn.pawn_push(&color);
+
+        if !(ours & ahead).is_empty() {
+            score += params.doubled;
+        }
+        let neighbours = ours & adjacent_files;
+        if neighbours.is_empty() {
+            score += params.isolated;
+        } else if (neighbours & !front_span).is_empty() && !(stop & (their_attacks | theirs)).is_empty() {
+            // No neighbour can come level to defend it, and it cannot advance
+            score += params.backward;
+        }
+        let supporters = (ours & pawn.pawn_attacks(color.opposite())).pop_count() as i32;
+        score += params.supported * supporters;
+        if !(ours & (pawn.east() | pawn.west())).is_empty() {
+            score += params.phalanx[rank];
+        }
+        if (theirs & front_span).is_empty() && (ours & ahead).is_empty() {
+            score += params.passed[rank];
+            passed |= pawn;
+        }
+    }
+    (score, passed)
+}
+
+/// Terms of the passed pawns of `color` that depend on the other pieces:
+/// the distance of both kings to the square in front and whether it is
+/// occupied.
+pub fn passed_pawns(board: &Board, color: PieceColor, passed: Bitboard, params: &EvalParams) -> Score {
+    let king = |color| board.get_piece_bitboard(color, PieceType::KING).get_single_set_bit();
+    let (our_king, their_king) = (king(color), king(color.opposite()));
+    let mut score = Score::default();
+    for square in passed {
+        let weight = (square.rank().relative(color).index() as i32 - 2).max(0);
+        let Some(stop) = square.forward(color).filter(|_| weight > 0) else { continue };
+        let distance = |king: Square| king.distance(stop).min(5) as i32;
+        score.eg += weight * (params.passed_own_king * distance(our_king) + params.passed_enemy_king * distance(their_king));
+        if board.piece_at(stop).is_some() {
+            score += params.passed_blocked * weight;
+        }
+    }
+    score
+}
@@ -0,0 +1,38 @@
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use super::attacks::AttackMaps;
+use super::params::EvalParams;
+use super::Score;
+
+/// Number of safe squares a piece is expected to reach, indexed by
+/// `PieceType`. Not in EvalParams: for each piece it only adds a constant,
+/// which the material weights already cover.
+const MOBILITY_BASE: [i32; 6] = [0, 4, 6, 7, 13, 0];
+
+/// Mobility of the knights, bishops, rooks and queens of `color`, counting
+/// the squares not occupied by its own pieces nor attacked by enemy pawns.
+pub fn mobility(board: &Board, color: PieceColor, attacks: &AttackMaps, params: &EvalParams) -> Score {
+    let safe = !(board.get_color_bitboard(color) | attacks.of(color.opposite(), PieceType::PAWN));
+    let mut score = Score::default();
+    for piece_type in [PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN] {
+        for square in board.get_piece_bitboard(color, piece_type) {
+            let squares = (attacks.by_square[square] & safe).pop_count() as i32;
+            score += params.mobility[piece_type as usize] * (squares - MOBILITY_BASE[piece_type as usize]);
+        }
+    }
+    score
+}
+
+/// Attacks of `color` on enemy pieces: undefended ones, and pieces attacked
+/// by something worth less.
+pub fn threats(board: &Board, color: PieceColor, attacks: &AttackMaps, params: &EvalParams) -> Score {
+    let them = color.opposite();
+    let pieces = board.non_pawn_pieces(them);
+    let majors = board.get_piece_bitboard(them, PieceType::ROOK) | board.get_piece_bitboard(them, PieceType::QUEEN);
+    let minor_attacks = attacks.of(color, PieceType::KNIGHT) | attacks.of(color, PieceType::BISHOP);
+
+    let hanging = (board.get_color_bitboard(them) & !board.get_piece_bitboard(them, PieceType::KING)) & attacks.all[color as usize] & !attacks.all[them as usize];
+    params.hanging * hanging.pop_count() as i32
+        + params.threat_by_pawn * (pieces & attacks.of(color, PieceType::PAWN)).pop_count() as i32
+        + params.threat_by_minor * (majors & minor_attacks).pop_count() as i32
+}
@@ -0,0 +1,20 @@
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use super::params::EvalParams;
+use super::Score;
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::PAWN, PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN, PieceType::KING];
+
+/// Piece-square bonus of the pieces of `color`, the sum of a file and a
+/// rank bonus for each.
+pub fn psqt(board: &Board, color: PieceColor, params: &EvalParams) -> Score {
+    let mut score = Score::default();
+    for piece_type in PIECE_TYPES {
+        for square in board.get_piece_bitboard(color, piece_type) {
+            let file = square.file().index().min(7 - square.file().index()) as usize;
+            let rank = square.rank().relative(color).index() as usize;
+            score += params.psqt_file[piece_type as usize][file] + params.psqt_rank[piece_type as usize][rank];
+        }
+    }
+    score
+}
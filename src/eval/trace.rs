@@ -0,0 +1,64 @@
+use crate::board::board::Board;
+use crate::board::piece::PieceColor;
+use super::params::EvalParams;
+use super::{endgame, pawns, phase, strong_side, Score, Terms, MAX_PHASE};
+
+/// Every step of the evaluation of a position, for tuning.
+#[derive(Clone, Debug)]
+pub struct EvalTrace {
+    pub fen: String,
+    pub terms: Terms,
+    pub phase: i32,
+    /// Sum of the terms from white's point of view, tapered by the phase
+    pub tapered: i32,
+    /// Factor out of `SCALE_NORMAL` applied to the tapered score
+    pub scale: i32,
+    /// Score of a specialised endgame evaluator, which replaces the terms
+    pub endgame: Option<i32>,
+    /// Final score from white's point of view
+    pub score: i32,
+    /// Final score from the side to move's point of view, as `evaluate`
+    /// returns it
+    pub relative_score: i32,
+}
+
+/// Evaluates `board` with `params`, keeping every intermediate value.
+pub fn trace(board: &Board, params: &EvalParams) -> EvalTrace {
+    let terms = Terms::new(board, &pawns::analyse(board, params), params);
+    let phase = phase(board);
+    let tapered = terms.total().taper(phase);
+    let scale = endgame::scale_factor(board, strong_side(tapered), params);
+    let endgame = endgame::evaluate(board);
+    let score = endgame.unwrap_or(tapered * scale / endgame::SCALE_NORMAL);
+    let relative_score = if board.turn == PieceColor::WHITE { score } else { -score };
+    EvalTrace { fen: board.to_fen(), terms, phase, tapered, scale, endgame, score, relative_score }
+}
+
+impl EvalTrace {
+    /// One line of JSON, with each term split by colour and phase.
+    pub fn to_json(&self) -> String {
+        let score = |score: Score| format!("{{\"mg\":{},\"eg\":{}}}", score.mg, score.eg);
+        let terms: Vec<String> = self
+            .terms
+            .named()
+            .iter()
+            .map(|(name, [white, black])| {
+                format!("\"{}\":{{\"white\":{},\"black\":{},\"total\":{}}}", name, score(*white), score(*black), score(*white - *black))
+            })
+            .collect();
+        format!(
+            "{{\"fen\":\"{}\",\"terms\":{{{}}},\"total\":{},\"phase\":{},\"max_phase\":{},\"tapered\":{},\"scale\":{},\"scale_normal\":{},\"endgame\":{},\"score\":{},\"relative_score\":{}}}",
+            self.fen,
+            terms.join(","),
+            score(self.terms.total()),
+            self.phase,
+            MAX_PHASE,
+            self.tapered,
+            self.scale,
+            endgame::SCALE_NORMAL,
+            self.endgame.map_or("null".to_string(), |score| score.to_string()),
+            self.score,
+            self.relative_score,
+        )
+    }
+}
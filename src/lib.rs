@@ -0,0 +1,10 @@
+pub mod board;
+pub mod book;
+pub mod eval;
+pub mod movegen;
+pub mod perft;
+pub mod roundtrip;
+pub mod search;
+pub mod tablebase;
+pub mod tune;
+pub mod uci;
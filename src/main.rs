@@ -1,10 +1,201 @@
-use std::collections::HashSet;
-use crate::board::board::Board;
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use rookbot::board::board::Board;
+use rookbot::eval::params::EvalParams;
+use rookbot::eval::trace::trace;
+use rookbot::perft::{perft, perft_bench, perft_bulk, perft_pseudo_legal, perft_validated, run_epd_file};
+use rookbot::roundtrip::check_random_games;
+use rookbot::search::bench::search_bench;
+use rookbot::search::SearchConfig;
+use rookbot::tablebase::dtm::generate::generate;
+use rookbot::tablebase::dtm::probe::DtmTablebases;
+use rookbot::tune::{fit_k, load_dataset, mean_error, tune};
+use rookbot::uci::Uci;
 
-pub mod board;
-pub mod movegen;
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
+#[derive(Parser)]
+#[command(name = "RookBot", version)]
+/// Speaks UCI on stdin and stdout when started without a subcommand
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
+#[derive(Subcommand)]
+enum Command {
+    /// Count leaf nodes of the move tree to the given depth
+    Perft {
+        #[arg(short, long)]
+        depth: u32,
+        #[arg(short, long, default_value = START_FEN)]
+        fen: String,
+        /// Print the node count below every root move
+        #[arg(long)]
+        divide: bool,
+        /// Use the pseudo-legal generator with lazy legality checks
+        #[arg(long)]
+        pseudo_legal: bool,
+        /// Check board invariants after every make and unmake
+        #[arg(long, conflicts_with = "pseudo_legal")]
+        validate: bool,
+    },
+    /// Time the legal and pseudo-legal generators against each other
+    PerftBench {
+        #[arg(short, long, default_value_t = 4)]
+        depth: u32,
+        /// Report the fastest of this many runs of each generator
+        #[arg(short, long, default_value_t = 5)]
+        runs: u32,
+    },
+    /// Search the bench positions to a fixed depth and report the time to depth
+    Bench {
+        #[arg(short, long, default_value_t = 6)]
+        depth: u32,
+        /// Plain alpha-beta without PVS, aspiration windows or pruning, for comparison
+        #[arg(long)]
+        baseline: bool,
+    },
+    /// Verify perft results from an EPD file
+    Epd {
+        file: String,
+    },
+    /// Play random games and check that every move unmakes cleanly
+    Roundtrip {
+        #[arg(short, long, default_value_t = 10)]
+        games: usize,
+        #[arg(short, long, default_value_t = 200)]
+        plies: usize,
+        #[arg(short, long, default_value_t = 1)]
+        seed: u64,
+    },
+    /// Print every evaluation term of a position as JSON
+    Eval {
+        #[arg(short, long, default_value = START_FEN)]
+        fen: String,
+        /// Weights file written by `tune`, instead of the built-in weights
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+    },
+    /// Fit the evaluation weights to game results with Texel's local search
+    Tune {
+        /// Quiet positions, one FEN per line followed by the result
+        dataset: PathBuf,
+        /// Weights to start from, the built-in ones by default
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+        /// Where the weights are written after every pass
+        #[arg(short, long, default_value = "params.txt")]
+        output: PathBuf,
+        #[arg(long, default_value_t = 100)]
+        passes: usize,
+        /// Sigmoid scale, fitted to the starting weights when not given
+        #[arg(short)]
+        k: Option<f64>,
+    },
+    /// Generate DTM tables for an ending and the endings it converts into
+    GenerateDtm {
+        /// Material such as KQvK or KBNK
+        material: String,
+        /// Directory to write the tables to, existing tables are reused
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+    },
+}
 
+fn main() {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Perft { depth, fen, divide, pseudo_legal, validate }) => {
+            let mut board = Board::from_fen(&fen);
+            if let Err(e) = board.validate() {
+                eprintln!("invalid position: {}", e);
+                std::process::exit(1);
+            }
+            if validate {
+                match perft_validated(&mut board, depth) {
+                    Ok(nodes) => println!("{}", nodes),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            } else if divide {
+                println!("{}", perft(&mut board, depth, pseudo_legal));
+            } else if pseudo_legal {
+                println!("{}", perft_pseudo_legal(&mut board, depth));
+            } else {
+                println!("{}", perft_bulk(&mut board, depth));
+            }
+        }
+        Some(Command::PerftBench { depth, runs }) => println!("{}", perft_bench(depth, runs)),
+        Some(Command::Bench { depth, baseline }) => {
+            let config = if baseline { SearchConfig::plain() } else { SearchConfig::default() };
+            println!("{}", search_bench(depth, config));
+        }
+        Some(Command::Epd { file }) => {
+            if let Err(e) = run_epd_file(&file) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Roundtrip { games, plies, seed }) => match check_random_games(games, plies, seed) {
+            Ok(moves) => println!("{} moves checked", moves),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        Some(Command::Eval { fen, params }) => {
+            let params = load_params(params);
+            match Board::try_from_fen(&fen) {
+                Ok(board) => println!("{}", trace(&board, &params).to_json()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Command::Tune { dataset, params, output, passes, k }) => {
+            let mut params = load_params(params);
+            let positions = load_dataset(&dataset).unwrap_or_else(|e| {
+                eprintln!("{}: {}", dataset.display(), e);
+                std::process::exit(1);
+            });
+            let k = k.unwrap_or_else(|| fit_k(&positions, &params));
+            println!("{} positions, K {:.3}, error {:.6}", positions.len(), k, mean_error(&positions, &params, k));
+            tune(&positions, &mut params, k, passes, |pass, error, params| {
+                println!("pass {} error {:.6}", pass, error);
+                if let Err(e) = params.save(&output) {
+                    eprintln!("{}: {}", output.display(), e);
+                    std::process::exit(1);
+                }
+            });
+            println!("wrote {}", output.display());
+        }
+        Some(Command::GenerateDtm { material, output }) => {
+            let mut tables = DtmTablebases::load(&output.to_string_lossy());
+            let existing: Vec<String> = tables.tables().map(|table| table.name()).collect();
+            let written = generate(&material, &mut tables).and_then(|_| {
+                let new_tables = tables.tables().filter(|table| !existing.contains(&table.name()));
+                new_tables.map(|table| table.write(&output).map(|_| table.name())).collect::<Result<Vec<_>, _>>()
+            });
+            match written {
+                Ok(names) => names.iter().for_each(|name| println!("wrote {}", name)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Uci::new().run(),
+    }
+}
 
-fn main() {}
\ No newline at end of file
+fn load_params(path: Option<PathBuf>) -> EvalParams {
+    let Some(path) = path else { return EvalParams::default() };
+    EvalParams::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        std::process::exit(1);
+    })
+}
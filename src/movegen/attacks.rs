@@ -0,0 +1,77 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::piece::{PieceColor, PieceType};
+use crate::board::square::Square;
+use crate::movegen::movedata::MoveData;
+use crate::movegen::magic::functions::{get_bishop_attacks, get_rook_attacks};
+use crate::movegen::precomputed::{KING_MOVES, KNIGHT_MOVES, PAWN_ATTACKS};
+
+impl Board {
+    /// Pieces of both colors attacking `square`, with sliders blocked by `occupancy`.
+    pub fn attackers_to(&self, square: Square, occupancy: Bitboard) -> Bitboard {
+        let of_type = |piece_type: PieceType| {
+            self.get_piece_bitboard(PieceColor::WHITE, piece_type) | self.get_piece_bitboard(PieceColor::BLACK, piece_type)
+        };
+        let queens = of_type(PieceType::QUEEN);
+
+        (PAWN_ATTACKS[PieceColor::BLACK as usize][square] & self.get_piece_bitboard(PieceColor::WHITE, PieceType::PAWN))
+            | (PAWN_ATTACKS[PieceColor::WHITE as usize][square] & self.get_piece_bitboard(PieceColor::BLACK, PieceType::PAWN))
+            | (KNIGHT_MOVES[square] & of_type(PieceType::KNIGHT))
+            | (KING_MOVES[square] & of_type(PieceType::KING))
+            | (get_bishop_attacks(square, occupancy) & (of_type(PieceType::BISHOP) | queens))
+            | (get_rook_attacks(square, occupancy) & (of_type(PieceType::ROOK) | queens))
+    }
+
+    /// Returns true if any piece of `color` attacks `square` in the current position.
+    pub fn is_square_attacked_by(&self, square: Square, color: PieceColor) -> bool {
+        self.attackers_to(square, self.get_all_pieces_bitboard()) & self.get_color_bitboard(color) != 0
+    }
+
+    /// Whether `mv`, legal in the current position, gives check. Uses the
+    /// attack tables on the occupancy after the move, without making it.
+    pub fn gives_check(&self, mv: &MoveData) -> bool {
+        let us = mv.piece_to_move.piece_color;
+        let their_king = self.get_piece_bitboard(us.opposite(), PieceType::KING);
+        if their_king.is_empty() {
+            return false;
+        }
+        let king = their_king.get_single_set_bit();
+        let mut occupancy = self.get_all_pieces_bitboard();
+        occupancy.clear_square(mv.from);
+        occupancy.set_square(mv.to);
+        // Squares left by our pieces, whose sliders may uncover a check
+        let mut moved = Bitboard::new(0);
+        moved.set_square(mv.from);
+        let direct = if let (Some(rook_start), Some(rook_end)) = (mv.get_rook_start(), mv.get_rook_end()) {
+            // The king gives no check itself, the rook may from its new
+            // square. In Chess960 the king may land on the rook's square
+            occupancy.clear_square(rook_start);
+            occupancy.set_square(mv.to);
+            occupancy.set_square(rook_end);
+            moved.set_square(rook_start);
+            get_rook_attacks(rook_end, occupancy)
+        } else {
+            if let Some(captured) = mv.get_capture_square() {
+                occupancy.clear_square(captured);
+                occupancy.set_square(mv.to);
+            }
+            let piece_type = mv.get_promoted_piece().map_or(mv.piece_to_move.piece_type, |piece| piece.piece_type);
+            match piece_type {
+                PieceType::PAWN => PAWN_ATTACKS[us as usize][mv.to],
+                PieceType::KNIGHT => KNIGHT_MOVES[mv.to],
+                PieceType::BISHOP => get_bishop_attacks(mv.to, occupancy),
+                PieceType::ROOK => get_rook_attacks(mv.to, occupancy),
+                PieceType::QUEEN => get_bishop_attacks(mv.to, occupancy) | get_rook_attacks(mv.to, occupancy),
+                PieceType::KING => Bitboard::new(0),
+            }
+        };
+        if direct.contains_square(king) {
+            return true;
+        }
+        // Discovered checks by the sliders left behind, castling included
+        let queens = self.get_piece_bitboard(us, PieceType::QUEEN);
+        let diagonal = (self.get_piece_bitboard(us, PieceType::BISHOP) | queens) & !moved;
+        let straight = (self.get_piece_bitboard(us, PieceType::ROOK) | queens) & !moved;
+        (get_bishop_attacks(king, occupancy) & diagonal) != 0 || (get_rook_attacks(king, occupancy) & straight) != 0
+    }
+}
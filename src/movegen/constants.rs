@@ -49,9 +49,5 @@ pub const ALL_OFSET: [Position; 8] = [
 
 pub(crate) const RANK_1: Bitboard = Bitboard::new(0x00000000000000FF);
 pub(crate) const RANK_2: Bitboard = Bitboard::new(0x000000000000FF00);
-pub(crate) const RANK_3: Bitboard = Bitboard::new(0x0000000000FF0000);
-pub(crate) const RANK_4: Bitboard = Bitboard::new(0x00000000FF000000);
-pub(crate) const RANK_5: Bitboard = Bitboard::new(0x000000FF00000000);
-pub(crate) const RANK_6: Bitboard = Bitboard::new(0x0000FF0000000000);
 pub(crate) const RANK_7: Bitboard = Bitboard::new(0x00FF000000000000);
 pub(crate) const RANK_8: Bitboard = Bitboard::new(0xFF00000000000000);
\ No newline at end of file
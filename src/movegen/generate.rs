@@ -1,14 +1,13 @@
-use std::sync::atomic::AtomicU64;
 use crate::board::bitboard::Bitboard;
 use crate::board::board::Board;
-use crate::board::castling::types::CastlingSide;
+use crate::board::castling::types::{AllowedCastling, CastlingSide};
 use crate::board::piece::{Piece, PieceColor, PieceType};
-use crate::board::position::Position;
-use crate::movegen::constants::{BISHOP_OFFSETS, RANK_1, RANK_2, RANK_7, RANK_8, ROOK_OFFSETS};
+use crate::board::square::Square;
+use crate::movegen::constants::{RANK_1, RANK_8};
 use crate::movegen::magic::functions::{get_bishop_attacks, get_rook_attacks};
 use crate::movegen::movedata::{CastlingMove, MoveData, MoveType, PromotionCapture};
 use crate::movegen::movelist::MoveList;
-use crate::movegen::precomputed::{ALIGN_MASK, DIR_RAY_MASK, NUM_SQUARES_FROM_SQUARE, SQR_A_B_MASK};
+use crate::movegen::precomputed::{BETWEEN, KING_MOVES, KNIGHT_MOVES, LINE};
 
 pub fn generate_all_opp_attacks(board: &Board) ->Bitboard
 {
@@ -22,34 +21,12 @@ pub fn generate_all_opp_attacks(board: &Board) ->Bitboard
     }
 
 
-pub fn get_attacking_pieces(board: &Board, square: u8, piece_color: PieceColor) -> Bitboard {
-    let mut attackers = Bitboard::new(0);
-    let blockers = board.get_all_pieces_bitboard();
-    let opp_pawns=board.get_piece_bitboard(piece_color.opposite(), PieceType::PAWN);
-    let square_bb=Bitboard::create_from_square(square);
-    // Check for pawn attacks
-    let pawn_attackers = square_bb.pawn_attack(piece_color, opp_pawns, true) | square_bb.pawn_attack(piece_color, opp_pawns, false);
-
-    attackers|=pawn_attackers;
-    // Check for knight attacks
-    attackers |= crate::movegen::precomputed::KNIGHT_MOVES[square as usize] & board.get_piece_bitboard(piece_color.opposite(), PieceType::KNIGHT);
-
-    // Check for bishop attacks
-    attackers |= get_bishop_attacks(square as usize, blockers) & board.get_piece_bitboard(piece_color.opposite(), PieceType::BISHOP);
-
-    // Check for rook attacks
-    attackers |= get_rook_attacks(square as usize, blockers) & board.get_piece_bitboard(piece_color.opposite(), PieceType::ROOK);
-
-    // Check for queen attacks
-    attackers |= (get_bishop_attacks(square as usize, blockers) | get_rook_attacks(square as usize, blockers)) & board.get_piece_bitboard(piece_color.opposite(), PieceType::QUEEN);
-
-    // Check for king attacks
-    attackers |= crate::movegen::precomputed::KING_MOVES[square as usize] & board.get_piece_bitboard(piece_color.opposite(), PieceType::KING);
-
-    attackers
+/// Pieces of the opposite color of `piece_color` attacking `square`.
+pub fn get_attacking_pieces(board: &Board, square: Square, piece_color: PieceColor) -> Bitboard {
+    board.attackers_to(square, board.get_all_pieces_bitboard()) & board.get_color_bitboard(piece_color.opposite())
 }
 
-fn generate_piece_attack_bitboard(board: &Board, piece_color: &PieceColor, piece_type: &PieceType) -> Bitboard {
+pub fn generate_piece_attack_bitboard(board: &Board, piece_color: &PieceColor, piece_type: &PieceType) -> Bitboard {
     let blockers = board.get_all_pieces_bitboard() & !board.get_piece_bitboard(piece_color.opposite(), PieceType::KING);
     let piece_bitboard = board.get_piece_bitboard(*piece_color, *piece_type);
 
@@ -57,82 +34,53 @@ fn generate_piece_attack_bitboard(board: &Board, piece_color: &PieceColor, piece
         PieceType::PAWN => {
             piece_bitboard.pawn_attack(*piece_color, Bitboard::new (u64::MAX), true) | piece_bitboard.pawn_attack(*piece_color, Bitboard::new (u64::MAX), false)
         }
-        PieceType::KNIGHT => {
-            let mut attacks = Bitboard::new(0);
-            let mut knights = piece_bitboard;
-            while knights != 0 {
-                let from_sqr = knights.pop_lsb();
-                attacks |= crate::movegen::precomputed::KNIGHT_MOVES[from_sqr as usize];
-            }
-            attacks
-        }
-        PieceType::BISHOP => {
-            let mut attacks = Bitboard::new(0);
-            let mut bishops = piece_bitboard;
-            while bishops != 0 {
-                let from_sqr = bishops.pop_lsb();
-                attacks |= get_bishop_attacks(from_sqr as usize, blockers);
-            }
-            attacks
-        }
-        PieceType::ROOK => {
-            let mut attacks = Bitboard::new(0);
-            let mut rooks = piece_bitboard;
-            while rooks != 0 {
-                let from_sqr = rooks.pop_lsb();
-                attacks |= get_rook_attacks(from_sqr as usize, blockers);
-            }
-            attacks
-        }
-        PieceType::QUEEN => {
-            let mut attacks = Bitboard::new(0);
-            let mut queens = piece_bitboard;
-            while queens != 0 {
-                let from_sqr = queens.pop_lsb();
-                attacks |= get_bishop_attacks(from_sqr as usize, blockers) | get_rook_attacks(from_sqr as usize, blockers);
-            }
-            attacks
-        }
-        PieceType::KING => {
-            let mut attacks = Bitboard::new(0);
-            let mut kings = piece_bitboard;
-            while kings != 0 {
-                let from_sqr = kings.pop_lsb();
-                attacks |= crate::movegen::precomputed::KING_MOVES[from_sqr as usize];
-            }
-            attacks
-        }
+        PieceType::KNIGHT => piece_bitboard.iter().fold(Bitboard::new(0), |attacks, sqr| attacks | KNIGHT_MOVES[sqr]),
+        PieceType::BISHOP => piece_bitboard.iter().fold(Bitboard::new(0), |attacks, sqr| attacks | get_bishop_attacks(sqr, blockers)),
+        PieceType::ROOK => piece_bitboard.iter().fold(Bitboard::new(0), |attacks, sqr| attacks | get_rook_attacks(sqr, blockers)),
+        PieceType::QUEEN => piece_bitboard.iter().fold(Bitboard::new(0), |attacks, sqr| {
+            attacks | get_bishop_attacks(sqr, blockers) | get_rook_attacks(sqr, blockers)
+        }),
+        PieceType::KING => piece_bitboard.iter().fold(Bitboard::new(0), |attacks, sqr| attacks | KING_MOVES[sqr]),
     }
 }
 pub fn update_check_status(board: &mut Board) {
+    let attackers = if board.attacked_square.contains_square(board.curr_king) {
+        get_attacking_pieces(board, board.curr_king, board.turn)
+    } else {
+        Bitboard::new(0)
+    };
+    set_checkers(board, attackers);
+}
+// Sets the check flags, and the squares a move other than the king's must
+// land on to answer a single check
+fn set_checkers(board: &mut Board, attackers: Bitboard) {
     board.is_double_check = false;
     let king_square = board.curr_king;
-    board.is_check = board.attacked_square.contains_square(king_square);
+    board.is_check = attackers != 0;
     if board.is_check {
-        let attackers = get_attacking_pieces(board, king_square, board.turn);
         if attackers.pop_count() > 1 {
             board.is_double_check = true;
         } else {
             let checker_square = attackers.get_single_set_bit();
             let mut valid_moves = Bitboard::new(0);
-            let checker_piece = board.squares[checker_square as usize].unwrap();
+            let checker_piece = board.squares[checker_square].unwrap();
 
             // If the checker is not a sliding piece, the only valid move is to capture the checker
             if checker_piece.piece_type != PieceType::BISHOP &&
                checker_piece.piece_type != PieceType::ROOK &&
                checker_piece.piece_type != PieceType::QUEEN {
                 valid_moves.set_square(checker_square);
-                board.check_ray = Bitboard::new(1 << checker_square)
+                board.check_ray = Bitboard::create_from_square(checker_square)
             } else {
-                // If the checker is a sliding piece, calculate the alignment mask between the king and the checker
-                let align_mask = SQR_A_B_MASK[king_square as usize][checker_square as usize];
+                // If the checker is a sliding piece, the squares between the king and the checker block it
+                let between = BETWEEN[king_square][checker_square];
 
 
                 // Add the checker square to the valid moves
                 valid_moves.set_square(checker_square);
 
-                // Add the alignment mask to the valid moves
-                valid_moves |= align_mask;
+                // Add the blocking squares to the valid moves
+                valid_moves |= between;
 
                 board.check_ray = valid_moves;
             }
@@ -159,7 +107,66 @@ pub fn generate_moves(board: &mut Board) -> MoveList {
     }
     move_list
 }
-fn is_pinned(board: &Board,sqr:u8)->bool{
+/// Generates moves without pin filtering or a map of the opponent's
+/// attacks. Only check evasions are generated when in check, and castling
+/// only when legal. Moves must be checked with `is_legal_after` once made.
+pub fn generate_pseudo_legal(board: &mut Board) -> MoveList {
+    board.curr_king=board.get_piece_bitboard(board.turn, PieceType::KING).get_single_set_bit();
+    board.pinned_ray = Bitboard::new(0);
+    board.attacked_square = Bitboard::new(0);
+    set_checkers(board, get_attacking_pieces(board, board.curr_king, board.turn));
+
+    let mut move_list = MoveList::new();
+    generate_king_steps(board, &mut move_list);
+    if board.is_double_check {
+        return move_list;
+    }
+    if !board.is_check {
+        // Only the squares the king crosses to castle need to be safe
+        let castling_rights = castling_rights(board);
+        for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+            if castling_rights.is_allowed(&side) && board.get_all_pieces_bitboard() & board.castling_config.required_empty(side, board.turn) == 0 {
+                for square in board.castling_config.king_moves_trough(side, board.turn) {
+                    if board.is_square_attacked_by(square, board.turn.opposite()) {
+                        board.attacked_square.set_square(square);
+                    }
+                }
+            }
+        }
+        generate_castling_moves(board, &mut move_list);
+    }
+    generate_knight_move(board, &mut move_list);
+    generate_pawn_moves(board, &mut move_list);
+    get_rook_moves(board, &mut move_list);
+    get_bishop_moves(board, &mut move_list);
+    get_queen_moves(board, &mut move_list);
+    move_list
+}
+/// Must be called right after `board.make_move(mv)` with a move from
+/// `generate_pseudo_legal` or `generate_moves`: returns false if the move
+/// left the mover's king attacked. Both only answer checks with evasions,
+/// so any other move than the king's can only uncover an attack along the
+/// line from the square it left to the king.
+pub fn is_legal_after(board: &Board, mv: &MoveData) -> bool {
+    let mover = mv.piece_to_move.piece_color;
+    let king_square = board.get_piece_bitboard(mover, PieceType::KING).get_single_set_bit();
+    if mv.piece_to_move.piece_type == PieceType::KING || mv.is_en_passant() {
+        return get_attacking_pieces(board, king_square, mover) == 0;
+    }
+    let them = mover.opposite();
+    let queens = board.get_piece_bitboard(them, PieceType::QUEEN);
+    let occupancy = board.get_all_pieces_bitboard();
+    let (files, ranks) = (mv.from.file_distance(king_square), mv.from.rank_distance(king_square));
+    let attackers = if files == 0 || ranks == 0 {
+        get_rook_attacks(king_square, occupancy) & (board.get_piece_bitboard(them, PieceType::ROOK) | queens)
+    } else if files == ranks {
+        get_bishop_attacks(king_square, occupancy) & (board.get_piece_bitboard(them, PieceType::BISHOP) | queens)
+    } else {
+        return true;
+    };
+    attackers.is_empty()
+}
+fn is_pinned(board: &Board,sqr:Square)->bool{
      board.pinned_ray.contains_square(sqr)
 }
 pub fn generate_knight_move(board: &Board, move_list: &mut MoveList) {
@@ -168,20 +175,20 @@ pub fn generate_knight_move(board: &Board, move_list: &mut MoveList) {
     let blockers=board.get_color_bitboard(board.turn);
     while *knights != 0  {
         let from_sqr=knights.pop_lsb();
-        let mut moves=crate::movegen::precomputed::KNIGHT_MOVES[from_sqr as usize] & !blockers & board.check_ray;
+        let mut moves=KNIGHT_MOVES[from_sqr] & !blockers & board.check_ray;
         if is_pinned(board, from_sqr) {
-            moves &= ALIGN_MASK[from_sqr as usize][board.curr_king as usize];
+            moves &= LINE[from_sqr][board.curr_king];
         }
         let mut captures= moves & opp_pieces;
         moves &= !captures;
         while moves!=0 {
             let to_sqr = moves.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Normal);
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Normal);
             move_list.add_move(curr_move);
         }
         while captures!=0 {
             let to_sqr = captures.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Capture(board.squares[to_sqr as usize].unwrap()));
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Capture(board.squares[to_sqr].unwrap()));
             move_list.add_move(curr_move);
         }
         
@@ -189,126 +196,99 @@ pub fn generate_knight_move(board: &Board, move_list: &mut MoveList) {
    
 }
 pub fn generate_king_move(board: &Board, move_list: &mut MoveList) {
+    generate_king_steps(board, move_list);
+    generate_castling_moves(board, move_list);
+}
+fn generate_king_steps(board: &Board, move_list: &mut MoveList) {
     let kings = &mut board.get_piece_bitboard(board.turn, PieceType::KING);
     let opp_pieces = board.get_color_bitboard(board.turn.opposite());
     let blockers = board.get_color_bitboard(board.turn);
         let from_sqr = kings.pop_lsb();
-        let mut moves = crate::movegen::precomputed::KING_MOVES[from_sqr as usize] & !blockers & !board.attacked_square;
+        let mut moves = KING_MOVES[from_sqr] & !blockers & !board.attacked_square;
         let mut captures = moves & opp_pieces;
         moves&= !captures;
         moves &= !board.get_color_bitboard(board.turn);
         while moves != 0 {
             let to_sqr = moves.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Normal);
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Normal);
             move_list.add_move(curr_move);
         }
         while captures != 0 {
             let to_sqr = captures.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Capture(board.squares[to_sqr as usize].unwrap()));
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Capture(board.squares[to_sqr].unwrap()));
             move_list.add_move(curr_move);
         }
-    let castling_options=[CastlingSide::Kingside, CastlingSide::Queenside];
-    let castling_rights = if board.turn == PieceColor::WHITE {
+}
+fn castling_rights(board: &Board) -> AllowedCastling {
+    if board.turn == PieceColor::WHITE {
         board.game_state.castle_white
     } else {
         board.game_state.castle_black
-    };
-    
+    }
+}
+fn generate_castling_moves(board: &Board, move_list: &mut MoveList) {
+    let castling_options=[CastlingSide::Kingside, CastlingSide::Queenside];
+    let castling_rights = castling_rights(board);
+    let config = &board.castling_config;
+
     for side in castling_options.iter(){
         if castling_rights.is_allowed(side) && !board.is_check &&
-            (board.get_all_pieces_bitboard() & side.required_empty(board.turn)==0 ) &&(board.attacked_square & side.king_moves_trough(board.turn)==0)
+            (board.get_all_pieces_bitboard() & config.required_empty(*side, board.turn)==0 ) &&(board.attacked_square & config.king_moves_trough(*side, board.turn)==0) &&
+            !castling_rook_shields_king(board, *side)
         {
-            let king_start=side.king_start(board.turn);
+            let king_start=config.king_start(board.turn);
             let king_end=side.king_end(board.turn);
-            let castle_move=MoveData::new(king_start, king_end, board.squares[king_start as usize].unwrap(), MoveType::Castling(CastlingMove::new(*side, board.turn)));
+            let castle_move=MoveData::new(king_start, king_end, board.squares[king_start].unwrap(), MoveType::Castling(CastlingMove::new(*side, board.turn, config.rook_start(*side, board.turn))));
             move_list.add_move(castle_move);
-            
-        } 
-        
+        }
     }
-
+}
+// In Chess960 the castling rook may block a rank attack on the king's destination
+fn castling_rook_shields_king(board: &Board, side: CastlingSide) -> bool {
+    let rook_start = board.castling_config.rook_start(side, board.turn);
+    let blockers = board.get_all_pieces_bitboard() & !Bitboard::create_from_square(rook_start);
+    let enemy_ortho = board.get_piece_bitboard(board.turn.opposite(), PieceType::ROOK) |
+        board.get_piece_bitboard(board.turn.opposite(), PieceType::QUEEN);
+    get_rook_attacks(side.king_end(board.turn), blockers) & enemy_ortho != 0
 }
 pub fn find_pinned_pieces(board: &Board) -> Bitboard {
-    let mut pinned_ray = Bitboard::new(0);
-    let king_square = Position::from_sqr(board.curr_king as i8).unwrap();
+    let mut pinned = Bitboard::new(0);
+    let king_square = board.curr_king;
     let opponent_color = board.turn.opposite();
-
-    // Iterate over all 8 directions
-    for (dir_index,dir) in ROOK_OFFSETS.iter().chain(BISHOP_OFFSETS.iter()).enumerate() {
-        let king_ray = DIR_RAY_MASK[king_square.to_sqr().unwrap() as usize][dir_index];
-
-        // Get opponent's sliding pieces in this direction
-        let opponent_sliders = match dir_index {
-            0..=3 => board.get_piece_bitboard(opponent_color, PieceType::ROOK) | board.get_piece_bitboard(opponent_color, PieceType::QUEEN),
-            _ => board.get_piece_bitboard(opponent_color, PieceType::BISHOP) | board.get_piece_bitboard(opponent_color, PieceType::QUEEN),
-        };
-
-        // Find overlapping rays
-        let overlap_ray = king_ray & opponent_sliders;
-
-        // Determine pinned pieces
-        if overlap_ray != Bitboard::new(0) {
-            let mut pieces_between = Bitboard::new(0);
-            let mut found_slider = false;
-            let mut found_friendly = false;
-
-
-
-            for index in 0..NUM_SQUARES_FROM_SQUARE[dir_index][king_square.to_sqr().unwrap() as usize]
-            {
-                let sq =  (king_square + (*dir * (index+1) )).to_sqr().unwrap() ;
-                if board.squares[sq as usize].is_some() {
-                    let piece = board.squares[sq as usize].unwrap();
-                    if piece.piece_color == board.turn {
-                        if found_friendly {
-                            found_friendly = false;
-                            break;
-                        }
-                        found_friendly = true;
-                    } else if piece.piece_color == opponent_color && is_correct_slider(piece, dir_index) {
-                        found_slider = true;
-                            break;
-
-
-                    }
-                    else{
-                        break;
-                    }
-                    pieces_between.set_square(sq as u8);
-                }
-            }
-
-            if found_friendly && found_slider {
-                pinned_ray |= pieces_between;
-            }
+    let opponent_pieces = board.get_color_bitboard(opponent_color);
+    let opponent_queens = board.get_piece_bitboard(opponent_color, PieceType::QUEEN);
+
+    // Sliders that would attack the king if our own pieces were transparent
+    let snipers = (get_rook_attacks(king_square, opponent_pieces)
+        & (board.get_piece_bitboard(opponent_color, PieceType::ROOK) | opponent_queens))
+        | (get_bishop_attacks(king_square, opponent_pieces)
+        & (board.get_piece_bitboard(opponent_color, PieceType::BISHOP) | opponent_queens));
+
+    for sniper_square in snipers {
+        let blockers = Bitboard::between(board.curr_king, sniper_square) & board.get_all_pieces_bitboard();
+        if !blockers.is_empty() && !blockers.more_than_one() {
+            pinned |= blockers & board.get_color_bitboard(board.turn);
         }
     }
 
-    pinned_ray
+    pinned
 }
-fn get_promotion_bitboard(pawns:&Bitboard, color: PieceColor) -> Bitboard {
+fn get_promotion_bitboard(color: PieceColor) -> Bitboard {
     if color == PieceColor::WHITE { RANK_8} else { RANK_1 }
 
 }
-fn is_correct_slider(slider:Piece, dir_index:usize)->bool{
-    match dir_index {
-        0..=3=>slider.piece_type==PieceType::ROOK || slider.piece_type==PieceType::QUEEN,
-        _=>slider.piece_type==PieceType::BISHOP || slider.piece_type==PieceType::QUEEN,
-    }
-}
 fn get_pawn_dir(color: PieceColor) -> i8 {
     if color == PieceColor::WHITE { 1 } else { -1 }
 }
-fn get_pawn_attack_dir(color: PieceColor, left: bool) -> i8 {
-    if color == PieceColor::WHITE {
-        if left { 7 } else { 9 }
-    } else if left { -7 } else { -9 }
+// File offset of a capture, seen from white's side of the board.
+fn get_pawn_attack_file_dir(color: PieceColor, left: bool) -> i8 {
+    if (color == PieceColor::WHITE) == left { -1 } else { 1 }
 }
 pub fn generate_pawn_moves(board: &Board, move_list: &mut MoveList) {
     let pawns = &mut board.get_piece_bitboard(board.turn, PieceType::PAWN);
     let opp_pieces = board.get_color_bitboard(board.turn.opposite());
     let blockers = board.get_all_pieces_bitboard();
-    let promotion_bitboard = get_promotion_bitboard(pawns, board.turn);
+    let promotion_bitboard = get_promotion_bitboard(board.turn);
 
     let mut double_pushes = pawns.pawn_double_push(&board.turn, blockers) & board.check_ray;
     let mut single_pushes = pawns.pawn_push(&board.turn) & !blockers & !opp_pieces & board.check_ray;
@@ -317,12 +297,12 @@ pub fn generate_pawn_moves(board: &Board, move_list: &mut MoveList) {
     while single_pushes != 0
     {
         let end_sq = single_pushes.pop_lsb();
-        let start_sq = (end_sq as i8 - (8 * get_pawn_dir(board.turn))) as u8;
-        if !is_pinned(board, start_sq) || ALIGN_MASK[start_sq as usize][board.curr_king as usize] == ALIGN_MASK[end_sq as usize][board.curr_king as usize] {
+        let start_sq = end_sq.offset(0, -get_pawn_dir(board.turn)).unwrap();
+        if !is_pinned(board, start_sq) || LINE[start_sq][board.curr_king] == LINE[end_sq][board.curr_king] {
             let curr_move = MoveData::new(
                 start_sq,
                 end_sq,
-                board.squares[start_sq as usize].unwrap(),
+                board.squares[start_sq].unwrap(),
                 MoveType::Normal
             );
             move_list.add_move(curr_move)
@@ -331,17 +311,17 @@ pub fn generate_pawn_moves(board: &Board, move_list: &mut MoveList) {
     while single_pushes_promote != 0
     {
         let end_sq = single_pushes_promote.pop_lsb();
-        let start_sq = (end_sq as i8 - (8 * get_pawn_dir(board.turn))) as u8;
-        if !is_pinned(board, start_sq) || ALIGN_MASK[start_sq as usize][board.curr_king as usize] == ALIGN_MASK[end_sq as usize][board.curr_king as usize] {
+        let start_sq = end_sq.offset(0, -get_pawn_dir(board.turn)).unwrap();
+        if !is_pinned(board, start_sq) || LINE[start_sq][board.curr_king] == LINE[end_sq][board.curr_king] {
             generate_promote_moves(board, start_sq, end_sq, move_list, board.turn);
         }
     }
     while double_pushes != 0
     {
         let end_sq = double_pushes.pop_lsb();
-        let start_sq = (end_sq as i8 - (16 * get_pawn_dir(board.turn))) as u8;
-        let curr_move = MoveData::new(start_sq, end_sq, board.squares[start_sq as usize].unwrap(), MoveType::Normal);
-        if !is_pinned(board, start_sq) || ALIGN_MASK[start_sq as usize][board.curr_king as usize] == ALIGN_MASK[end_sq as usize][board.curr_king as usize] {
+        let start_sq = end_sq.offset(0, -2 * get_pawn_dir(board.turn)).unwrap();
+        let curr_move = MoveData::new(start_sq, end_sq, board.squares[start_sq].unwrap(), MoveType::Normal);
+        if !is_pinned(board, start_sq) || LINE[start_sq][board.curr_king] == LINE[end_sq][board.curr_king] {
             move_list.add_move(curr_move);
         }
     }
@@ -353,13 +333,13 @@ pub fn generate_pawn_moves(board: &Board, move_list: &mut MoveList) {
         while attacks != 0
         {
             let end_sq = attacks.pop_lsb();
-            let start_sq = (end_sq as i8 - (get_pawn_attack_dir(board.turn, *left))) as u8;
-            if !is_pinned(board, start_sq) || ALIGN_MASK[start_sq as usize][board.curr_king as usize] == ALIGN_MASK[end_sq as usize][board.curr_king as usize] {
+            let start_sq = end_sq.offset(-get_pawn_attack_file_dir(board.turn, *left), -get_pawn_dir(board.turn)).unwrap();
+            if !is_pinned(board, start_sq) || LINE[start_sq][board.curr_king] == LINE[end_sq][board.curr_king] {
                let curr_move = MoveData::new(
     start_sq,
     end_sq,
-    board.squares[start_sq as usize].unwrap(),
-    MoveType::Capture(board.squares[end_sq as usize].unwrap())
+    board.squares[start_sq].unwrap(),
+    MoveType::Capture(board.squares[end_sq].unwrap())
 );
                 move_list.add_move(curr_move);
             }
@@ -367,41 +347,43 @@ pub fn generate_pawn_moves(board: &Board, move_list: &mut MoveList) {
         while promote_attacks != 0
         {
             let end_sq = promote_attacks.pop_lsb();
-            let start_sq = (end_sq as i8 - (get_pawn_attack_dir(board.turn, *left))) as u8;
-            if !is_pinned(board, start_sq) || ALIGN_MASK[start_sq as usize][board.curr_king as usize] == ALIGN_MASK[end_sq as usize][board.curr_king as usize] {
-                generate_promote_captures(board, start_sq, end_sq, move_list, board.turn, board.squares[end_sq as usize].unwrap());
+            let start_sq = end_sq.offset(-get_pawn_attack_file_dir(board.turn, *left), -get_pawn_dir(board.turn)).unwrap();
+            if !is_pinned(board, start_sq) || LINE[start_sq][board.curr_king] == LINE[end_sq][board.curr_king] {
+                generate_promote_captures(board, start_sq, end_sq, move_list, board.turn, board.squares[end_sq].unwrap());
             }
         }
     }
     if let Some(en_passant_square) = board.game_state.en_passant_square
     {
-        let en_passant_target = (en_passant_square as i8 - (8 * get_pawn_dir(board.turn))) as u8;
-        let mut en_passent_bitboard=Bitboard::create_from_square(en_passant_square );
+        let en_passant_target = en_passant_square.offset(0, -get_pawn_dir(board.turn)).unwrap();
+        let en_passent_bitboard=Bitboard::create_from_square(en_passant_square );
         let  mut pawns_can_capture = en_passent_bitboard.pawn_attack(board.turn.opposite(), *pawns, true) | en_passent_bitboard.pawn_attack(board.turn.opposite(), *pawns, false);
        // let mut pawns_can_capture = pawns_attack_pattern & *pawns;
         while pawns_can_capture != 0
         {
             let start_sq = pawns_can_capture.pop_lsb();
-            if !is_pinned(board, start_sq) || ALIGN_MASK[start_sq as usize][board.curr_king as usize] == ALIGN_MASK[en_passant_target as usize][board.curr_king as usize] {
-                if !in_check_after_en_passant(board, start_sq, en_passant_square, en_passant_target) {
-                    let curr_move = MoveData::new(start_sq, en_passant_square, board.squares[start_sq as usize].unwrap(), MoveType::EnPassant(board.squares[en_passant_target as usize].unwrap(), en_passant_target));
-                    move_list.add_move(curr_move);
-                }
+            // In check, the capture must block the check or take the checker
+            let resolves_check = board.check_ray & (en_passent_bitboard | Bitboard::create_from_square(en_passant_target)) != 0;
+            if resolves_check
+                && (!is_pinned(board, start_sq) || LINE[start_sq][board.curr_king] == LINE[en_passant_square][board.curr_king])
+                && !in_check_after_en_passant(board, start_sq, en_passant_square, en_passant_target) {
+                let curr_move = MoveData::new(start_sq, en_passant_square, board.squares[start_sq].unwrap(), MoveType::EnPassant(board.squares[en_passant_target].unwrap(), en_passant_target));
+                move_list.add_move(curr_move);
             }
         }
     }
 }
-fn generate_promote_moves(board: &Board,start_square:u8, end_square:u8, move_list: &mut MoveList, color: PieceColor) {
+fn generate_promote_moves(board: &Board,start_square:Square, end_square:Square, move_list: &mut MoveList, color: PieceColor) {
     let promote_pieces = [PieceType::QUEEN, PieceType::ROOK, PieceType::BISHOP, PieceType::KNIGHT];
     for piece in promote_pieces.iter() {
-        let curr_move = MoveData::new(start_square, end_square, board.squares[start_square as usize].unwrap(), MoveType::Promotion(Piece::new(color, *piece)));
+        let curr_move = MoveData::new(start_square, end_square, board.squares[start_square].unwrap(), MoveType::Promotion(Piece::new(color, *piece)));
         move_list.add_move(curr_move);
     }
 }
-pub fn generate_promote_captures(board:&Board,start_square:u8, end_square:u8, move_list: &mut MoveList, color: PieceColor, captured_piece: Piece) {
+pub fn generate_promote_captures(board:&Board,start_square:Square, end_square:Square, move_list: &mut MoveList, color: PieceColor, captured_piece: Piece) {
     let promote_pieces = [PieceType::QUEEN, PieceType::ROOK, PieceType::BISHOP, PieceType::KNIGHT];
     for piece in promote_pieces.iter() {
-        let curr_move = MoveData::new(start_square, end_square, board.squares[start_square as usize].unwrap() , MoveType::PromotionCapture(PromotionCapture { captured_piece, promoted_piece: Piece::new(color, *piece) }));
+        let curr_move = MoveData::new(start_square, end_square, board.squares[start_square].unwrap() , MoveType::PromotionCapture(PromotionCapture { captured_piece, promoted_piece: Piece::new(color, *piece) }));
         move_list.add_move(curr_move);
     }
 }
@@ -412,20 +394,20 @@ pub fn get_rook_moves(board: &Board, move_list: &mut MoveList) {
     
     while *rooks != 0 {
         let from_sqr = rooks.pop_lsb();
-        let mut moves = get_rook_attacks(from_sqr as usize, blockers) & board.check_ray & !board.get_color_bitboard(board.turn);
+        let mut moves = get_rook_attacks(from_sqr, blockers) & board.check_ray & !board.get_color_bitboard(board.turn);
         if is_pinned(board, from_sqr) {
-            moves &= ALIGN_MASK[from_sqr as usize][board.curr_king as usize];
+            moves &= LINE[from_sqr][board.curr_king];
         }
         let mut captures = moves & opp_pieces;
         moves &= !captures;
         while moves != 0 {
             let to_sqr = moves.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Normal);
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Normal);
             move_list.add_move(curr_move);
         }
         while captures != 0 {
             let to_sqr = captures.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Capture(board.squares[to_sqr as usize].unwrap()));
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Capture(board.squares[to_sqr].unwrap()));
             move_list.add_move(curr_move);
         }
     }
@@ -437,20 +419,20 @@ pub fn get_bishop_moves(board: &Board, move_list: &mut MoveList) {
     let our_pieces=board.get_color_bitboard(board.turn);
     while *bishops != 0 {
         let from_sqr = bishops.pop_lsb();
-        let mut moves = get_bishop_attacks(from_sqr as usize, blockers) & board.check_ray & !our_pieces;
+        let mut moves = get_bishop_attacks(from_sqr, blockers) & board.check_ray & !our_pieces;
         if is_pinned(board, from_sqr) {
-            moves &= ALIGN_MASK[from_sqr as usize][board.curr_king as usize];
+            moves &= LINE[from_sqr][board.curr_king];
         }
         let mut captures = moves & opp_pieces;
         moves &= !captures;
         while moves != 0 {
             let to_sqr = moves.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Normal);
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Normal);
             move_list.add_move(curr_move);
         }
         while captures != 0 {
             let to_sqr = captures.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Capture(board.squares[to_sqr as usize].unwrap()));
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Capture(board.squares[to_sqr].unwrap()));
             move_list.add_move(curr_move);
         }
     }
@@ -462,27 +444,27 @@ pub fn get_queen_moves(board: &Board, move_list: &mut MoveList) {
     let our_pieces=board.get_color_bitboard(board.turn);
     while *queens != 0 {
         let from_sqr = queens.pop_lsb();
-        let mut moves = (get_bishop_attacks(from_sqr as usize, blockers) | get_rook_attacks(from_sqr as usize, blockers)) & board.check_ray;
+        let mut moves = (get_bishop_attacks(from_sqr, blockers) | get_rook_attacks(from_sqr, blockers)) & board.check_ray;
         moves &=!our_pieces;
         if is_pinned(board, from_sqr) {
-            moves &= ALIGN_MASK[from_sqr as usize][board.curr_king as usize];
+            moves &= LINE[from_sqr][board.curr_king];
         }
         let mut captures = moves & opp_pieces;
         moves &= !captures;
         while moves != 0 {
             let to_sqr = moves.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Normal);
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Normal);
             move_list.add_move(curr_move);
         }
         while captures != 0 {
             let to_sqr = captures.pop_lsb();
-            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr as usize].unwrap(), MoveType::Capture(board.squares[to_sqr as usize].unwrap()));
+            let curr_move = MoveData::new(from_sqr, to_sqr, board.squares[from_sqr].unwrap(), MoveType::Capture(board.squares[to_sqr].unwrap()));
             move_list.add_move(curr_move);
         }
     }
     
 }
-pub fn in_check_after_en_passant(board: &Board, start_square: u8, target_square: u8, ep_capture_square: u8) -> bool {
+pub fn in_check_after_en_passant(board: &Board, start_square: Square, target_square: Square, ep_capture_square: Square) -> bool {
     let enemy_ortho = board.get_piece_bitboard(board.turn.opposite(), PieceType::ROOK) |
         board.get_piece_bitboard(board.turn.opposite(), PieceType::QUEEN);
 
@@ -491,7 +473,7 @@ pub fn in_check_after_en_passant(board: &Board, start_square: u8, target_square:
             (Bitboard::create_from_square(ep_capture_square) |
                 Bitboard::create_from_square(start_square) |
                 Bitboard::create_from_square(target_square));
-        let rook_attacks = get_rook_attacks(board.curr_king as usize, masked_blockers);
+        let rook_attacks = get_rook_attacks(board.curr_king, masked_blockers);
         return (rook_attacks & enemy_ortho) != Bitboard::new(0);
     }
 
@@ -0,0 +1,205 @@
+use std::fmt;
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::castling::types::CastlingSide;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::{Rank, Square};
+use crate::movegen::generate::{generate_moves, in_check_after_en_passant};
+use crate::movegen::magic::functions::{get_bishop_attacks, get_rook_attacks};
+use crate::movegen::movedata::MoveData;
+use crate::movegen::precomputed::{KING_MOVES, KNIGHT_MOVES, LINE};
+
+/// Reason an externally supplied move was rejected.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    InvalidFormat,
+    NoPiece(Square),
+    WrongColor(Square),
+    MissingPromotion,
+    InvalidPromotion,
+    CastlingNotAllowed(CastlingSide),
+    IllegalPieceMove,
+    Pinned,
+    KingInCheck,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::InvalidFormat => write!(f, "invalid move format"),
+            MoveError::NoPiece(sqr) => write!(f, "no piece on {}", sqr),
+            MoveError::WrongColor(sqr) => write!(f, "piece on {} belongs to the side not to move", sqr),
+            MoveError::MissingPromotion => write!(f, "pawn move to last rank requires a promotion piece"),
+            MoveError::InvalidPromotion => write!(f, "invalid promotion"),
+            MoveError::CastlingNotAllowed(side) => write!(f, "{:?} castling not allowed", side),
+            MoveError::IllegalPieceMove => write!(f, "piece cannot move to that square"),
+            MoveError::Pinned => write!(f, "piece is pinned"),
+            MoveError::KingInCheck => write!(f, "king in check"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl Board {
+    /// Parses a move in UCI long algebraic notation (e.g. "e2e4", "e7e8q", "e1g1")
+    /// and returns the matching legal move, or the reason it is illegal.
+    /// Castling may also be given as king takes rook ("e1h1"), which is the
+    /// only accepted form when `chess960` is set.
+    pub fn parse_uci_move(&mut self, uci: &str) -> Result<MoveData, MoveError> {
+        if !(4..=5).contains(&uci.len()) || !uci.is_ascii() {
+            return Err(MoveError::InvalidFormat);
+        }
+        let from = parse_square(&uci[0..2])?;
+        let to = parse_square(&uci[2..4])?;
+        let promotion = match uci.chars().nth(4) {
+            Some(c) => Some(match c.to_ascii_lowercase() {
+                'q' => PieceType::QUEEN,
+                'r' => PieceType::ROOK,
+                'b' => PieceType::BISHOP,
+                'n' => PieceType::KNIGHT,
+                _ => return Err(MoveError::InvalidPromotion),
+            }),
+            None => None,
+        };
+
+        let piece = self.squares[from].ok_or(MoveError::NoPiece(from))?;
+        if piece.piece_color != self.turn {
+            return Err(MoveError::WrongColor(from));
+        }
+
+        let castling_side = self.castling_side_of(from, to);
+        let move_list = generate_moves(self);
+        let found = move_list.iter().find(|mv| match castling_side {
+            Some(side) => mv.get_castling_side() == Some(side),
+            None => {
+                mv.from == from
+                    && mv.to == to
+                    && !mv.is_castling()
+                    && mv.get_promoted_piece().map(|p| p.piece_type) == promotion
+            }
+        });
+        if let Some(mv) = found.copied() {
+            if castling_side.is_some() && promotion.is_some() {
+                return Err(MoveError::InvalidPromotion);
+            }
+            if !self.keeps_king_safe(&mv) {
+                return Err(MoveError::KingInCheck);
+            }
+            return Ok(mv);
+        }
+        Err(self.diagnose_illegal_move(from, to, promotion, castling_side))
+    }
+
+    // Castling is written as king takes own rook, or in standard chess also
+    // as the king moving two squares.
+    fn castling_side_of(&self, from: Square, to: Square) -> Option<CastlingSide> {
+        let piece = self.squares[from]?;
+        if piece.piece_type != PieceType::KING || from != self.castling_config.king_start(piece.piece_color) {
+            return None;
+        }
+        let is_castling_rook = self.squares[to] == Some(Piece::new(piece.piece_color, PieceType::ROOK));
+        if let Some(side) = self.castling_config.side_of_rook(to, piece.piece_color).filter(|_| is_castling_rook) {
+            return Some(side);
+        }
+        if !self.chess960 && from.file_distance(to) == 2 && from.rank() == to.rank() {
+            return Some(if to > from { CastlingSide::Kingside } else { CastlingSide::Queenside });
+        }
+        None
+    }
+
+    /// Returns true if `mv` is one of the legal moves in the current position.
+    /// Besides being generated, the move is played to check that it does
+    /// not leave the king attacked, so that a generator bug cannot let an
+    /// illegal move from a user through.
+    pub fn is_legal(&mut self, mv: &MoveData) -> bool {
+        generate_moves(self).is_move_in_list(mv) && self.keeps_king_safe(mv)
+    }
+
+    // Tests every attacker rather than trusting the generator's evasions
+    fn keeps_king_safe(&mut self, mv: &MoveData) -> bool {
+        self.make_move(mv);
+        let king = self.get_piece_bitboard(mv.piece_to_move.piece_color, PieceType::KING).get_single_set_bit();
+        let safe = !self.is_square_attacked_by(king, self.turn);
+        self.unmake_move(mv);
+        safe
+    }
+
+    // Called after `generate_moves`, so the check and pin state is up to date.
+    fn diagnose_illegal_move(&self, from: Square, to: Square, promotion: Option<PieceType>, castling_side: Option<CastlingSide>) -> MoveError {
+        let piece = self.squares[from].unwrap();
+        let to_bb = Bitboard::create_from_square(to);
+
+        if let Some(side) = castling_side {
+            let rights = if self.turn == PieceColor::WHITE { self.game_state.castle_white } else { self.game_state.castle_black };
+            if promotion.is_some() {
+                return MoveError::InvalidPromotion;
+            }
+            if !rights.is_allowed(&side) {
+                return MoveError::CastlingNotAllowed(side);
+            }
+            if self.is_check || self.attacked_square & self.castling_config.king_moves_trough(side, self.turn) != 0 {
+                return MoveError::KingInCheck;
+            }
+            return MoveError::IllegalPieceMove;
+        }
+
+        if (self.reachable_squares(from) & to_bb) == 0 {
+            return MoveError::IllegalPieceMove;
+        }
+
+        let is_last_rank = to.rank() == Rank::First || to.rank() == Rank::Eighth;
+        match (piece.piece_type == PieceType::PAWN && is_last_rank, promotion) {
+            (true, None) => return MoveError::MissingPromotion,
+            (true, Some(PieceType::KING)) | (true, Some(PieceType::PAWN)) | (false, Some(_)) => return MoveError::InvalidPromotion,
+            _ => {}
+        }
+
+        if piece.piece_type == PieceType::KING || self.is_double_check {
+            return MoveError::KingInCheck;
+        }
+        if self.pinned_ray.contains_square(from) && !LINE[from][self.curr_king].contains_square(to) {
+            return MoveError::Pinned;
+        }
+        if self.is_check {
+            return MoveError::KingInCheck;
+        }
+        if piece.piece_type == PieceType::PAWN && self.game_state.en_passant_square == Some(to) {
+            let captured = to.offset(0, if self.turn == PieceColor::WHITE { -1 } else { 1 }).unwrap();
+            if in_check_after_en_passant(self, from, to, captured) {
+                return MoveError::Pinned;
+            }
+        }
+        MoveError::IllegalPieceMove
+    }
+
+    // Squares the piece on `from` could move to ignoring pins and checks.
+    fn reachable_squares(&self, from: Square) -> Bitboard {
+        let piece = self.squares[from].unwrap();
+        let occupancy = self.get_all_pieces_bitboard();
+        let own = self.get_color_bitboard(piece.piece_color);
+        let attacks = match piece.piece_type {
+            PieceType::KNIGHT => KNIGHT_MOVES[from],
+            PieceType::BISHOP => get_bishop_attacks(from, occupancy),
+            PieceType::ROOK => get_rook_attacks(from, occupancy),
+            PieceType::QUEEN => get_bishop_attacks(from, occupancy) | get_rook_attacks(from, occupancy),
+            PieceType::KING => KING_MOVES[from],
+            PieceType::PAWN => {
+                let pawn = Bitboard::create_from_square(from);
+                let mut targets = self.get_color_bitboard(piece.piece_color.opposite());
+                if let Some(ep_square) = self.game_state.en_passant_square {
+                    targets.set_square(ep_square);
+                }
+                pawn.pawn_push(&piece.piece_color) & !occupancy
+                    | pawn.pawn_double_push(&piece.piece_color, occupancy)
+                    | pawn.pawn_attack(piece.piece_color, targets, true)
+                    | pawn.pawn_attack(piece.piece_color, targets, false)
+            }
+        };
+        attacks & !own
+    }
+}
+
+fn parse_square(notation: &str) -> Result<Square, MoveError> {
+    notation.parse().map_err(|_| MoveError::InvalidFormat)
+}
@@ -1,7 +1,9 @@
+pub mod attacks;
 pub mod constants;
 pub mod movedata;
 pub mod movelist;
 pub mod precomputed;
 pub mod generate;
-mod magic;
+pub mod legality;
+pub(crate) mod magic;
 
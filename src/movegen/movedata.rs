@@ -1,4 +1,4 @@
-use crate::board::position::Position;
+use crate::board::square::Square;
 use crate::board::{
     castling::types::CastlingSide,
     piece::{Piece, PieceColor},
@@ -11,23 +11,24 @@ pub enum MoveType {
     Castling(CastlingMove), // Using struct for castling
     Promotion(Piece),
     PromotionCapture(PromotionCapture),
-    EnPassant(Piece, u8), // Piece and the square of the captured pawn
+    EnPassant(Piece, Square), // Piece and the square of the captured pawn
 }
 
 #[derive(Clone, Copy, PartialEq, Eq,Debug)]
 pub struct CastlingMove {
     pub side: CastlingSide,
     pub color: PieceColor,
+    pub rook_start: Square,
 }
 impl CastlingMove {
-    pub fn new(side: CastlingSide, color: PieceColor) -> CastlingMove {
-        CastlingMove { side, color }
+    pub fn new(side: CastlingSide, color: PieceColor, rook_start: Square) -> CastlingMove {
+        CastlingMove { side, color, rook_start }
     }
-    pub fn get_rook_end(&self) -> u8 {
+    pub fn get_rook_end(&self) -> Square {
         self.side.rook_end(self.color)
     }
-    pub fn get_rook_start(&self) -> u8 {
-        self.side.rook_start(self.color)
+    pub fn get_rook_start(&self) -> Square {
+        self.rook_start
     }
     
 }
@@ -39,8 +40,8 @@ pub struct PromotionCapture {
 
 #[derive(Clone, Copy, PartialEq, Eq,Debug)]
 pub struct MoveData {
-    pub from: u8,
-    pub to: u8,
+    pub from: Square,
+    pub to: Square,
     pub piece_to_move: Piece,
     move_type: MoveType,
 }
@@ -48,8 +49,8 @@ pub struct MoveData {
 impl MoveData {
     // Constructor to create a new MoveData instance from algebraic notation
     pub fn new(
-        from: u8,
-        to: u8,
+        from: Square,
+        to: Square,
         piece_to_move: Piece,
         move_type: MoveType,
     ) ->  MoveData{
@@ -85,27 +86,22 @@ impl MoveData {
         )
     }
     pub fn is_double_push(&self) -> bool {
-        let from_pos = Position::from_sqr(self.from as i8).unwrap();
-        let to_pos = Position::from_sqr(self.to as i8).unwrap();
-        let from_rank = from_pos.y;
-        let to_rank = to_pos.y;
-        let diff = (to_rank  - from_rank ).abs();
-        diff == 2
+        self.from.rank_distance(self.to) == 2
     }
 
     // Check if the move is an en passant
     pub fn is_en_passant(&self) -> bool {
         matches!(self.move_type, MoveType::EnPassant(_, _))
     }
-    pub fn get_capture_square(&self) -> Option<u8> {
+    pub fn get_capture_square(&self) -> Option<Square> {
         match &self.move_type {
             MoveType::Capture(_) => Some(self.to),
             MoveType::EnPassant(_, square) => Some(*square),
-            MoveType::PromotionCapture(promo_capture) => Some(self.to),
+            MoveType::PromotionCapture(_) => Some(self.to),
             _ => None,
         }
     }
-    pub fn get_rook_start(&self) -> Option<u8> {
+    pub fn get_rook_start(&self) -> Option<Square> {
         match &self.move_type {
             MoveType::Castling(castling) => Some(castling.get_rook_start()),
             _ => None,
@@ -117,40 +113,41 @@ impl MoveData {
             _ => None,
         }
     }
-    pub fn get_rook_end(&self) -> Option<u8> {
+    pub fn get_rook_end(&self) -> Option<Square> {
         match &self.move_type {
             MoveType::Castling(castling) => Some(castling.get_rook_end()),
             _ => None,
         }
     }
-    // Convert the move to algebraic notation
+    // Convert the move to algebraic notation, e.g. "e7e8=Q"
     pub fn to_algebraic(&self) -> String {
-        let from_pos = Position::from_sqr(self.from as i8).unwrap();
-        let to_pos = Position::from_sqr(self.to as i8).unwrap();
-
-        let from_notation = from_pos.to_chess_notation().unwrap();
-        let to_notation = to_pos.to_chess_notation().unwrap();
-
-        match &self.move_type {
-            MoveType::Promotion(piece) => {
-                format!("{}{}={}", from_notation, to_notation, piece.piece_type.to_char())
-            }
-            MoveType::PromotionCapture(promo) => {
-                format!(
-                    "{}{}={}",
-                    from_notation, to_notation, promo.promoted_piece.piece_type.to_char()
-                )
-            }
-            _ => format!("{}{}", from_notation, to_notation),
+        let uci = self.to_uci(false);
+        match self.get_promoted_piece() {
+            Some(piece) => format!("{}={}", &uci[..4], piece.piece_type.to_char()),
+            None => uci,
+        }
+    }
+    // Convert the move to UCI long algebraic notation, e.g. "e7e8q".
+    // With `chess960` castling is written as king takes rook, e.g. "e1h1"
+    pub fn to_uci(&self, chess960: bool) -> String {
+        let to = match self.get_rook_start() {
+            Some(rook_start) if chess960 => rook_start,
+            _ => self.to,
+        };
+        let from_notation = self.from;
+        let to_notation = to;
+        match self.get_promoted_piece() {
+            Some(piece) => format!("{}{}{}", from_notation, to_notation, piece.piece_type.to_char().to_ascii_lowercase()),
+            None => format!("{}{}", from_notation, to_notation),
         }
     }
     // Get the captured piece if it's a capture move
     pub fn get_captured_piece(&self) -> Option<Piece> {
         match &self.move_type {
-            MoveType::Capture(piece) => Some(piece.clone()),
-            MoveType::EnPassant(piece, _) => Some(piece.clone()),
+            MoveType::Capture(piece) => Some(*piece),
+            MoveType::EnPassant(piece, _) => Some(*piece),
             MoveType::PromotionCapture(ref promo_capture) => {
-                Some(promo_capture.captured_piece.clone())
+                Some(promo_capture.captured_piece)
             }
             _ => None,
         }
@@ -159,9 +156,9 @@ impl MoveData {
     // Get the promoted piece if it's a promotion move
     pub fn get_promoted_piece(&self) -> Option<Piece> {
         match &self.move_type {
-            MoveType::Promotion(piece) => Some(piece.clone()),
+            MoveType::Promotion(piece) => Some(*piece),
             MoveType::PromotionCapture(ref promo_capture) => {
-                Some(promo_capture.promoted_piece.clone())
+                Some(promo_capture.promoted_piece)
             }
             _ => None,
         }
@@ -1,3 +1,4 @@
+use crate::board::square::Square;
 use crate::movegen::constants::MAX_MOVES;
 use crate::movegen::movedata::MoveData;
 
@@ -13,7 +14,7 @@ impl Default for MoveList {
 }
 
 impl MoveList {
-    pub fn iter(&self) -> MoveListIterator {
+    pub fn iter(&self) -> MoveListIterator<'_> {
         MoveListIterator {
             movelist: self,
             index: 0,
@@ -22,6 +23,9 @@ impl MoveList {
     pub fn len(&self) -> usize {
         self.count
     }
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
     pub fn new() -> Self {
         MoveList {
             moves: [None; MAX_MOVES],
@@ -54,15 +58,8 @@ impl MoveList {
             .take(self.count)
             .any(|m| m.as_ref() == Some(mv))
     }
-    pub fn find_move_by_start_end_square(self,from:u8,to:u8)->Option<MoveData>{
-        for i in 0..MAX_MOVES{
-            if let Some(mv)=self.moves[i]{
-                if mv.from==from && mv.to==to{
-                    return Some(mv);
-                }
-            }
-        }
-        None
+    pub fn find_move_by_start_end_square(&self, from: Square, to: Square) -> Option<MoveData> {
+        self.iter().find(|mv| mv.from == from && mv.to == to).copied()
     }
 }
 
@@ -3,39 +3,41 @@ use crate::board::bitboard::Bitboard;
 use crate::board::position::Position;
 use crate::movegen::constants::{BISHOP_OFFSETS, KING_OFFSETS, KNIGHT_OFFSETS, ROOK_OFFSETS};
 
-pub static KNIGHT_MOVES: LazyLock<[Bitboard; 64]> = LazyLock::new(|| {
-    let mut moves = [Bitboard::new(0); 64];
-    for i in 0..64 {
-        if let Some(pos) = Position::from_sqr(i) {
-            let mut curr_move = Bitboard::new(0);
-            for offset in KNIGHT_OFFSETS.iter() {
-                let new_pos = pos + *offset;
-                if let Some(sqr) = new_pos.to_sqr() {
-                    curr_move.set_square(sqr as u8);
-                }
-            }
-            moves[i as usize] = curr_move;
-        }
-    }
-    moves
-});
+// The tables used on every node are built at compile time, so looking
+// them up skips the lazy initialisation check
 
-pub static KING_MOVES: LazyLock<[Bitboard; 64]> = LazyLock::new(|| {
-    let mut moves = [Bitboard::new(0); 64];
-    for i in 0..64 {
-        if let Some(pos) = Position::from_sqr(i) {
-            let mut curr_move = Bitboard::new(0);
-            for offset in KING_OFFSETS.iter() {
-                let new_pos = pos + *offset;
-                if let Some(sqr) = new_pos.to_sqr() {
-                    curr_move.set_square(sqr as u8);
-                }
-            }
-            moves[i as usize] = curr_move;
+pub static KNIGHT_MOVES: [Bitboard; 64] = leaper_table(&KNIGHT_OFFSETS);
+
+pub static KING_MOVES: [Bitboard; 64] = leaper_table(&KING_OFFSETS);
+
+/// Squares attacked by a pawn of the given color standing on each square.
+pub static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_table(&[Position { x: -1, y: 1 }, Position { x: 1, y: 1 }]),
+    leaper_table(&[Position { x: -1, y: -1 }, Position { x: 1, y: -1 }]),
+];
+
+// Bit of the square `offset` away from `square`, zero when off the board.
+const fn offset_bit(square: usize, offset: Position) -> u64 {
+    let x = (square % 8) as i8 + offset.x;
+    let y = (square / 8) as i8 + offset.y;
+    if x >= 0 && x < 8 && y >= 0 && y < 8 { 1 << (y * 8 + x) } else { 0 }
+}
+
+const fn leaper_table(offsets: &[Position]) -> [Bitboard; 64] {
+    let mut table = [Bitboard::new(0); 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < offsets.len() {
+            bits |= offset_bit(square, offsets[i]);
+            i += 1;
         }
+        table[square] = Bitboard::new(bits);
+        square += 1;
     }
-    moves
-});
+    table
+}
 pub static DIR_RAY_MASK: LazyLock<[[Bitboard; 8]; 64]> = LazyLock::new(|| {
     let mut dir_ray_mask = [[Bitboard::new(0); 8]; 64];
 
@@ -46,8 +48,8 @@ pub static DIR_RAY_MASK: LazyLock<[[Bitboard; 8]; 64]> = LazyLock::new(|| {
             let mut current_pos = pos;
             loop {
                 current_pos = current_pos + *offset;
-                if let Some(index) = current_pos.to_sqr() {
-                    ray.set_square(index as u8);
+                if let Some(index) = current_pos.to_square() {
+                    ray.set_square(index);
                 } else {
                     break;
                 }
@@ -58,53 +60,46 @@ pub static DIR_RAY_MASK: LazyLock<[[Bitboard; 8]; 64]> = LazyLock::new(|| {
 
     dir_ray_mask
 });
-pub static ALIGN_MASK: LazyLock<[[Bitboard; 64]; 64]> = LazyLock::new(|| {
-    let mut align_mask = [[Bitboard::new(0); 64]; 64];
-
-    for square_a in 0..64 {
-        for square_b in 0..64 {
-            let pos_a = Position::from_sqr(square_a).unwrap();
-            let pos_b = Position::from_sqr(square_b).unwrap();
-            let delta = pos_b - pos_a;
-            let dir = Position::new(delta.x.signum(), delta.y.signum());
+// Unit step from `a` towards `b` if both lie on a common rank, file or diagonal.
+const fn line_step(a: usize, b: usize) -> Option<Position> {
+    let dx = (b % 8) as i8 - (a % 8) as i8;
+    let dy = (b / 8) as i8 - (a / 8) as i8;
+    let aligned = dx == 0 || dy == 0 || dx.abs() == dy.abs();
+    if aligned && a != b { Some(Position { x: dx.signum(), y: dy.signum() }) } else { None }
+}
 
-            for i in -8..8 {
-                let coord = pos_a + dir * i as i8;
-                if let Some(index) = coord.to_sqr() {
-                    align_mask[square_a as usize][square_b as usize].set_square(index as u8);
-                }
-            }
-        }
-    }
-
-    align_mask
-});
-pub static SQR_A_B_MASK: LazyLock<[[Bitboard; 64]; 64]> = LazyLock::new(|| {
-    let mut align_mask = [[Bitboard::new(0); 64]; 64];
-
-    for square_a in 0..64 {
-        for square_b in 0..64 {
-            let pos_a = Position::from_sqr(square_a).unwrap();
-            let pos_b = Position::from_sqr(square_b).unwrap();
-            let delta = pos_b - pos_a;
-            let dir = Position::new(delta.x.signum(), delta.y.signum());
-
-            for i in 1..8 {
-                let coord = pos_a + dir * i as i8;
-                if let Some(index) = coord.to_sqr()  {
-                    if(index == square_b ){
-                        align_mask[square_a as usize][square_b as usize].set_square(index as u8);
+// Squares strictly between `a` and `b` when `between`, otherwise the whole
+// line through both.
+const fn line_table(between: bool) -> [[Bitboard; 64]; 64] {
+    let mut table = [[Bitboard::new(0); 64]; 64];
+    let mut a = 0;
+    while a < 64 {
+        let mut b = 0;
+        while b < 64 {
+            if let Some(step) = line_step(a, b) {
+                let mut bits = 0;
+                let mut i: i8 = if between { 1 } else { -7 };
+                while i < 8 {
+                    let bit = offset_bit(a, Position { x: step.x * i, y: step.y * i });
+                    if between && bit == 1 << b {
                         break;
                     }
-
-                    align_mask[square_a as usize][square_b as usize].set_square(index as u8);
+                    bits |= bit;
+                    i += 1;
                 }
+                table[a][b] = Bitboard::new(bits);
             }
+            b += 1;
         }
+        a += 1;
     }
+    table
+}
+
+pub static BETWEEN: [[Bitboard; 64]; 64] = line_table(true);
+
+pub static LINE: [[Bitboard; 64]; 64] = line_table(false);
 
-    align_mask
-});
 pub static NUM_SQUARES_FROM_SQUARE: LazyLock<[[u8; 64]; 8]> = LazyLock::new(|| {
     let mut num_squares_from_square = [[0; 64]; 8];
 
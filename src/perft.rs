@@ -1,21 +1,39 @@
 use std::fs::File;
 use std::io;
 use std::io::BufRead;
-use crate::movegen::movelist::MoveList;
-use crate::movegen::movedata::MoveData;
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use crate::board::board::Board;
-use crate::movegen::generate::generate_moves;
-
-pub fn perft(board: &mut Board, depth: u32) -> String {
+use crate::movegen::generate::{generate_moves, generate_pseudo_legal, is_legal_after};
+
+pub const BENCH_POSITIONS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    // En passant is possible but does not answer the discovered check
+    "6b1/8/8/4Pp2/8/8/K7/7k w - f6 0 2",
+];
+
+/// Node count below every root move, then the total. With `pseudo_legal`
+/// the counts come from the pseudo-legal generator, as in
+/// `perft_pseudo_legal`.
+pub fn perft(board: &mut Board, depth: u32, pseudo_legal: bool) -> String {
 
     let mut result = String::new();
     let mut total_nodes = 0;
 
-    let move_list= generate_moves(board);
+    let move_list = if pseudo_legal { generate_pseudo_legal(board) } else { generate_moves(board) };
     for mv in move_list.iter(){
 
         board.make_move(mv);
-        let nodes = perft_recursive(board, depth - 1);
+        if pseudo_legal && !is_legal_after(board, mv) {
+            board.unmake_move(mv);
+            continue;
+        }
+        let nodes = if pseudo_legal { perft_pseudo_legal(board, depth - 1) } else { perft_recursive(board, depth - 1) };
         board.unmake_move(mv);
 
         result.push_str(&format!("{} {}\n", mv.to_algebraic(), nodes));
@@ -58,7 +76,53 @@ pub fn perft_bulk(board: &mut Board, depth: u32) -> u32 {
 
     nodes
 }
-use std::time::Instant;
+
+/// Same as `perft_bulk`, but uses the pseudo-legal generator and tests
+/// legality after each move is made.
+pub fn perft_pseudo_legal(board: &mut Board, depth: u32) -> u32 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let move_list = generate_pseudo_legal(board);
+    let mut nodes = 0;
+
+    for mv in move_list.iter() {
+        board.make_move(mv);
+        if is_legal_after(board, mv) {
+            nodes += perft_pseudo_legal(board, depth - 1);
+        }
+        board.unmake_move(mv);
+    }
+
+    nodes
+}
+
+/// Same as `perft_bulk`, but checks `Board::validate` after every make and
+/// unmake, and that unmaking restores the FEN. Works in release builds too.
+pub fn perft_validated(board: &mut Board, depth: u32) -> Result<u32, String> {
+    if depth == 0 {
+        return Ok(1);
+    }
+
+    let move_list = generate_moves(board);
+    let fen = board.to_fen();
+    let mut nodes = 0;
+
+    for mv in move_list.iter() {
+        let uci = mv.to_uci(board.chess960);
+        board.make_move(mv);
+        board.validate().map_err(|e| format!("{} after {} from {}", e, uci, fen))?;
+        nodes += perft_validated(board, depth - 1)?;
+        board.unmake_move(mv);
+        board.validate().map_err(|e| format!("{} after undoing {} from {}", e, uci, fen))?;
+        if board.to_fen() != fen {
+            return Err(format!("undoing {} gave {} instead of {}", uci, board.to_fen(), fen));
+        }
+    }
+
+    Ok(nodes)
+}
 
 pub fn perft_with_timing(fen: &str, depth: u32) -> String {
     let mut board = Board::from_fen(fen);
@@ -68,11 +132,41 @@ pub fn perft_with_timing(fen: &str, depth: u32) -> String {
 
     format!("time taken (in ms): {}, depth: {}, move count: {}", duration, depth, move_count)
 }
-use std::panic;
 
+/// Runs both generators over `BENCH_POSITIONS` and reports node counts and
+/// timings. Each timing is the fastest of `runs`, taken in turn for both
+/// generators so that a slow spell on the machine hits them alike.
+pub fn perft_bench(depth: u32, runs: u32) -> String {
+    let mut result = String::new();
+    let (mut legal_total, mut pseudo_total) = (0, 0);
+
+    for fen in BENCH_POSITIONS.iter() {
+        let mut board = Board::from_fen(fen);
+        let (mut legal_nodes, mut pseudo_nodes) = (0, 0);
+        let (mut legal_ms, mut pseudo_ms) = (u128::MAX, u128::MAX);
 
+        for _ in 0..runs.max(1) {
+            let start_time = Instant::now();
+            legal_nodes = perft_bulk(&mut board, depth);
+            legal_ms = legal_ms.min(start_time.elapsed().as_millis());
 
-use std::sync::{Arc, Mutex};
+            let start_time = Instant::now();
+            pseudo_nodes = perft_pseudo_legal(&mut board, depth);
+            pseudo_ms = pseudo_ms.min(start_time.elapsed().as_millis());
+        }
+
+        legal_total += legal_ms;
+        pseudo_total += pseudo_ms;
+        let status = if legal_nodes == pseudo_nodes { "ok" } else { "MISMATCH" };
+        result.push_str(&format!(
+            "{}\n  legal: {} nodes in {} ms, pseudo-legal: {} nodes in {} ms [{}]\n",
+            fen, legal_nodes, legal_ms, pseudo_nodes, pseudo_ms, status
+        ));
+    }
+
+    result.push_str(&format!("total time (in ms): legal {}, pseudo-legal {}", legal_total, pseudo_total));
+    result
+}
 
 pub fn check_epd_line(line: &str) -> Result<(), String> {
     let parts: Vec<&str> = line.split(';').collect();
@@ -84,7 +178,7 @@ pub fn check_epd_line(line: &str) -> Result<(), String> {
     let board = Arc::new(Mutex::new(Board::from_fen(fen)));
 
     for depth_and_result in parts.iter().skip(1) {
-        let depth_and_result: Vec<&str> = depth_and_result.trim().split_whitespace().collect();
+        let depth_and_result: Vec<&str> = depth_and_result.split_whitespace().collect();
         if depth_and_result.len() < 2 {
             continue;
         }
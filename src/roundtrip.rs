@@ -0,0 +1,181 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::board::Board;
+use crate::board::castling::types::CastlingSide;
+use crate::board::gamestate::GameState;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::Rank;
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+use crate::perft::BENCH_POSITIONS;
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::PAWN, PieceType::KNIGHT, PieceType::BISHOP, PieceType::ROOK, PieceType::QUEEN, PieceType::KING];
+
+/// Positions with en passant, promotions and Chess960 castling, played in
+/// addition to `BENCH_POSITIONS`.
+pub const ROUNDTRIP_POSITIONS: [&str; 6] = [
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    "8/8/8/2k5/2pP4/8/B7/4K3 b - d3 0 3",
+    "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+    "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+    "1r2k2r/8/8/8/8/8/8/R3K1R1 w GAhb - 0 1",
+];
+
+// Everything make_move touches; history is left out as it only grows.
+#[derive(PartialEq, Debug)]
+struct Snapshot {
+    fen: String,
+    squares: [Option<Piece>; 64],
+    pieces: [[Bitboard; 6]; 2],
+    colors: [Bitboard; 2],
+    all: Bitboard,
+    game_state: GameState,
+    hash: u64,
+    pawn_hash: u64,
+}
+
+impl Snapshot {
+    fn of(board: &Board) -> Snapshot {
+        let colors = [PieceColor::WHITE, PieceColor::BLACK];
+        Snapshot {
+            fen: board.to_fen(),
+            squares: board.squares,
+            pieces: colors.map(|color| PIECE_TYPES.map(|piece_type| board.get_piece_bitboard(color, piece_type))),
+            colors: colors.map(|color| board.get_color_bitboard(color)),
+            all: board.get_all_pieces_bitboard(),
+            game_state: board.game_state,
+            hash: board.get_hash(),
+            pawn_hash: board.get_pawn_hash(),
+        }
+    }
+}
+
+/// Makes and unmakes every legal move. After each make the board must pass
+/// `validate` and equal the board parsed from its own FEN; after each unmake
+/// it must equal the board before the move.
+pub fn check_round_trips(board: &mut Board) -> Result<(), String> {
+    let before = Snapshot::of(board);
+    let move_list = generate_moves(board);
+    for mv in move_list.iter() {
+        let uci = mv.to_uci(board.chess960);
+        board.make_move(mv);
+        board.validate().map_err(|e| format!("{} after {} from {}", e, uci, before.fen))?;
+        let fen = board.to_fen();
+        let reparsed = Board::try_parse_fen(&fen, board.chess960).map_err(|e| format!("{}: {} after {} from {}", fen, e, uci, before.fen))?;
+        if Snapshot::of(&reparsed) != Snapshot::of(board) || !same_castling_squares(&reparsed, board) {
+            return Err(format!("{} does not round-trip after {} from {}", fen, uci, before.fen));
+        }
+        board.unmake_move(mv);
+        let after = Snapshot::of(board);
+        if after != before {
+            return Err(format!("undoing {} from {} gave {:?}", uci, before.fen, after));
+        }
+    }
+    Ok(())
+}
+
+// The FEN only records castling squares for sides that may still castle.
+fn same_castling_squares(a: &Board, b: &Board) -> bool {
+    [(PieceColor::WHITE, a.game_state.castle_white), (PieceColor::BLACK, a.game_state.castle_black)]
+        .into_iter()
+        .all(|(color, rights)| {
+            [CastlingSide::Kingside, CastlingSide::Queenside].iter().filter(|side| rights.is_allowed(side)).all(|side| {
+                a.castling_config.king_start(color) == b.castling_config.king_start(color)
+                    && a.castling_config.rook_start(*side, color) == b.castling_config.rook_start(*side, color)
+            })
+        })
+}
+
+/// Plays `plies` moves picked by `choose` from `board`, checking round trips
+/// at every position. Returns the number of moves checked.
+pub fn check_game(board: &mut Board, plies: usize, mut choose: impl FnMut(usize) -> usize) -> Result<usize, String> {
+    let mut checked = 0;
+    for _ in 0..plies {
+        check_round_trips(board)?;
+        let move_list = generate_moves(board);
+        if move_list.is_empty() {
+            break;
+        }
+        checked += move_list.len();
+        let mv: MoveData = move_list[choose(move_list.len()) % move_list.len()];
+        board.make_move(&mv);
+    }
+    Ok(checked)
+}
+
+/// Plays `games` random games of up to `plies` moves from every position in
+/// `BENCH_POSITIONS` and `ROUNDTRIP_POSITIONS`.
+pub fn check_random_games(games: usize, plies: usize, seed: u64) -> Result<usize, String> {
+    // xorshift64, zero is a fixed point
+    let mut state = seed.max(1);
+    let mut next = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+    let mut checked = 0;
+    for fen in BENCH_POSITIONS.iter().chain(ROUNDTRIP_POSITIONS.iter()) {
+        for _ in 0..games {
+            let mut board = Board::from_fen(fen);
+            checked += check_game(&mut board, plies, &mut next)?;
+        }
+    }
+    Ok(checked)
+}
+
+/// Returns true if moves can be generated for the position: the board state
+/// is consistent, the side not to move is not in check, no pawn stands on
+/// the first or last rank and an en passant square agrees with any check.
+pub fn is_playable(board: &Board) -> bool {
+    let back_ranks = Bitboard::rank_mask(Rank::First) | Bitboard::rank_mask(Rank::Eighth);
+    let pawns = board.get_piece_bitboard(PieceColor::WHITE, PieceType::PAWN) | board.get_piece_bitboard(PieceColor::BLACK, PieceType::PAWN);
+    if board.validate().is_err() || !(pawns & back_ranks).is_empty() {
+        return false;
+    }
+    let waiting = board.turn.opposite();
+    let king = board.get_piece_bitboard(waiting, PieceType::KING).get_single_set_bit();
+    !board.is_square_attacked_by(king, board.turn) && double_push_explains_check(board)
+}
+
+// After a double push the side to move can only be in check from the pushed
+// pawn, or from a slider uncovered by the pawn leaving its start square.
+fn double_push_explains_check(board: &Board) -> bool {
+    let Some(ep_square) = board.game_state.en_passant_square else {
+        return true;
+    };
+    let mover = board.turn.opposite();
+    let (Some(pawn_square), Some(start_square)) = (ep_square.forward(mover), ep_square.forward(board.turn)) else {
+        return false;
+    };
+    let king = board.get_piece_bitboard(board.turn, PieceType::KING).get_single_set_bit();
+    let checkers = board.attackers_to(king, board.get_all_pieces_bitboard()) & board.get_color_bitboard(mover);
+    checkers.iter().all(|checker| checker == pawn_square || Bitboard::between(checker, king).contains_square(start_square))
+}
+
+/// Entry point of the fuzz target. Bytes up to the first newline are parsed
+/// as a FEN, every following byte selects a move. Malformed or unplayable
+/// FENs are ignored; an error means a make/unmake round trip failed.
+pub fn check_fuzz_input(data: &[u8]) -> Result<(), String> {
+    let (fen, moves) = match data.iter().position(|b| *b == b'\n') {
+        Some(end) => (&data[..end], &data[end + 1..]),
+        None => (data, &[][..]),
+    };
+    let Ok(fen) = std::str::from_utf8(fen) else {
+        return Ok(());
+    };
+    let Ok(mut board) = Board::try_from_fen(fen) else {
+        return Ok(());
+    };
+    if !is_playable(&board) {
+        return Ok(());
+    }
+    let to_fen = board.to_fen();
+    let reparsed = Board::try_from_fen(&to_fen).map_err(|e| format!("{} from {}: {}", to_fen, fen, e))?;
+    if reparsed.to_fen() != to_fen {
+        return Err(format!("{} does not round-trip, parsed from {}", to_fen, fen));
+    }
+    let mut choices = moves.iter().map(|b| *b as usize);
+    check_game(&mut board, moves.len(), |_| choices.next().unwrap_or(0))?;
+    Ok(())
+}
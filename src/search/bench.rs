@@ -0,0 +1,26 @@
+use std::time::Instant;
+use crate::board::board::Board;
+use crate::perft::BENCH_POSITIONS;
+use super::limits::SearchLimits;
+use super::{SearchConfig, Searcher};
+
+/// Searches every bench position to `depth` and reports the time to depth
+/// and the nodes searched.
+pub fn search_bench(depth: u32, config: SearchConfig) -> String {
+    let mut result = String::new();
+    let (mut total_nodes, mut total_ms) = (0, 0);
+    for fen in BENCH_POSITIONS.iter() {
+        let mut board = Board::from_fen(fen);
+        let mut searcher = Searcher::new(config);
+        let start_time = Instant::now();
+        let search = searcher.search(&mut board, &SearchLimits::depth(depth), &[], |_| {});
+        let ms = start_time.elapsed().as_millis();
+        total_nodes += search.nodes;
+        total_ms += ms;
+        let best = search.best_move.map_or("none".to_string(), |mv| mv.to_uci(board.chess960));
+        result.push_str(&format!("{}\n  depth {} in {} ms, {} nodes, score {}, best {}\n", fen, search.depth, ms, search.nodes, search.score, best));
+    }
+    let nps = total_nodes as u128 * 1000 / total_ms.max(1);
+    result.push_str(&format!("total: {} nodes in {} ms ({} nps)", total_nodes, total_ms, nps));
+    result
+}
@@ -0,0 +1,168 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::board::piece::PieceColor;
+use crate::movegen::movedata::MoveData;
+
+/// Depth searched by a `go` without any limit. `go infinite` searches
+/// until `stop` instead.
+pub const DEFAULT_DEPTH: u32 = 5;
+// Kept in reserve for communication with the GUI
+const MOVE_OVERHEAD: Duration = Duration::from_millis(20);
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Limits of one search as given to `go`.
+#[derive(Clone, Default, Debug)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    /// Remaining time of white and black
+    pub time: [Option<Duration>; 2],
+    pub increment: [Duration; 2],
+    pub moves_to_go: Option<u32>,
+    /// Look for a mate in at most this many moves
+    pub mate: Option<u32>,
+    pub infinite: bool,
+    /// Think on the opponent's time until `ponderhit` or `stop`
+    pub ponder: bool,
+    /// Only these moves are searched at the root, every legal move when empty
+    pub root_moves: Vec<MoveData>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits { depth: Some(depth), ..Default::default() }
+    }
+
+    /// Parses the arguments of `go`, ignoring unknown tokens.
+    pub fn parse(tokens: &[&str]) -> Self {
+        let mut limits = SearchLimits::default();
+        let mut tokens = tokens.iter();
+        while let Some(token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|v| v.parse::<u64>().ok());
+            match *token {
+                "depth" => limits.depth = value().map(|v| v as u32),
+                "nodes" => limits.nodes = value(),
+                "movetime" => limits.movetime = value().map(Duration::from_millis),
+                "wtime" => limits.time[PieceColor::WHITE as usize] = value().map(Duration::from_millis),
+                "btime" => limits.time[PieceColor::BLACK as usize] = value().map(Duration::from_millis),
+                "winc" => limits.increment[PieceColor::WHITE as usize] = Duration::from_millis(value().unwrap_or(0)),
+                "binc" => limits.increment[PieceColor::BLACK as usize] = Duration::from_millis(value().unwrap_or(0)),
+                "movestogo" => limits.moves_to_go = value().map(|v| v as u32),
+                "mate" => limits.mate = value().map(|v| v as u32),
+                "infinite" => limits.infinite = true,
+                "ponder" => limits.ponder = true,
+                _ => {}
+            }
+        }
+        limits
+    }
+
+    /// Whether nothing but the depth bounds the search.
+    pub fn is_unbounded(&self) -> bool {
+        self.depth.is_none() && self.mate.is_none() && self.nodes.is_none() && self.movetime.is_none() && self.time.iter().all(Option::is_none)
+    }
+}
+
+/// Shared with a running search to stop it or to end pondering from
+/// another thread.
+#[derive(Debug, Default)]
+pub struct SearchControl {
+    stop: AtomicBool,
+    pondering: AtomicBool,
+    // When pondering ended, the clock only runs from then on
+    ponderhit: Mutex<Option<Instant>>,
+}
+
+impl SearchControl {
+    pub fn new(ponder: bool) -> Self {
+        SearchControl { pondering: AtomicBool::new(ponder), ..Default::default() }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// The opponent played the expected move: the search goes on under the
+    /// time limits, counted from now.
+    pub fn ponderhit(&self) {
+        *self.ponderhit.lock().unwrap() = Some(Instant::now());
+        self.pondering.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.load(Ordering::Relaxed)
+    }
+
+    /// Blocks a finished search until its best move may be sent: after
+    /// `ponderhit` or `stop` when pondering, and only after `stop` when
+    /// infinite.
+    pub fn wait_for_release(&self, infinite: bool) {
+        while !self.is_stopped() && (infinite || self.is_pondering()) {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn clock_start(&self) -> Option<Instant> {
+        *self.ponderhit.lock().unwrap()
+    }
+}
+
+/// Decides when to stop, from the limits and the side to move.
+pub(crate) struct TimeManager<'a> {
+    start: Instant,
+    control: &'a SearchControl,
+    // No new iteration is started after this
+    optimum: Option<Duration>,
+    // The search is aborted after this
+    maximum: Option<Duration>,
+    nodes: Option<u64>,
+}
+
+impl<'a> TimeManager<'a> {
+    pub(crate) fn new(limits: &SearchLimits, turn: PieceColor, control: &'a SearchControl) -> Self {
+        let (optimum, maximum) = if let Some(movetime) = limits.movetime {
+            (Some(movetime), Some(movetime))
+        } else if let Some(time) = limits.time[turn as usize] {
+            let available = time.saturating_sub(MOVE_OVERHEAD);
+            let moves_to_go = limits.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let optimum = (available / moves_to_go + limits.increment[turn as usize] * 3 / 4).min(available);
+            (Some(optimum), Some((optimum * 4).min(available)))
+        } else {
+            (None, None)
+        };
+        TimeManager { start: Instant::now(), control, optimum, maximum, nodes: limits.nodes }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    // Time charged to the engine, which excludes the time spent pondering
+    fn used(&self) -> Duration {
+        self.control.clock_start().unwrap_or(self.start).elapsed()
+    }
+
+    pub(crate) fn should_abort(&self, nodes: u64) -> bool {
+        if self.control.is_stopped() {
+            return true;
+        }
+        if self.control.is_pondering() {
+            return false;
+        }
+        self.nodes.is_some_and(|max| nodes >= max) || self.maximum.is_some_and(|max| self.used() >= max)
+    }
+
+    pub(crate) fn should_start_iteration(&self) -> bool {
+        if self.control.is_stopped() {
+            return false;
+        }
+        self.control.is_pondering() || self.optimum.is_none_or(|optimum| self.used() < optimum)
+    }
+}
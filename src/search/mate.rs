@@ -0,0 +1,146 @@
+use crate::board::board::Board;
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+
+// Proof and disproof numbers of decided nodes
+const INFINITE: u32 = u32::MAX / 2;
+
+struct Node {
+    mv: Option<MoveData>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    proof: u32,
+    disproof: u32,
+    // The attacker is to move and needs one move that mates, the defender
+    // needs one move that escapes
+    attacker: bool,
+    // Plies the attacker has left to mate in
+    plies_left: u32,
+}
+
+/// Proof-number search for a mate in which every attacking move gives
+/// check. Defending moves are all considered.
+struct MateSolver {
+    nodes: Vec<Node>,
+    max_nodes: usize,
+}
+
+/// The shortest mate of at most `max_moves` moves made only of checks, as
+/// the full line, if one is found within `max_nodes` tree nodes.
+pub fn solve_mate(board: &mut Board, max_moves: u32, max_nodes: usize) -> Option<Vec<MoveData>> {
+    let mut used = 0;
+    for moves in 1..=max_moves {
+        let mut solver = MateSolver { nodes: Vec::new(), max_nodes: max_nodes.saturating_sub(used) };
+        let line = solver.solve(board, 2 * moves - 1);
+        used += solver.nodes.len();
+        if line.is_some() || used >= max_nodes {
+            return line;
+        }
+    }
+    None
+}
+
+impl MateSolver {
+    fn solve(&mut self, board: &mut Board, plies: u32) -> Option<Vec<MoveData>> {
+        self.nodes.push(Node { mv: None, parent: None, children: Vec::new(), proof: 1, disproof: 1, attacker: true, plies_left: plies });
+        while self.nodes[0].proof != 0 && self.nodes[0].disproof != 0 && self.nodes.len() < self.max_nodes {
+            let mut path = Vec::new();
+            let mut node = 0;
+            while !self.nodes[node].children.is_empty() {
+                node = self.most_proving_child(node);
+                let mv = self.nodes[node].mv.unwrap();
+                board.make_move(&mv);
+                path.push(mv);
+            }
+            self.expand(board, node);
+            self.update_ancestors(node);
+            for mv in path.iter().rev() {
+                board.unmake_move(mv);
+            }
+        }
+        (self.nodes[0].proof == 0).then(|| self.principal_variation())
+    }
+
+    fn most_proving_child(&self, node: usize) -> usize {
+        let children = self.nodes[node].children.iter().copied();
+        if self.nodes[node].attacker {
+            children.min_by_key(|child| self.nodes[*child].proof).unwrap()
+        } else {
+            children.min_by_key(|child| self.nodes[*child].disproof).unwrap()
+        }
+    }
+
+    fn expand(&mut self, board: &mut Board, node: usize) {
+        let (attacker, plies_left) = (self.nodes[node].attacker, self.nodes[node].plies_left);
+        let moves = generate_moves(board);
+        for mv in moves.iter() {
+            if attacker && !board.gives_check(mv) {
+                continue;
+            }
+            board.make_move(mv);
+            let replies = generate_moves(board).len() as u32;
+            let (proof, disproof) = if replies == 0 {
+                // Checks leave no stalemates, the attacker has lost or drawn
+                if attacker { (0, INFINITE) } else { (INFINITE, 0) }
+            } else if attacker && plies_left == 1 {
+                (INFINITE, 0)
+            } else if attacker {
+                (replies, 1)
+            } else {
+                (1, 1)
+            };
+            board.unmake_move(mv);
+            let child = self.nodes.len();
+            self.nodes.push(Node { mv: Some(*mv), parent: Some(node), children: Vec::new(), proof, disproof, attacker: !attacker, plies_left: plies_left - 1 });
+            self.nodes[node].children.push(child);
+        }
+        if self.nodes[node].children.is_empty() {
+            // No checks left for the attacker
+            self.nodes[node].proof = INFINITE;
+            self.nodes[node].disproof = 0;
+        }
+    }
+
+    fn update_ancestors(&mut self, mut node: usize) {
+        loop {
+            let children = &self.nodes[node].children;
+            if !children.is_empty() {
+                let proofs = children.iter().map(|child| self.nodes[*child].proof);
+                let disproofs = children.iter().map(|child| self.nodes[*child].disproof);
+                let (proof, disproof) = if self.nodes[node].attacker {
+                    (proofs.min().unwrap(), disproofs.fold(0, |sum, d| (sum + d).min(INFINITE)))
+                } else {
+                    (proofs.fold(0, |sum, p| (sum + p).min(INFINITE)), disproofs.min().unwrap())
+                };
+                self.nodes[node].proof = proof;
+                self.nodes[node].disproof = disproof;
+            }
+            match self.nodes[node].parent {
+                Some(parent) => node = parent,
+                None => break,
+            }
+        }
+    }
+
+    // Fastest mate for the attacker against the longest defence
+    fn principal_variation(&self) -> Vec<MoveData> {
+        let mut line = Vec::new();
+        let mut node = 0;
+        while !self.nodes[node].children.is_empty() {
+            let proven = self.nodes[node].children.iter().copied().filter(|child| self.nodes[*child].proof == 0);
+            node = if self.nodes[node].attacker {
+                proven.min_by_key(|child| self.mate_distance(*child)).unwrap()
+            } else {
+                proven.max_by_key(|child| self.mate_distance(*child)).unwrap()
+            };
+            line.push(self.nodes[node].mv.unwrap());
+        }
+        line
+    }
+
+    // Plies to mate in the proof tree below a proven node
+    fn mate_distance(&self, node: usize) -> u32 {
+        let proven = self.nodes[node].children.iter().copied().filter(|child| self.nodes[*child].proof == 0).map(|child| self.mate_distance(child) + 1);
+        if self.nodes[node].attacker { proven.min() } else { proven.max() }.unwrap_or(0)
+    }
+}
@@ -0,0 +1,844 @@
+pub mod bench;
+pub mod limits;
+pub mod mate;
+pub(crate) mod ordering;
+pub mod pv;
+pub mod tt;
+
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use crate::board::board::Board;
+use crate::board::piece::PieceType;
+use crate::board::square::Rank;
+use crate::eval::nnue::Network;
+use crate::eval::params::EvalParams;
+use crate::eval::pawns::PawnTable;
+use crate::eval::{evaluate_cached, KNOWN_WIN};
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+use crate::tablebase::syzygy::probe::{Tablebases, Wdl};
+use limits::{SearchControl, SearchLimits, TimeManager};
+use mate::solve_mate;
+use ordering::{is_tactical, order_moves, order_tactical, HistoryTable};
+use pv::{validate_line, PvTable};
+use tt::{Bound, TranspositionTable, TtEntry};
+
+pub const MAX_PLY: usize = 128;
+pub const INFINITY: i32 = 32_001;
+/// Score of being mated at the root, mates further away score less.
+pub const MATE: i32 = 32_000;
+/// Scores beyond this are mates.
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+/// Score of a tablebase win at the root, below every mate score.
+pub const TB_WIN: i32 = MATE_BOUND - MAX_PLY as i32;
+// Iterations from which aspiration windows are used
+const ASPIRATION_DEPTH: u32 = 4;
+// Nodes between checks of the clock
+const CHECK_INTERVAL: u64 = 2048;
+
+const NULL_MOVE_DEPTH: u32 = 3;
+const REVERSE_FUTILITY_DEPTH: u32 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 80;
+const FUTILITY_DEPTH: u32 = 3;
+const FUTILITY_MARGIN: i32 = 120;
+const RAZORING_DEPTH: u32 = 2;
+const RAZORING_MARGIN: i32 = 250;
+const LATE_MOVE_PRUNING_DEPTH: u32 = 3;
+const LMR_DEPTH: u32 = 3;
+// Quiet moves with more history than this are reduced one ply less
+const LMR_HISTORY: i32 = 4_000;
+const SINGULAR_DEPTH: u32 = 6;
+// Tree nodes the proof-number solver may use for `go mate`
+const MATE_SOLVER_NODES: usize = 1_000_000;
+
+// Late move reductions by depth and move index
+static REDUCTIONS: LazyLock<[[u32; 64]; 64]> = LazyLock::new(|| {
+    let mut reductions = [[0; 64]; 64];
+    for (depth, row) in reductions.iter_mut().enumerate().skip(1) {
+        for (index, reduction) in row.iter_mut().enumerate().skip(1) {
+            *reduction = (0.75 + (depth as f64).ln() * (index as f64).ln() / 2.25) as u32;
+        }
+    }
+    reductions
+});
+
+/// Search techniques that can be switched off, to measure what they gain.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchConfig {
+    /// Search moves after the first with a zero window, and again with the
+    /// full window if they beat alpha
+    pub pvs: bool,
+    /// Start iterations with a window around the previous score, widened
+    /// when the score falls outside it
+    pub aspiration: bool,
+    /// Initial half-width of the aspiration window in centipawns
+    pub aspiration_delta: i32,
+    /// Let the opponent move twice in a reduced search, and cut off if they
+    /// still cannot reach beta. Never in check or with only pawns left.
+    pub null_move: bool,
+    /// Search late quiet moves to a reduced depth first, less so for moves
+    /// with a good history
+    pub late_move_reductions: bool,
+    /// Cut off when the static evaluation beats beta by a margin growing
+    /// with depth
+    pub reverse_futility: bool,
+    /// Skip quiet moves near the leaves when the static evaluation is too
+    /// far below alpha for them to help
+    pub futility: bool,
+    /// Drop into the quiescence search at shallow depths when the static
+    /// evaluation is far below alpha
+    pub razoring: bool,
+    /// Skip the remaining quiet moves at shallow depths after a number
+    /// growing with depth
+    pub late_move_pruning: bool,
+    /// Probe the transposition table for cutoffs and a first move
+    pub transposition_table: bool,
+    /// Extend moves that give check
+    pub check_extension: bool,
+    /// Extend the transposition table move when a reduced search without it
+    /// shows every other move to be clearly worse
+    pub singular_extension: bool,
+    /// Extend a capture on the square where the opponent just captured
+    pub recapture_extension: bool,
+    /// Extend pawn pushes to the seventh rank
+    pub pawn_push_extension: bool,
+}
+
+impl SearchConfig {
+    /// Plain alpha-beta with every technique switched off.
+    pub fn plain() -> Self {
+        SearchConfig {
+            pvs: false,
+            aspiration: false,
+            null_move: false,
+            late_move_reductions: false,
+            reverse_futility: false,
+            futility: false,
+            razoring: false,
+            late_move_pruning: false,
+            transposition_table: false,
+            check_extension: false,
+            singular_extension: false,
+            recapture_extension: false,
+            pawn_push_extension: false,
+            ..SearchConfig::default()
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            pvs: true,
+            aspiration: true,
+            aspiration_delta: 25,
+            null_move: true,
+            late_move_reductions: true,
+            reverse_futility: true,
+            futility: true,
+            razoring: true,
+            late_move_pruning: true,
+            transposition_table: true,
+            check_extension: true,
+            singular_extension: true,
+            recapture_extension: true,
+            pawn_push_extension: true,
+        }
+    }
+}
+
+/// Progress reported after every completed iteration.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<MoveData>,
+    /// Rank of the line, from 1, when several are searched
+    pub multipv: Option<usize>,
+    /// Set when the score is only a bound, after the search failed outside
+    /// its aspiration window
+    pub bound: Option<Bound>,
+}
+
+impl SearchInfo {
+    pub fn to_uci(&self, chess960: bool) -> String {
+        let millis = self.time.as_millis() as u64;
+        let nps = self.nodes * 1000 / millis.max(1);
+        let pv: Vec<String> = self.pv.iter().map(|mv| mv.to_uci(chess960)).collect();
+        let multipv = self.multipv.map_or(String::new(), |k| format!(" multipv {}", k));
+        let bound = match self.bound {
+            Some(Bound::Lower) => " lowerbound",
+            Some(Bound::Upper) => " upperbound",
+            _ => "",
+        };
+        format!(
+            "info depth {}{} score {}{} nodes {} nps {} time {} pv {}",
+            self.depth,
+            multipv,
+            format_score(self.score),
+            bound,
+            self.nodes,
+            nps,
+            millis,
+            pv.join(" ")
+        )
+    }
+}
+
+// Whether the score mates in at most `moves` moves
+fn mates_within(score: i32, moves: u32) -> bool {
+    score > MATE - 2 * moves as i32
+}
+
+/// A score as sent in `info`: `cp` for centipawns, or `mate` with the moves
+/// to mate, negative when the engine is getting mated.
+pub fn format_score(score: i32) -> String {
+    if score > MATE_BOUND {
+        format!("mate {}", (MATE - score + 1) / 2)
+    } else if score < -MATE_BOUND {
+        format!("mate {}", -(MATE + score) / 2)
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// `None` only when there is no legal move
+    pub best_move: Option<MoveData>,
+    pub score: i32,
+    /// Last completed iteration
+    pub depth: u32,
+    pub pv: Vec<MoveData>,
+    /// Every line searched in the last iteration, best first
+    pub lines: Vec<PvLine>,
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    /// The expected reply to the best move, taken from the PV when it is
+    /// legal after the best move.
+    pub fn ponder_move(&self, board: &mut Board) -> Option<MoveData> {
+        let best = self.best_move?;
+        let reply = self.pv.get(1).copied().filter(|_| self.pv[0] == best)?;
+        board.make_move(&best);
+        let legal = generate_moves(board).is_move_in_list(&reply);
+        board.unmake_move(&best);
+        legal.then_some(reply)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub score: i32,
+    pub pv: Vec<MoveData>,
+}
+
+/// Iterative deepening alpha-beta search. Killer moves and history are kept
+/// between searches until `clear`.
+pub struct Searcher {
+    pub config: SearchConfig,
+    /// Number of best root moves to search and report, each with its own
+    /// line
+    pub multi_pv: usize,
+    pv: PvTable,
+    // Line of the previous iteration, searched first
+    previous_pv: Vec<MoveData>,
+    follow_pv: bool,
+    killers: [[Option<MoveData>; 2]; MAX_PLY],
+    history: HistoryTable,
+    tt: TranspositionTable,
+    pawn_table: PawnTable,
+    eval_params: EvalParams,
+    // Network set on the searched board, replacing the hand-crafted
+    // evaluation
+    network: Option<Arc<Network>>,
+    // Syzygy tables probed for WDL values inside the tree
+    tablebases: Option<Arc<Tablebases>>,
+    // Move left out by the singular extension search at each ply
+    excluded: [Option<MoveData>; MAX_PLY],
+    // Root moves already reported in this iteration, left out of the
+    // search for the next line
+    root_excluded: Vec<MoveData>,
+    // Moves the root is restricted to, every legal move when empty
+    root_moves: Vec<MoveData>,
+    // Lines searched in the current search
+    lines: usize,
+    // Plies the line to each ply was extended by
+    extensions: [u32; MAX_PLY + 1],
+    root_depth: u32,
+    // Hashes of the positions before the current one, for repetitions
+    positions: Vec<u64>,
+    // Moves made in the search, `None` for null moves
+    moves_played: Vec<Option<MoveData>>,
+    nodes: u64,
+    stopped: bool,
+    control: Arc<SearchControl>,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(SearchConfig::default())
+    }
+}
+
+impl Searcher {
+    pub fn new(config: SearchConfig) -> Self {
+        Searcher {
+            config,
+            multi_pv: 1,
+            pv: PvTable::new(),
+            previous_pv: Vec::new(),
+            follow_pv: false,
+            killers: [[None; 2]; MAX_PLY],
+            history: HistoryTable::new(),
+            tt: TranspositionTable::default(),
+            pawn_table: PawnTable::new(),
+            eval_params: EvalParams::default(),
+            network: None,
+            tablebases: None,
+            excluded: [None; MAX_PLY],
+            root_excluded: Vec::new(),
+            root_moves: Vec::new(),
+            lines: 1,
+            extensions: [0; MAX_PLY + 1],
+            root_depth: 0,
+            positions: Vec::new(),
+            moves_played: Vec::new(),
+            nodes: 0,
+            stopped: false,
+            control: Arc::default(),
+        }
+    }
+
+    /// Makes the next searches obey `control`, so they can be stopped from
+    /// another thread.
+    pub fn set_control(&mut self, control: Arc<SearchControl>) {
+        self.control = control;
+    }
+
+    /// Evaluates with `params` from the next search on.
+    pub fn set_eval_params(&mut self, params: EvalParams) {
+        self.eval_params = params;
+        self.pawn_table.clear();
+    }
+
+    /// Evaluates with `network` from the next search on, or with the
+    /// hand-crafted evaluation for `None`.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.network = network;
+    }
+
+    /// Probes `tablebases` inside the tree from the next search on.
+    pub fn set_tablebases(&mut self, tablebases: Option<Arc<Tablebases>>) {
+        self.tablebases = tablebases;
+    }
+
+    /// Forgets what was learned in earlier searches, for a new game.
+    pub fn clear(&mut self) {
+        self.killers = [[None; 2]; MAX_PLY];
+        self.history = HistoryTable::new();
+        self.tt.clear();
+        self.pawn_table.clear();
+    }
+
+    /// Replaces the transposition table with an empty one of the given size.
+    pub fn resize_tt(&mut self, megabytes: usize) {
+        self.tt = TranspositionTable::new(megabytes);
+    }
+
+    /// Searches `board` within `limits`. `game_history` holds the hashes of
+    /// the positions played before it, and `report` receives every completed
+    /// iteration.
+    pub fn search(&mut self, board: &mut Board, limits: &SearchLimits, game_history: &[u64], report: impl FnMut(&SearchInfo)) -> SearchResult {
+        // A board that already has a network keeps it
+        let set_network = self.network.is_some() && board.nnue().is_none();
+        if set_network {
+            board.set_network(self.network.clone());
+        }
+        let result = self.search_root(board, limits, game_history, report);
+        if set_network {
+            board.set_network(None);
+        }
+        result
+    }
+
+    fn search_root(&mut self, board: &mut Board, limits: &SearchLimits, game_history: &[u64], mut report: impl FnMut(&SearchInfo)) -> SearchResult {
+        let control = self.control.clone();
+        let timer = TimeManager::new(limits, board.turn, &control);
+        self.positions = game_history.to_vec();
+        self.moves_played.clear();
+        self.previous_pv.clear();
+        self.nodes = 0;
+        self.stopped = false;
+
+        let legal_moves = generate_moves(board);
+        self.root_moves = limits.root_moves.iter().filter(|mv| legal_moves.is_move_in_list(mv)).copied().collect();
+        let root_count = if self.root_moves.is_empty() { legal_moves.len() } else { self.root_moves.len() };
+        let best_move = self.root_moves.first().or(legal_moves.iter().next()).copied();
+        let mut result = SearchResult { best_move, score: 0, depth: 0, pv: Vec::new(), lines: Vec::new(), nodes: 0 };
+        if legal_moves.is_empty() {
+            result.score = if board.is_check { -MATE } else { 0 };
+            return result;
+        }
+        if let Some(moves) = limits.mate {
+            self.lines = 1;
+            return self.search_mate(board, moves.max(1), &timer, result, report);
+        }
+        let max_depth = limits.depth.unwrap_or(if limits.is_unbounded() && !limits.infinite && !limits.ponder { limits::DEFAULT_DEPTH } else { MAX_PLY as u32 - 1 });
+        self.lines = self.multi_pv.clamp(1, root_count);
+        self.iterate(board, max_depth, None, &timer, result, &mut report)
+    }
+
+    fn iterate(
+        &mut self,
+        board: &mut Board,
+        max_depth: u32,
+        mate: Option<u32>,
+        timer: &TimeManager,
+        mut result: SearchResult,
+        report: &mut impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let lines = self.lines;
+        let multipv = |k: usize| (lines > 1).then_some(k + 1);
+        for depth in 1..=max_depth.min(MAX_PLY as u32 - 1) {
+            self.root_depth = depth;
+            self.root_excluded.clear();
+            let mut current: Vec<PvLine> = Vec::new();
+            for k in 0..lines {
+                self.previous_pv = result.lines.get(k).map_or(Vec::new(), |line| line.pv.clone());
+                self.follow_pv = true;
+                let score = if self.config.aspiration && depth >= ASPIRATION_DEPTH {
+                    let previous = result.lines.get(k).map_or(result.score, |line| line.score);
+                    self.aspiration(board, depth, previous, multipv(k), timer, report)
+                } else {
+                    self.negamax(board, depth, 0, -INFINITY, INFINITY, timer)
+                };
+                if self.stopped {
+                    break;
+                }
+                let pv = validate_line(board, &self.pv.line());
+                self.root_excluded.extend(pv.first());
+                current.push(PvLine { score, pv });
+            }
+            if self.stopped {
+                break;
+            }
+            current.sort_by_key(|line| -line.score);
+            for (k, line) in current.iter().enumerate() {
+                report(&SearchInfo { depth, score: line.score, nodes: self.nodes, time: timer.elapsed(), pv: line.pv.clone(), multipv: multipv(k), bound: None });
+            }
+            let (score, pv) = (current[0].score, current[0].pv.clone());
+            result = SearchResult { best_move: pv.first().copied().or(result.best_move), score, depth, pv, lines: current, nodes: self.nodes };
+            if !timer.should_start_iteration() || mate.is_some_and(|moves| mates_within(score, moves)) {
+                break;
+            }
+        }
+        self.root_excluded.clear();
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn is_root_move(&self, mv: &MoveData) -> bool {
+        self.root_moves.is_empty() || self.root_moves.contains(mv)
+    }
+
+    // Shortest mate in at most `moves` moves. The proof-number solver finds
+    // mates made of checks, then a full-width search without pruning looks
+    // for shorter mates with quiet moves, or for any mate if there was none.
+    fn search_mate(&mut self, board: &mut Board, moves: u32, timer: &TimeManager, result: SearchResult, mut report: impl FnMut(&SearchInfo)) -> SearchResult {
+        let proof = solve_mate(board, moves, MATE_SOLVER_NODES).filter(|line| line.first().is_some_and(|mv| self.is_root_move(mv)));
+        let moves = proof.as_ref().map_or(moves, |line| (line.len() as u32).div_ceil(2) - 1);
+        let config = self.config;
+        self.config = SearchConfig { pvs: config.pvs, transposition_table: config.transposition_table, check_extension: config.check_extension, ..SearchConfig::plain() };
+        let searched = if moves > 0 { Some(self.iterate(board, 2 * moves - 1, Some(moves), timer, result.clone(), &mut report)) } else { None };
+        self.config = config;
+        match (searched, proof) {
+            (Some(searched), _) if mates_within(searched.score, moves) => searched,
+            (_, Some(line)) => {
+                let score = MATE - line.len() as i32;
+                report(&SearchInfo { depth: line.len() as u32, score, nodes: self.nodes, time: timer.elapsed(), pv: line.clone(), multipv: None, bound: None });
+                let lines = vec![PvLine { score, pv: line.clone() }];
+                SearchResult { best_move: line.first().copied(), score, depth: line.len() as u32, pv: line, lines, nodes: self.nodes }
+            }
+            (searched, None) => searched.unwrap_or(result),
+        }
+    }
+
+    // Searches a window around the previous score, widening the side the
+    // score falls out of until it lands inside. Each failure is reported
+    // with the score as a bound.
+    fn aspiration(&mut self, board: &mut Board, depth: u32, previous: i32, multipv: Option<usize>, timer: &TimeManager, report: &mut impl FnMut(&SearchInfo)) -> i32 {
+        let mut delta = self.config.aspiration_delta;
+        let (mut alpha, mut beta) = ((previous - delta).max(-INFINITY), (previous + delta).min(INFINITY));
+        loop {
+            self.follow_pv = true;
+            let score = self.negamax(board, depth, 0, alpha, beta, timer);
+            if self.stopped {
+                return score;
+            }
+            let bound = if score <= alpha {
+                alpha = (score - delta).max(-INFINITY);
+                Bound::Upper
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+                Bound::Lower
+            } else {
+                return score;
+            };
+            let line = validate_line(board, &self.pv.line());
+            let pv = if line.is_empty() { self.previous_pv.clone() } else { line };
+            report(&SearchInfo { depth, score, nodes: self.nodes, time: timer.elapsed(), pv, multipv, bound: Some(bound) });
+            delta *= 2;
+        }
+    }
+
+    fn negamax(&mut self, board: &mut Board, depth: u32, ply: usize, mut alpha: i32, mut beta: i32, timer: &TimeManager) -> i32 {
+        self.pv.clear(ply);
+        if self.check_stop(timer) {
+            return 0;
+        }
+        if ply > 0 {
+            if self.is_draw(board) {
+                return 0;
+            }
+            // Mate distance pruning: no line from here beats mating or being
+            // mated right away
+            alpha = alpha.max(-MATE + ply as i32);
+            beta = beta.min(MATE - ply as i32 - 1);
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        if depth == 0 {
+            return self.quiescence(board, ply, alpha, beta, timer);
+        }
+        self.nodes += 1;
+        if ply >= MAX_PLY - 1 {
+            return evaluate_cached(board, &mut self.pawn_table, &self.eval_params);
+        }
+
+        let pv_node = beta - alpha > 1;
+        let excluded = self.excluded[ply];
+        let hash = board.get_hash();
+        let tt_entry = if self.config.transposition_table && excluded.is_none() { self.tt.probe(hash, ply) } else { None };
+        if let Some(entry) = tt_entry {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if !pv_node && ply > 0 && entry.depth >= depth && usable {
+                return entry.score;
+            }
+        }
+
+        if ply > 0 && excluded.is_none() {
+            if let Some(score) = self.probe_wdl(board, ply) {
+                if self.config.transposition_table {
+                    self.tt.store(hash, None, score, depth, Bound::Exact, ply);
+                }
+                return score;
+            }
+        }
+
+        let moves = generate_moves(board);
+        if moves.is_empty() {
+            return if board.is_check { -MATE + ply as i32 } else { 0 };
+        }
+        let in_check = board.is_check;
+        let static_eval = if in_check { -INFINITY } else { evaluate_cached(board, &mut self.pawn_table, &self.eval_params) };
+
+        // Evaluations of won endings are left to the search, to find the mate
+        let prune = !pv_node && !in_check && static_eval.abs() < KNOWN_WIN && excluded.is_none();
+        if prune && ply > 0 {
+            if self.config.reverse_futility && depth <= REVERSE_FUTILITY_DEPTH && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta {
+                return static_eval;
+            }
+            if self.config.razoring && depth <= RAZORING_DEPTH && static_eval + RAZORING_MARGIN * depth as i32 <= alpha {
+                let score = self.quiescence(board, ply, alpha, alpha + 1, timer);
+                if score <= alpha {
+                    return score;
+                }
+            }
+            if self.config.null_move
+                && depth >= NULL_MOVE_DEPTH
+                && static_eval >= beta
+                && self.moves_played.last() != Some(&None)
+                && !board.non_pawn_pieces(board.turn).is_empty()
+                && board.make_null_move()
+            {
+                let reduction = 3 + depth / 6;
+                self.positions.push(hash);
+                self.moves_played.push(None);
+                self.extensions[ply + 1] = self.extensions[ply];
+                let score = -self.negamax(board, depth.saturating_sub(1 + reduction), ply + 1, -beta, -beta + 1, timer);
+                self.positions.pop();
+                self.moves_played.pop();
+                board.unmake_null_move();
+                if self.stopped {
+                    return 0;
+                }
+                if score >= beta {
+                    // Mates found without our move are not proven
+                    return if score >= MATE_BOUND { beta } else { score };
+                }
+            }
+        }
+
+        let tt_move = tt_entry.and_then(|entry| entry.mv).filter(|mv| moves.is_move_in_list(mv));
+        let pv_move = if self.follow_pv { self.previous_pv.get(ply).copied().filter(|mv| moves.is_move_in_list(mv)) } else { None };
+        self.follow_pv &= pv_move.is_some();
+        let singular_move = self.singular_move(board, depth, ply, tt_entry.filter(|_| tt_move.is_some()), timer);
+        if self.stopped {
+            return 0;
+        }
+
+        let futile = self.config.futility && prune && depth <= FUTILITY_DEPTH && static_eval + FUTILITY_MARGIN * depth as i32 <= alpha;
+        let late_move_count = 3 + depth * depth;
+        let original_alpha = alpha;
+        let mut quiets_searched: Vec<MoveData> = Vec::new();
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut searched = 0;
+        for mv in order_moves(&moves, pv_move.or(tt_move), &self.killers[ply], &self.history).iter() {
+            if excluded == Some(*mv) || (ply == 0 && (self.root_excluded.contains(mv) || !self.is_root_move(mv))) {
+                continue;
+            }
+            let quiet = !is_tactical(mv);
+            let gives_check = board.gives_check(mv);
+            // Quiet moves are pruned unless they give check
+            if quiet && best > -MATE_BOUND && !gives_check {
+                let late = self.config.late_move_pruning && prune && depth <= LATE_MOVE_PRUNING_DEPTH && quiets_searched.len() as u32 >= late_move_count;
+                if futile || late {
+                    continue;
+                }
+            }
+            self.make_move(board, mv);
+            let extension = self.extension(ply, mv, gives_check, singular_move == Some(*mv));
+            self.extensions[ply + 1] = self.extensions[ply] + extension;
+            let new_depth = depth - 1 + extension;
+            let reduction = if self.config.late_move_reductions && quiet && !in_check && !gives_check && extension == 0 && depth >= LMR_DEPTH && searched >= 2 {
+                let mut reduction = REDUCTIONS[(depth as usize).min(63)][searched.min(63)] as i32;
+                reduction -= pv_node as i32 + (self.history.get(mv) > LMR_HISTORY) as i32;
+                reduction += (self.history.get(mv) < 0) as i32;
+                reduction.clamp(0, depth as i32 - 2) as u32
+            } else {
+                0
+            };
+            let score = if searched == 0 {
+                -self.negamax(board, new_depth, ply + 1, -beta, -alpha, timer)
+            } else {
+                let mut score = alpha + 1;
+                if reduction > 0 {
+                    score = -self.negamax(board, new_depth - reduction, ply + 1, -alpha - 1, -alpha, timer);
+                }
+                if score > alpha {
+                    score = if self.config.pvs {
+                        // Prove the move is no better than alpha with a zero window
+                        let score = -self.negamax(board, new_depth, ply + 1, -alpha - 1, -alpha, timer);
+                        if score > alpha && score < beta {
+                            -self.negamax(board, new_depth, ply + 1, -beta, -alpha, timer)
+                        } else {
+                            score
+                        }
+                    } else {
+                        -self.negamax(board, new_depth, ply + 1, -beta, -alpha, timer)
+                    };
+                }
+                score
+            };
+            self.unmake_move(board, mv);
+            self.follow_pv = false;
+            searched += 1;
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(*mv);
+                    self.pv.update(ply, *mv);
+                    if score >= beta {
+                        if quiet {
+                            self.update_quiet_history(ply, depth, mv, &quiets_searched);
+                        }
+                        break;
+                    }
+                }
+            }
+            if quiet {
+                quiets_searched.push(*mv);
+            }
+        }
+        if searched == 0 && excluded.is_some() {
+            // Only the excluded move was left
+            return alpha;
+        }
+
+        if self.config.transposition_table && excluded.is_none() && (ply > 0 || (self.root_excluded.is_empty() && self.root_moves.is_empty())) {
+            let bound = if best >= beta {
+                Bound::Lower
+            } else if best > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.tt.store(hash, best_move, best, depth, bound, ply);
+        }
+        best
+    }
+
+    // Tablebase value of the position. Only probed right after a capture or
+    // pawn move, where the tables' 50-move rule counter matches the game;
+    // wins and losses score below mates, so that mates found are kept.
+    fn probe_wdl(&self, board: &mut Board, ply: usize) -> Option<i32> {
+        let tablebases = self.tablebases.as_ref()?;
+        if board.game_state.halfmove_clock != 0 {
+            return None;
+        }
+        Some(match tablebases.probe_wdl(board)? {
+            Wdl::Win => TB_WIN - ply as i32,
+            Wdl::Loss => -TB_WIN + ply as i32,
+            Wdl::CursedWin => 1,
+            Wdl::BlessedLoss => -1,
+            Wdl::Draw => 0,
+        })
+    }
+
+    // The TT move, if every other move fails low against a margin below its
+    // stored score in a reduced search
+    fn singular_move(&mut self, board: &mut Board, depth: u32, ply: usize, tt_entry: Option<TtEntry>, timer: &TimeManager) -> Option<MoveData> {
+        let entry = tt_entry?;
+        if !self.config.singular_extension
+            || ply == 0
+            || depth < SINGULAR_DEPTH
+            || self.excluded[ply].is_some()
+            || entry.bound == Bound::Upper
+            || entry.depth + 3 < depth
+            || entry.score.abs() >= MATE_BOUND
+        {
+            return None;
+        }
+        let singular_beta = (entry.score - 2 * depth as i32).max(-MATE_BOUND);
+        self.excluded[ply] = entry.mv;
+        let score = self.negamax(board, (depth - 1) / 2, ply, singular_beta - 1, singular_beta, timer);
+        self.excluded[ply] = None;
+        // The exclusion search reused this ply's line
+        self.pv.clear(ply);
+        (score < singular_beta).then_some(entry.mv?)
+    }
+
+    // Plies added to the search of `mv`, which has just been made. At most
+    // one per move, and a line gets no more than the depth of the iteration
+    fn extension(&self, ply: usize, mv: &MoveData, gives_check: bool, singular: bool) -> u32 {
+        if self.extensions[ply] >= self.root_depth {
+            return 0;
+        }
+        let config = &self.config;
+        let previous = self.moves_played.iter().rev().nth(1).copied().flatten();
+        let recapture = mv.is_capture() && previous.is_some_and(|previous| previous.is_capture() && previous.to == mv.to);
+        let seventh_rank_push = mv.piece_to_move.piece_type == PieceType::PAWN && mv.to.rank().relative(mv.piece_to_move.piece_color) == Rank::Seventh;
+        let extend = (config.check_extension && gives_check)
+            || (config.singular_extension && singular)
+            || (config.recapture_extension && recapture)
+            || (config.pawn_push_extension && seventh_rank_push);
+        extend as u32
+    }
+
+    // Rewards the quiet move that caused a cutoff and penalises the quiet
+    // moves tried before it
+    fn update_quiet_history(&mut self, ply: usize, depth: u32, mv: &MoveData, quiets_searched: &[MoveData]) {
+        if self.killers[ply][0] != Some(*mv) {
+            self.killers[ply] = [Some(*mv), self.killers[ply][0]];
+        }
+        let bonus = (depth * depth) as i32 * 16;
+        self.history.update(mv, bonus);
+        for quiet in quiets_searched {
+            self.history.update(quiet, -bonus);
+        }
+    }
+
+    // Resolves captures and promotions so that only quiet positions are
+    // evaluated. In check, every evasion is searched.
+    fn quiescence(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32, timer: &TimeManager) -> i32 {
+        self.pv.clear(ply);
+        self.nodes += 1;
+        if self.check_stop(timer) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate_cached(board, &mut self.pawn_table, &self.eval_params);
+        }
+        let moves = generate_moves(board);
+        let (mut best, moves) = if board.is_check {
+            if moves.is_empty() {
+                return -MATE + ply as i32;
+            }
+            (-INFINITY, order_moves(&moves, None, &[None; 2], &self.history))
+        } else {
+            if moves.is_empty() {
+                return 0;
+            }
+            let stand_pat = evaluate_cached(board, &mut self.pawn_table, &self.eval_params);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            (stand_pat, order_tactical(&moves))
+        };
+        for mv in &moves {
+            self.make_move(board, mv);
+            let score = -self.quiescence(board, ply + 1, -beta, -alpha, timer);
+            self.unmake_move(board, mv);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    self.pv.update(ply, *mv);
+                    if score >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn make_move(&mut self, board: &mut Board, mv: &MoveData) {
+        self.positions.push(board.get_hash());
+        self.moves_played.push(Some(*mv));
+        board.make_move(mv);
+    }
+
+    fn unmake_move(&mut self, board: &mut Board, mv: &MoveData) {
+        board.unmake_move(mv);
+        self.positions.pop();
+        self.moves_played.pop();
+    }
+
+    // Fifty-move rule, or a repetition of a position since the last
+    // capture or pawn move
+    fn is_draw(&self, board: &Board) -> bool {
+        let halfmoves = board.game_state.halfmove_clock as usize;
+        if halfmoves >= 100 {
+            return true;
+        }
+        let hash = board.get_hash();
+        let len = self.positions.len();
+        (2..=halfmoves.min(len)).step_by(2).any(|distance| self.positions[len - distance] == hash)
+    }
+
+    fn check_stop(&mut self, timer: &TimeManager) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(CHECK_INTERVAL) && timer.should_abort(self.nodes) {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+}
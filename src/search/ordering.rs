@@ -0,0 +1,73 @@
+use crate::eval::PIECE_VALUES;
+use crate::movegen::movedata::MoveData;
+use crate::movegen::movelist::MoveList;
+
+const PV_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const PROMOTION_SCORE: i32 = 90_000;
+const KILLER_SCORE: i32 = 80_000;
+
+/// Moves of a node, best first: the PV move, captures by most valuable
+/// victim and least valuable attacker, promotions, killer moves, then quiet
+/// moves by history.
+pub(crate) fn order_moves(moves: &MoveList, pv_move: Option<MoveData>, killers: &[Option<MoveData>; 2], history: &HistoryTable) -> Vec<MoveData> {
+    let mut scored: Vec<(i32, MoveData)> = moves.iter().map(|mv| (score(mv, pv_move, killers, history), *mv)).collect();
+    scored.sort_by_key(|(score, _)| -score);
+    scored.into_iter().map(|(_, mv)| mv).collect()
+}
+
+/// Captures and promotions only, for the quiescence search.
+pub(crate) fn order_tactical(moves: &MoveList) -> Vec<MoveData> {
+    let mut scored: Vec<(i32, MoveData)> = moves.iter().filter(|mv| is_tactical(mv)).map(|mv| (tactical_score(mv), *mv)).collect();
+    scored.sort_by_key(|(score, _)| -score);
+    scored.into_iter().map(|(_, mv)| mv).collect()
+}
+
+pub(crate) fn is_tactical(mv: &MoveData) -> bool {
+    mv.is_capture() || mv.is_promotion()
+}
+
+fn score(mv: &MoveData, pv_move: Option<MoveData>, killers: &[Option<MoveData>; 2], history: &HistoryTable) -> i32 {
+    if pv_move == Some(*mv) {
+        PV_SCORE
+    } else if is_tactical(mv) {
+        tactical_score(mv)
+    } else if killers[0] == Some(*mv) {
+        KILLER_SCORE
+    } else if killers[1] == Some(*mv) {
+        KILLER_SCORE - 1
+    } else {
+        history.get(mv)
+    }
+}
+
+fn tactical_score(mv: &MoveData) -> i32 {
+    let promotion = mv.get_promoted_piece().map_or(0, |piece| PIECE_VALUES[piece.piece_type as usize]);
+    match mv.get_captured_piece() {
+        Some(victim) => CAPTURE_SCORE + 10 * PIECE_VALUES[victim.piece_type as usize] - mv.piece_to_move.piece_type as i32 + promotion,
+        None => PROMOTION_SCORE + promotion,
+    }
+}
+
+const MAX_HISTORY: i32 = 16_384;
+
+/// Scores of quiet moves by side, origin and destination, raised when the
+/// move causes a cutoff and lowered when another quiet move does.
+pub(crate) struct HistoryTable([[[i32; 64]; 64]; 2]);
+
+impl HistoryTable {
+    pub(crate) fn new() -> Self {
+        HistoryTable([[[0; 64]; 64]; 2])
+    }
+
+    pub(crate) fn get(&self, mv: &MoveData) -> i32 {
+        self.0[mv.piece_to_move.piece_color as usize][mv.from.index()][mv.to.index()]
+    }
+
+    /// Moves the score towards the bound, slowing down as it gets closer.
+    pub(crate) fn update(&mut self, mv: &MoveData, bonus: i32) {
+        let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+        let entry = &mut self.0[mv.piece_to_move.piece_color as usize][mv.from.index()][mv.to.index()];
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+}
@@ -0,0 +1,59 @@
+use crate::board::board::Board;
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+use super::MAX_PLY;
+
+/// Triangular table of principal variations. Row `ply` holds the best line
+/// found from that ply, starting at column `ply`.
+pub struct PvTable {
+    moves: Vec<[Option<MoveData>; MAX_PLY]>,
+    lengths: [usize; MAX_PLY + 1],
+}
+
+impl Default for PvTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PvTable {
+    pub fn new() -> Self {
+        PvTable { moves: vec![[None; MAX_PLY]; MAX_PLY], lengths: [0; MAX_PLY + 1] }
+    }
+
+    /// Empties the line of `ply`, done when a node is entered.
+    pub fn clear(&mut self, ply: usize) {
+        self.lengths[ply] = ply;
+    }
+
+    /// Makes `mv` followed by the line of the next ply the line of `ply`.
+    pub fn update(&mut self, ply: usize, mv: MoveData) {
+        let end = self.lengths[ply + 1].max(ply + 1);
+        self.moves[ply][ply] = Some(mv);
+        for i in ply + 1..end {
+            self.moves[ply][i] = self.moves[ply + 1][i];
+        }
+        self.lengths[ply] = end;
+    }
+
+    /// The principal variation from the root.
+    pub fn line(&self) -> Vec<MoveData> {
+        self.moves[0][..self.lengths[0]].iter().map_while(|mv| *mv).collect()
+    }
+}
+
+/// The longest prefix of `line` made of legal moves from `board`.
+pub fn validate_line(board: &mut Board, line: &[MoveData]) -> Vec<MoveData> {
+    let mut valid = Vec::new();
+    for mv in line {
+        if !generate_moves(board).is_move_in_list(mv) {
+            break;
+        }
+        board.make_move(mv);
+        valid.push(*mv);
+    }
+    for mv in valid.iter().rev() {
+        board.unmake_move(mv);
+    }
+    valid
+}
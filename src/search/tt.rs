@@ -0,0 +1,86 @@
+use std::mem::size_of;
+use crate::movegen::movedata::MoveData;
+use super::MATE_BOUND;
+
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const MAX_HASH_MB: usize = 4096;
+
+/// How the stored score relates to the true score of the position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Exact,
+    /// The search failed high, the score is at least this
+    Lower,
+    /// The search failed low, the score is at most this
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TtEntry {
+    key: u64,
+    pub mv: Option<MoveData>,
+    pub score: i32,
+    pub depth: u32,
+    pub bound: Bound,
+}
+
+/// Results of earlier searches indexed by Zobrist hash, one entry per slot
+/// and always replaced, except that a known best move is kept when the new
+/// result has none.
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Self {
+        let len = (megabytes.clamp(1, MAX_HASH_MB) << 20) / size_of::<Option<TtEntry>>();
+        TranspositionTable { entries: vec![None; len] }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Entry stored for `hash`, with mate scores made relative to `ply`.
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TtEntry> {
+        let entry = self.entries[self.index(hash)].filter(|entry| entry.key == hash)?;
+        Some(TtEntry { score: from_tt(entry.score, ply), ..entry })
+    }
+
+    pub fn store(&mut self, hash: u64, mv: Option<MoveData>, score: i32, depth: u32, bound: Bound, ply: usize) {
+        let index = self.index(hash);
+        let mv = mv.or_else(|| self.entries[index].filter(|entry| entry.key == hash).and_then(|entry| entry.mv));
+        self.entries[index] = Some(TtEntry { key: hash, mv, score: to_tt(score, ply), depth, bound });
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.entries.len() as u64) as usize
+    }
+}
+
+// Mate scores are stored as distance from the node rather than the root
+fn to_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_BOUND {
+        score + ply as i32
+    } else if score < -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn from_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_BOUND {
+        score - ply as i32
+    } else if score < -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
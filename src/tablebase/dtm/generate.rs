@@ -0,0 +1,218 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::{Rank, Square};
+use crate::movegen::generate::generate_moves;
+use crate::movegen::magic::functions::{get_bishop_attacks, get_rook_attacks};
+use crate::movegen::precomputed::{KING_MOVES, KNIGHT_MOVES};
+use super::probe::DtmTablebases;
+use super::table::{Dtm, DtmError, DtmTable, Material};
+
+const MAX_PLIES: usize = 254;
+const PROMOTIONS: [PieceType; 4] = [PieceType::QUEEN, PieceType::ROOK, PieceType::BISHOP, PieceType::KNIGHT];
+
+const VALID: u8 = 1;
+const RESOLVED: u8 = 2;
+// A capture or promotion draws or wins, so the position is not lost
+const CANNOT_LOSE: u8 = 4;
+
+/// Generates the table for `name` and every table it converts into by a
+/// capture or promotion, adding them to `tables`. Tables already present are
+/// not generated again.
+pub fn generate(name: &str, tables: &mut DtmTablebases) -> Result<(), DtmError> {
+    generate_material(&Material::from_name(name)?, tables)
+}
+
+fn generate_material(material: &Material, tables: &mut DtmTablebases) -> Result<(), DtmError> {
+    let material = if material.is_canonical() { material.clone() } else { material.mirrored() };
+    if tables.contains(&material.name()) {
+        return Ok(());
+    }
+    for successor in successors(&material) {
+        generate_material(&successor, tables)?;
+    }
+    let table = solve(&material, tables)?;
+    tables.insert(table);
+    Ok(())
+}
+
+// Materials reachable by one capture, promotion or capturing promotion
+fn successors(material: &Material) -> Vec<Material> {
+    let others = &material.pieces[2..];
+    let without = |skip: &[usize], replace: Option<(usize, Piece)>| -> Material {
+        Material::new(
+            others
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !skip.contains(i))
+                .map(|(i, p)| replace.filter(|(at, _)| *at == i).map_or(*p, |(_, promoted)| promoted))
+                .collect(),
+        )
+    };
+    let mut result = Vec::new();
+    for (i, piece) in others.iter().enumerate() {
+        result.push(without(&[i], None));
+        if piece.piece_type != PieceType::PAWN {
+            continue;
+        }
+        for promoted in PROMOTIONS.map(|t| Piece::new(piece.piece_color, t)) {
+            result.push(without(&[], Some((i, promoted))));
+            for (j, captured) in others.iter().enumerate() {
+                if captured.piece_color != piece.piece_color && captured.piece_type != PieceType::PAWN {
+                    result.push(without(&[j], Some((i, promoted))));
+                }
+            }
+        }
+    }
+    result
+}
+
+// Retrograde analysis. Every position first counts its distinct successors
+// inside the table and takes the values of captures and promotions from the
+// smaller tables. Starting from the mates, positions are then resolved one ply
+// at a time: a predecessor of a lost position is won, and a position whose
+// last undecided successor turned out to be won is lost.
+fn solve(material: &Material, tables: &DtmTablebases) -> Result<DtmTable, DtmError> {
+    let size = material.table_size();
+    let mut values = vec![0u8; size];
+    let mut flags = vec![0u8; size];
+    let mut moves_left = vec![0u8; size];
+    let mut exit_loss = vec![0u8; size];
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); MAX_PLIES + 1];
+
+    for index in 0..size {
+        let (squares, white_to_move) = material.squares(index);
+        if !is_valid_placement(material, &squares, white_to_move, index) {
+            continue;
+        }
+        let mut board = material.board(&squares, white_to_move);
+        let their_king = Square::new(squares[white_to_move as usize]);
+        if board.is_square_attacked_by(their_king, board.turn) {
+            continue;
+        }
+        flags[index] = VALID;
+
+        let moves = generate_moves(&mut board);
+        let mut children = Vec::new();
+        for mv in moves.iter() {
+            if mv.is_capture() || mv.is_promotion() {
+                board.make_move(mv);
+                let value = tables.probe(&board).ok_or_else(|| DtmError::MissingTable(Material::from_board(&board).unwrap().name()));
+                board.unmake_move(mv);
+                match value?.parent() {
+                    Dtm::Win(plies) => {
+                        flags[index] |= CANNOT_LOSE;
+                        schedule(&mut buckets, plies as usize, index)?;
+                    }
+                    Dtm::Draw => flags[index] |= CANNOT_LOSE,
+                    Dtm::Loss(plies) => exit_loss[index] = exit_loss[index].max(plies),
+                }
+            } else {
+                let mut child = squares.clone();
+                let moved = squares.iter().position(|sq| *sq == mv.from.to_u8()).unwrap();
+                child[moved] = mv.to.to_u8();
+                children.push(material.index(&child, !white_to_move));
+            }
+        }
+        children.sort_unstable();
+        children.dedup();
+        moves_left[index] = children.len() as u8;
+
+        if moves.is_empty() {
+            if board.is_check {
+                schedule(&mut buckets, 0, index)?;
+            }
+        } else if children.is_empty() && flags[index] & CANNOT_LOSE == 0 {
+            schedule(&mut buckets, exit_loss[index] as usize, index)?;
+        }
+    }
+
+    for plies in 0..=MAX_PLIES {
+        let mut resolved = Vec::new();
+        for index in std::mem::take(&mut buckets[plies]) {
+            if flags[index] & RESOLVED == 0 {
+                flags[index] |= RESOLVED;
+                values[index] = if plies % 2 == 0 { Dtm::Loss(plies as u8) } else { Dtm::Win(plies as u8) }.encode();
+                resolved.push(index);
+            }
+        }
+        for child in resolved {
+            for parent in predecessors(material, child) {
+                if flags[parent] & (VALID | RESOLVED) != VALID {
+                    continue;
+                }
+                if plies % 2 == 0 {
+                    schedule(&mut buckets, plies + 1, parent)?;
+                } else {
+                    moves_left[parent] -= 1;
+                    if moves_left[parent] == 0 && flags[parent] & CANNOT_LOSE == 0 {
+                        schedule(&mut buckets, (plies + 1).max(exit_loss[parent] as usize), parent)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(DtmTable::new(material.clone(), values))
+}
+
+fn schedule(buckets: &mut [Vec<usize>], plies: usize, index: usize) -> Result<(), DtmError> {
+    buckets.get_mut(plies).ok_or(DtmError::TooDeep)?.push(index);
+    Ok(())
+}
+
+// Rejects overlapping pieces, pawns on the first or last rank and indices
+// that are not the canonical form of their position
+fn is_valid_placement(material: &Material, squares: &[u8], white_to_move: bool, index: usize) -> bool {
+    let occupied = squares.iter().fold(0u64, |bits, sq| bits | 1 << sq);
+    if occupied.count_ones() as usize != squares.len() {
+        return false;
+    }
+    let back_ranks = Bitboard::rank_mask(Rank::First) | Bitboard::rank_mask(Rank::Eighth);
+    let on_back_rank = |(piece, sq): (&Piece, &u8)| piece.piece_type == PieceType::PAWN && back_ranks.contains_square(Square::new(*sq));
+    !material.pieces.iter().zip(squares).any(on_back_rank) && material.index(squares, white_to_move) == index
+}
+
+// Distinct positions with a quiet move into `index`, found by moving the
+// pieces of the side that just moved backwards
+fn predecessors(material: &Material, index: usize) -> Vec<usize> {
+    let (squares, white_to_move) = material.squares(index);
+    let mover = if white_to_move { PieceColor::BLACK } else { PieceColor::WHITE };
+    let occupied = Bitboard::new(squares.iter().fold(0u64, |bits, sq| bits | 1 << sq));
+    let mut parents = Vec::new();
+    for (i, piece) in material.pieces.iter().enumerate().filter(|(_, p)| p.piece_color == mover) {
+        let to = Square::new(squares[i]);
+        let from = match piece.piece_type {
+            PieceType::KING => KING_MOVES[to],
+            PieceType::KNIGHT => KNIGHT_MOVES[to],
+            PieceType::BISHOP => get_bishop_attacks(to, occupied),
+            PieceType::ROOK => get_rook_attacks(to, occupied),
+            PieceType::QUEEN => get_bishop_attacks(to, occupied) | get_rook_attacks(to, occupied),
+            PieceType::PAWN => pawn_origins(to, mover, occupied),
+        };
+        for from in Bitboard::new(from.get_bitboard() & !occupied.get_bitboard()).iter() {
+            let mut parent = squares.clone();
+            parent[i] = from.to_u8();
+            parents.push(material.index(&parent, mover == PieceColor::WHITE));
+        }
+    }
+    parents.sort_unstable();
+    parents.dedup();
+    parents
+}
+
+fn pawn_origins(to: Square, color: PieceColor, occupied: Bitboard) -> Bitboard {
+    let mut origins = Bitboard::new(0);
+    let rank = to.rank().relative(color);
+    let Some(single) = to.offset(0, if color == PieceColor::WHITE { -1 } else { 1 }) else {
+        return origins;
+    };
+    if rank >= Rank::Third && !occupied.contains_square(single) {
+        origins.set_square(single);
+        if rank == Rank::Fourth {
+            let double = single.offset(0, if color == PieceColor::WHITE { -1 } else { 1 }).unwrap();
+            if !occupied.contains_square(double) {
+                origins.set_square(double);
+            }
+        }
+    }
+    origins
+}
@@ -0,0 +1,3 @@
+pub mod generate;
+pub mod probe;
+pub mod table;
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::board::board::Board;
+use crate::board::castling::types::AllowedCastling;
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+use super::table::{Dtm, DtmTable, Material, FILE_EXTENSION};
+
+const PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// A set of DTM tables, either generated in memory or loaded from files.
+#[derive(Default)]
+pub struct DtmTablebases {
+    tables: HashMap<String, DtmTable>,
+}
+
+impl DtmTablebases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every readable `.rdtm` file in the directories of `paths`,
+    /// separated like `PATH`.
+    pub fn load(paths: &str) -> Self {
+        let mut tablebases = DtmTablebases::new();
+        for dir in paths.split(PATH_SEPARATOR).filter(|dir| !dir.is_empty()) {
+            let Ok(entries) = std::fs::read_dir(Path::new(dir)) else {
+                continue;
+            };
+            for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+                if path.extension().is_some_and(|ext| ext == FILE_EXTENSION) {
+                    if let Ok(table) = DtmTable::read(&path) {
+                        tablebases.insert(table);
+                    }
+                }
+            }
+        }
+        tablebases
+    }
+
+    pub fn insert(&mut self, table: DtmTable) {
+        self.tables.insert(table.name(), table);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &DtmTable> {
+        self.tables.values()
+    }
+
+    /// Distance to mate of the position, if a table covers its material and
+    /// no castling is possible.
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        if board.game_state.castle_white != AllowedCastling::None || board.game_state.castle_black != AllowedCastling::None {
+            return None;
+        }
+        let material = Material::from_board(board)?;
+        let canonical = if material.is_canonical() { material } else { material.mirrored() };
+        self.tables.get(&canonical.name())?.probe(board)
+    }
+
+    /// The move reaching the fastest mate, or holding the draw or delaying
+    /// mate the longest, with the value of the position.
+    pub fn best_move(&self, board: &mut Board) -> Option<(MoveData, Dtm)> {
+        self.probe(board)?;
+        let mut best: Option<(MoveData, Dtm)> = None;
+        for mv in generate_moves(board).iter() {
+            board.make_move(mv);
+            let value = self.probe(board).map(Dtm::parent);
+            board.unmake_move(mv);
+            let value = value?;
+            if best.is_none_or(|(_, best_value)| value.score() > best_value.score()) {
+                best = Some((*mv, value));
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+use crate::board::board::Board;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::board::square::Square;
+
+pub const FILE_EXTENSION: &str = "rdtm";
+pub const MAX_PIECES: usize = 4;
+const MAGIC: [u8; 4] = *b"RDTM";
+const VERSION: u8 = 1;
+// Squares a1-d1-d4 the white king is mapped to in tables without pawns
+const TRIANGLE: [u8; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+const PIECE_ORDER: [PieceType; 5] = [PieceType::QUEEN, PieceType::ROOK, PieceType::BISHOP, PieceType::KNIGHT, PieceType::PAWN];
+
+/// Distance to mate in plies for the side to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dtm {
+    Draw,
+    Win(u8),
+    /// `Loss(0)` is checkmate
+    Loss(u8),
+}
+
+impl Dtm {
+    // 0 is a draw, otherwise plies + 1. Wins have an odd number of plies.
+    pub(crate) fn encode(self) -> u8 {
+        match self {
+            Dtm::Draw => 0,
+            Dtm::Win(plies) | Dtm::Loss(plies) => plies + 1,
+        }
+    }
+
+    pub(crate) fn decode(byte: u8) -> Dtm {
+        match byte {
+            0 => Dtm::Draw,
+            _ if byte.is_multiple_of(2) => Dtm::Win(byte - 1),
+            _ => Dtm::Loss(byte - 1),
+        }
+    }
+
+    /// The value of the position before the move leading here.
+    pub fn parent(self) -> Dtm {
+        match self {
+            Dtm::Draw => Dtm::Draw,
+            Dtm::Win(plies) => Dtm::Loss(plies.saturating_add(1)),
+            Dtm::Loss(plies) => Dtm::Win(plies.saturating_add(1)),
+        }
+    }
+
+    /// Orders values from the side to move's point of view: faster wins
+    /// first and slower losses last.
+    pub fn score(self) -> i32 {
+        match self {
+            Dtm::Draw => 0,
+            Dtm::Win(plies) => 1000 - plies as i32,
+            Dtm::Loss(plies) => plies as i32 - 1000,
+        }
+    }
+}
+
+/// Reason a DTM table could not be generated, read or written.
+#[derive(Debug)]
+pub enum DtmError {
+    Io(io::Error),
+    InvalidMaterial(String),
+    InvalidFile,
+    MissingTable(String),
+    TooDeep,
+}
+
+impl fmt::Display for DtmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DtmError::Io(e) => write!(f, "{}", e),
+            DtmError::InvalidMaterial(name) => write!(f, "unsupported material '{}'", name),
+            DtmError::InvalidFile => write!(f, "not a DTM table"),
+            DtmError::MissingTable(name) => write!(f, "missing table {}", name),
+            DtmError::TooDeep => write!(f, "mate is too deep to be stored"),
+        }
+    }
+}
+
+impl std::error::Error for DtmError {}
+
+impl From<io::Error> for DtmError {
+    fn from(e: io::Error) -> Self {
+        DtmError::Io(e)
+    }
+}
+
+/// The pieces of an ending: the two kings first, then the other white and
+/// black pieces from queen to pawn.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Material {
+    pub pieces: Vec<Piece>,
+}
+
+impl Material {
+    /// Parses names like `KQvK` or `KQK`.
+    pub fn from_name(name: &str) -> Result<Material, DtmError> {
+        let invalid = || DtmError::InvalidMaterial(name.to_string());
+        let (white, black) = match name.split_once('v') {
+            Some(sides) => sides,
+            None => name.rfind('K').filter(|i| *i > 0).map(|i| name.split_at(i)).ok_or_else(invalid)?,
+        };
+        let mut sides = [Vec::new(), Vec::new()];
+        for (color, side) in [(PieceColor::WHITE, white), (PieceColor::BLACK, black)] {
+            let mut chars = side.chars();
+            if chars.next() != Some('K') {
+                return Err(invalid());
+            }
+            for c in chars {
+                let piece = Piece::from_fen(&c.to_string()).filter(|p| p.piece_type != PieceType::KING).ok_or_else(invalid)?;
+                sides[color as usize].push(Piece::new(color, piece.piece_type));
+            }
+        }
+        let material = Material::new(sides.concat());
+        if material.pieces.len() > MAX_PIECES {
+            return Err(invalid());
+        }
+        Ok(material)
+    }
+
+    /// Material with the two kings and the given other pieces.
+    pub(crate) fn new(mut others: Vec<Piece>) -> Material {
+        others.sort_by_key(|p| (p.piece_color as usize, PIECE_ORDER.iter().position(|t| *t == p.piece_type)));
+        let mut pieces = vec![Piece::new(PieceColor::WHITE, PieceType::KING), Piece::new(PieceColor::BLACK, PieceType::KING)];
+        pieces.extend(others);
+        Material { pieces }
+    }
+
+    /// The material on the board, `None` with more than `MAX_PIECES`.
+    pub fn from_board(board: &Board) -> Option<Material> {
+        if board.get_all_pieces_bitboard().pop_count() as usize > MAX_PIECES {
+            return None;
+        }
+        let mut others = Vec::new();
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            for piece_type in PIECE_ORDER {
+                let count = board.get_piece_bitboard(color, piece_type).pop_count() as usize;
+                others.extend(std::iter::repeat_n(Piece::new(color, piece_type), count));
+            }
+        }
+        Some(Material::new(others))
+    }
+
+    pub fn name(&self) -> String {
+        let side = |color: PieceColor| -> String {
+            std::iter::once("K".to_string())
+                .chain(self.pieces[2..].iter().filter(|p| p.piece_color == color).map(|p| p.to_fen().to_ascii_uppercase()))
+                .collect()
+        };
+        format!("{}v{}", side(PieceColor::WHITE), side(PieceColor::BLACK))
+    }
+
+    /// The same material with colours swapped.
+    pub fn mirrored(&self) -> Material {
+        Material::new(self.pieces[2..].iter().map(|p| Piece::new(p.piece_color.opposite(), p.piece_type)).collect())
+    }
+
+    /// Tables are stored for the side with more or stronger pieces as white.
+    pub fn is_canonical(&self) -> bool {
+        let strength = |color: PieceColor| -> Vec<usize> {
+            self.pieces[2..].iter().filter(|p| p.piece_color == color).map(|p| p.piece_type as usize).collect()
+        };
+        let (white, black) = (strength(PieceColor::WHITE), strength(PieceColor::BLACK));
+        (white.len(), &white) >= (black.len(), &black)
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.pieces.iter().any(|p| p.piece_type == PieceType::PAWN)
+    }
+
+    fn king_squares(&self) -> usize {
+        if self.has_pawns() { 32 } else { TRIANGLE.len() }
+    }
+
+    pub fn table_size(&self) -> usize {
+        (2 * self.king_squares()) << (6 * (self.pieces.len() - 1))
+    }
+
+    /// Maps squares in piece order to a table index, using the board
+    /// symmetries. Pieces of the same kind are ordered by square.
+    pub fn index(&self, squares: &[u8], white_to_move: bool) -> usize {
+        let mut squares = squares.to_vec();
+        let wk = squares[0];
+        let mut transform = 0;
+        if wk & 7 > 3 {
+            transform ^= 7;
+        }
+        if !self.has_pawns() && wk >> 3 > 3 {
+            transform ^= 56;
+        }
+        for sq in squares.iter_mut() {
+            *sq ^= transform;
+        }
+        self.sort_same_pieces(&mut squares);
+        // With the king on the a1-h8 diagonal both the position and its
+        // reflection are in the triangle, the smaller one is used
+        if !self.has_pawns() && (squares[0] & 7) <= (squares[0] >> 3) {
+            let mut reflected: Vec<u8> = squares.iter().map(|sq| (sq >> 3) | ((sq & 7) << 3)).collect();
+            self.sort_same_pieces(&mut reflected);
+            if (squares[0] & 7) < (squares[0] >> 3) || reflected < squares {
+                squares = reflected;
+            }
+        }
+
+        let king = if self.has_pawns() {
+            (squares[0] >> 3) as usize * 4 + (squares[0] & 7) as usize
+        } else {
+            TRIANGLE.iter().position(|sq| *sq == squares[0]).unwrap()
+        };
+        let mut index = (!white_to_move) as usize * self.king_squares() + king;
+        for sq in &squares[1..] {
+            index = index * 64 + *sq as usize;
+        }
+        index
+    }
+
+    fn sort_same_pieces(&self, squares: &mut [u8]) {
+        let mut start = 2;
+        while start < squares.len() {
+            let end = (start..squares.len()).find(|i| self.pieces[*i] != self.pieces[start]).unwrap_or(squares.len());
+            squares[start..end].sort_unstable();
+            start = end;
+        }
+    }
+
+    /// Inverse of `index`, returns the squares and whether white is to move.
+    pub fn squares(&self, mut index: usize) -> (Vec<u8>, bool) {
+        let mut squares = vec![0; self.pieces.len()];
+        for sq in squares[1..].iter_mut().rev() {
+            *sq = (index % 64) as u8;
+            index /= 64;
+        }
+        let king = index % self.king_squares();
+        squares[0] = if self.has_pawns() { (king / 4 * 8 + king % 4) as u8 } else { TRIANGLE[king] };
+        (squares, index < self.king_squares())
+    }
+
+    pub(crate) fn board(&self, squares: &[u8], white_to_move: bool) -> Board {
+        let pieces: Vec<(Piece, Square)> = self.pieces.iter().zip(squares).map(|(p, sq)| (*p, Square::new(*sq))).collect();
+        Board::from_pieces(&pieces, if white_to_move { PieceColor::WHITE } else { PieceColor::BLACK })
+    }
+
+    /// Squares of the pieces on `board` in piece order, with colours and
+    /// ranks swapped if `mirror` is set. The board must have this material.
+    fn board_squares(&self, board: &Board, mirror: bool) -> Vec<u8> {
+        let mut squares = Vec::with_capacity(self.pieces.len());
+        let mut taken = 0u64;
+        for piece in &self.pieces {
+            let color = if mirror { piece.piece_color.opposite() } else { piece.piece_color };
+            let square = (board.get_piece_bitboard(color, piece.piece_type).get_bitboard() & !taken).trailing_zeros() as u8;
+            taken |= 1 << square;
+            squares.push(if mirror { square ^ 56 } else { square });
+        }
+        squares
+    }
+}
+
+/// A complete DTM table for one material signature.
+pub struct DtmTable {
+    pub material: Material,
+    values: Vec<u8>,
+}
+
+impl DtmTable {
+    pub(crate) fn new(material: Material, values: Vec<u8>) -> DtmTable {
+        DtmTable { material, values }
+    }
+
+    pub fn name(&self) -> String {
+        self.material.name()
+    }
+
+    /// Value of a position with this table's material or its colour
+    /// mirror. En passant rights are ignored.
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        let material = Material::from_board(board)?;
+        let mirror = if material == self.material {
+            false
+        } else if material.mirrored() == self.material {
+            true
+        } else {
+            return None;
+        };
+        let squares = self.material.board_squares(board, mirror);
+        let index = self.material.index(&squares, (board.turn == PieceColor::WHITE) != mirror);
+        Some(Dtm::decode(self.values[index]))
+    }
+
+    /// Format: magic, version, piece count, the pieces as FEN characters
+    /// and one byte per position.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([VERSION, self.material.pieces.len() as u8]);
+        bytes.extend(self.material.pieces.iter().map(|p| p.to_fen().as_bytes()[0]));
+        bytes.extend(&self.values);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<DtmTable, DtmError> {
+        if bytes.len() < 6 || bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return Err(DtmError::InvalidFile);
+        }
+        let count = bytes[5] as usize;
+        let header = bytes.get(6..6 + count).ok_or(DtmError::InvalidFile)?;
+        let pieces: Option<Vec<Piece>> = header.iter().map(|c| Piece::from_fen(&(*c as char).to_string())).collect();
+        let material = Material { pieces: pieces.ok_or(DtmError::InvalidFile)? };
+        if Material::from_name(&material.name()).ok() != Some(material.clone()) {
+            return Err(DtmError::InvalidFile);
+        }
+        let values = &bytes[6 + count..];
+        if values.len() != material.table_size() {
+            return Err(DtmError::InvalidFile);
+        }
+        Ok(DtmTable { material, values: values.to_vec() })
+    }
+
+    /// Writes `<name>.rdtm` into `dir`.
+    pub fn write(&self, dir: &Path) -> Result<(), DtmError> {
+        std::fs::write(dir.join(format!("{}.{}", self.name(), FILE_EXTENSION)), self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<DtmTable, DtmError> {
+        DtmTable::from_bytes(&std::fs::read(path)?)
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::board::board::Board;
+use crate::board::castling::types::AllowedCastling;
+use crate::board::piece::{Piece, PieceColor, PieceType};
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+use super::table::{Lookup, Material, TableFile, TableKind};
+
+const PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+const PIECE_CHARS: [(PieceType, char); 6] = [
+    (PieceType::KING, 'K'),
+    (PieceType::QUEEN, 'Q'),
+    (PieceType::ROOK, 'R'),
+    (PieceType::BISHOP, 'B'),
+    (PieceType::KNIGHT, 'N'),
+    (PieceType::PAWN, 'P'),
+];
+
+/// Game theoretical value with the 50-move rule, for the side to move.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Wdl {
+    Loss = -2,
+    /// Lost, but drawn by the 50-move rule
+    BlessedLoss = -1,
+    Draw = 0,
+    /// Won, but drawn by the 50-move rule
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            i32::MIN..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+}
+
+/// A root move with its DTZ rank. Higher ranks are better; all moves that
+/// keep a certain win share the same rank.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RootMove {
+    pub mv: MoveData,
+    pub rank: i32,
+    pub dtz: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProbeState {
+    Ok,
+    // The stored value may be wrong because a capture or pawn move is best
+    ZeroingBestMove,
+}
+
+struct TableEntry {
+    material: Material,
+    directories: Vec<PathBuf>,
+    wdl: OnceLock<Option<TableFile>>,
+    dtz: OnceLock<Option<TableFile>>,
+}
+
+impl TableEntry {
+    // Files are read on first use
+    fn file(&self, kind: TableKind) -> Option<&TableFile> {
+        let cell = match kind {
+            TableKind::Wdl => &self.wdl,
+            TableKind::Dtz => &self.dtz,
+        };
+        cell.get_or_init(|| {
+            let file_name = format!("{}.{}", self.material.name, kind.extension());
+            self.directories
+                .iter()
+                .map(|dir| dir.join(&file_name))
+                .find(|path| path.is_file())
+                .and_then(|path| TableFile::load(&path, kind, &self.material))
+        })
+        .as_ref()
+    }
+}
+
+/// Syzygy tables found in a set of directories.
+pub struct Tablebases {
+    entries: Vec<TableEntry>,
+    // Both "KRvK" and "KvKR" name the same entry
+    by_name: HashMap<String, usize>,
+    max_pieces: usize,
+}
+
+impl Tablebases {
+    /// Registers every .rtbw file in `paths`, a list of directories separated
+    /// like the `PATH` environment variable. DTZ files are looked up in the
+    /// same directories when first needed.
+    pub fn new(paths: &str) -> Tablebases {
+        let directories: Vec<PathBuf> = paths.split(PATH_SEPARATOR).filter(|p| !p.is_empty()).map(PathBuf::from).collect();
+        let mut tablebases = Tablebases {
+            entries: Vec::new(),
+            by_name: HashMap::new(),
+            max_pieces: 0,
+        };
+        for dir in &directories {
+            let Ok(read_dir) = std::fs::read_dir(dir) else { continue };
+            let mut names: Vec<String> = read_dir
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| wdl_table_name(&entry.path()))
+                .collect();
+            names.sort();
+            for name in names {
+                tablebases.add(&name, &directories);
+            }
+        }
+        tablebases
+    }
+
+    fn add(&mut self, name: &str, directories: &[PathBuf]) {
+        let Some(material) = Material::from_name(name) else { return };
+        if self.by_name.contains_key(name) {
+            return;
+        }
+        let (white, black) = name.split_once('v').unwrap();
+        self.max_pieces = self.max_pieces.max(material.piece_count);
+        self.by_name.insert(name.to_string(), self.entries.len());
+        self.by_name.insert(format!("{}v{}", black, white), self.entries.len());
+        self.entries.push(TableEntry {
+            material,
+            directories: directories.to_vec(),
+            wdl: OnceLock::new(),
+            dtz: OnceLock::new(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Largest number of pieces, kings included, of any table found.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// True if the position is covered: tables never contain castling rights
+    /// and must exist for this many pieces.
+    pub fn can_probe(&self, board: &Board) -> bool {
+        board.game_state.castle_white == AllowedCastling::None
+            && board.game_state.castle_black == AllowedCastling::None
+            && board.get_all_pieces_bitboard().pop_count() as usize <= self.max_pieces
+    }
+
+    /// WDL value of the position, for use at interior nodes. The en passant
+    /// square is taken into account; the halfmove clock is not, a position
+    /// right after a capture or pawn move is assumed.
+    pub fn probe_wdl(&self, board: &mut Board) -> Option<Wdl> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        self.search(board, false).map(|(wdl, _)| Wdl::from_value(wdl))
+    }
+
+    /// Distance to the next capture or pawn move in plies, signed like the
+    /// WDL value, assuming the halfmove clock is zero. Wins and losses that
+    /// are drawn by the 50-move rule are beyond 100. The value can be one ply
+    /// too high.
+    pub fn probe_dtz(&self, board: &mut Board) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        self.dtz(board)
+    }
+
+    /// Ranks the legal moves with the DTZ tables, best first. Moves that
+    /// win without running into the 50-move rule from the current halfmove
+    /// clock are all ranked 1000, losses -1000 unless the 50-move rule may
+    /// still save them.
+    pub fn rank_root_moves(&self, board: &mut Board) -> Option<Vec<RootMove>> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        let halfmove_clock = board.game_state.halfmove_clock as i32;
+        let mut root_moves = Vec::new();
+        for mv in generate_moves(board).iter() {
+            board.make_move(mv);
+            let dtz = if is_zeroing(mv) {
+                self.search(board, false).map(|(wdl, _)| dtz_before_zeroing(-wdl))
+            } else {
+                self.dtz(board).map(|dtz| -dtz + (-dtz).signum())
+            };
+            let is_mate = dtz == Some(2) && generate_moves(board).is_empty() && board.is_check;
+            board.unmake_move(mv);
+            let dtz = if is_mate { 1 } else { dtz? };
+            let rank = if dtz > 0 {
+                if dtz + halfmove_clock <= 99 { 1000 } else { 1000 - (dtz + halfmove_clock) }
+            } else if dtz < 0 {
+                if -dtz * 2 + halfmove_clock < 100 { -1000 } else { -1000 + (-dtz + halfmove_clock) }
+            } else {
+                0
+            };
+            root_moves.push(RootMove { mv: *mv, rank, dtz });
+        }
+        // Among equal ranks, the fastest win and the slowest loss come first
+        root_moves.sort_by_key(|root| (-root.rank, root.dtz));
+        Some(root_moves)
+    }
+
+    /// Keeps only the root moves that preserve the best DTZ rank. Returns
+    /// false and leaves `moves` alone if a table was missing.
+    pub fn filter_root_moves(&self, board: &mut Board, moves: &mut Vec<MoveData>) -> bool {
+        let Some(ranked) = self.rank_root_moves(board) else {
+            return false;
+        };
+        let Some(best) = ranked.first().map(|root| root.rank) else {
+            return false;
+        };
+        moves.retain(|mv| ranked.iter().any(|root| root.mv == *mv && root.rank == best));
+        true
+    }
+
+    fn dtz(&self, board: &mut Board) -> Option<i32> {
+        let (wdl, state) = self.search(board, true)?;
+        // DTZ files don't store draws
+        if wdl == 0 {
+            return Some(0);
+        }
+        if state == ProbeState::ZeroingBestMove {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        match self.probe_table(board, TableKind::Dtz, wdl)? {
+            Lookup::Value(dtz) => Some((dtz + if wdl.abs() == 1 { 100 } else { 0 }) * wdl.signum()),
+            Lookup::ChangeSideToMove => {
+                // The file stores the other side to move: find the best DTZ
+                // among the moves, a ply further away
+                let mut min_dtz = i32::MAX;
+                for mv in generate_moves(board).iter() {
+                    let zeroing = is_zeroing(mv);
+                    board.make_move(mv);
+                    let dtz = if zeroing {
+                        self.search(board, false).map(|(wdl, _)| -dtz_before_zeroing(wdl))
+                    } else {
+                        self.dtz(board).map(|dtz| -dtz)
+                    };
+                    let is_mate = dtz == Some(1) && generate_moves(board).is_empty() && board.is_check;
+                    board.unmake_move(mv);
+                    let mut dtz = dtz?;
+                    if is_mate {
+                        min_dtz = 1;
+                    }
+                    if !zeroing {
+                        dtz += dtz.signum();
+                    }
+                    if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                        min_dtz = dtz;
+                    }
+                }
+                Some(if min_dtz == i32::MAX { -1 } else { min_dtz })
+            }
+        }
+    }
+
+    // Tables store "don't care" values where a capture (or for DTZ a pawn
+    // move) is at least as good as the stored value, so those moves are
+    // searched and the best of them and the table value is returned.
+    fn search(&self, board: &mut Board, check_zeroing: bool) -> Option<(i32, ProbeState)> {
+        let moves = generate_moves(board);
+        let mut best = -2;
+        let mut searched = 0;
+        for mv in moves.iter() {
+            if !mv.is_capture() && (!check_zeroing || mv.piece_to_move.piece_type != PieceType::PAWN) {
+                continue;
+            }
+            searched += 1;
+            board.make_move(mv);
+            let result = self.search(board, false);
+            board.unmake_move(mv);
+            let value = -result?.0;
+            if value > best {
+                best = value;
+                if value >= 2 {
+                    return Some((value, ProbeState::ZeroingBestMove));
+                }
+            }
+        }
+
+        // With every move searched the stored value may be wrong, e.g. it
+        // ignores en passant
+        let all_searched = searched > 0 && searched == moves.len();
+        let value = if all_searched {
+            best
+        } else {
+            match self.probe_table(board, TableKind::Wdl, 0)? {
+                Lookup::Value(value) => value,
+                Lookup::ChangeSideToMove => return None,
+            }
+        };
+        if best >= value {
+            let state = if best > 0 || all_searched { ProbeState::ZeroingBestMove } else { ProbeState::Ok };
+            return Some((best, state));
+        }
+        Some((value, ProbeState::Ok))
+    }
+
+    fn probe_table(&self, board: &Board, kind: TableKind, wdl: i32) -> Option<Lookup> {
+        let all = board.get_all_pieces_bitboard();
+        if all.pop_count() == 2 {
+            return Some(Lookup::Value(0));
+        }
+        let name = format!("{}v{}", side_name(board, PieceColor::WHITE), side_name(board, PieceColor::BLACK));
+        let entry = &self.entries[*self.by_name.get(&name)?];
+        let file = entry.file(kind)?;
+
+        // Tables are stored with the first side of the name as white, and
+        // symmetric ones only with white to move
+        let flip = name != entry.material.name || (entry.material.symmetric && board.turn == PieceColor::BLACK);
+        let pieces: Vec<(u8, usize)> = all
+            .into_iter()
+            .filter_map(|sqr| board.piece_at(sqr).map(|piece| (sqr, piece)))
+            .map(|(sqr, piece)| if flip { (piece_code(piece) ^ 8, sqr.index() ^ 56) } else { (piece_code(piece), sqr.index()) })
+            .collect();
+        let white_to_move = (board.turn == PieceColor::WHITE) != flip;
+        file.probe(&pieces, white_to_move, wdl)
+    }
+}
+
+fn wdl_table_name(path: &Path) -> Option<String> {
+    if path.extension()? != TableKind::Wdl.extension() {
+        return None;
+    }
+    let name = path.file_stem()?.to_str()?;
+    Material::from_name(name).map(|_| name.to_string())
+}
+
+// Pieces from king to pawn, as in table names
+fn side_name(board: &Board, color: PieceColor) -> String {
+    PIECE_CHARS
+        .iter()
+        .flat_map(|(piece_type, c)| std::iter::repeat_n(*c, board.get_piece_bitboard(color, *piece_type).pop_count() as usize))
+        .collect()
+}
+
+fn piece_code(piece: Piece) -> u8 {
+    piece.piece_type as u8 + 1 + if piece.piece_color == PieceColor::BLACK { 8 } else { 0 }
+}
+
+fn is_zeroing(mv: &MoveData) -> bool {
+    mv.is_capture() || mv.piece_to_move.piece_type == PieceType::PAWN
+}
+
+// DTZ of the move before a capture or pawn move, which DTZ files don't store
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,748 @@
+use std::path::Path;
+use std::sync::LazyLock;
+use crate::board::square::Square;
+use crate::movegen::precomputed::KING_MOVES;
+
+pub const MAX_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+// Flags of a PairsData record
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// Flag in the first byte of a file
+const FILE_HAS_PAWNS: u8 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+impl TableKind {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TableKind::Wdl => "rtbw",
+            TableKind::Dtz => "rtbz",
+        }
+    }
+
+    // WDL files store both sides to move, DTZ files only one
+    fn sides(self) -> usize {
+        match self {
+            TableKind::Wdl => 2,
+            TableKind::Dtz => 1,
+        }
+    }
+}
+
+/// Material of a table, from its name such as "KRPvKR". The first side is
+/// stored as white in the file.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Material {
+    pub name: String,
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    pub has_unique_pieces: bool,
+    /// Pawns of the leading side, then of the other side
+    pub pawn_count: [usize; 2],
+    pub symmetric: bool,
+}
+
+impl Material {
+    pub fn from_name(name: &str) -> Option<Material> {
+        let (white, black) = name.split_once('v')?;
+        let valid = |side: &str| side.starts_with('K') && side[1..].chars().all(|c| "QRBNP".contains(c));
+        if !valid(white) || !valid(black) || white.len() + black.len() > MAX_PIECES {
+            return None;
+        }
+        let count = |side: &str, c: char| side.chars().filter(|x| *x == c).count();
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+        // The side with fewer pawns leads, which compresses better
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Some(Material {
+            name: name.to_string(),
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: [white, black].iter().any(|side| "QRBNP".chars().any(|c| count(side, c) == 1)),
+            pawn_count: if white_leads { [white_pawns, black_pawns] } else { [black_pawns, white_pawns] },
+            symmetric: white == black,
+        })
+    }
+}
+
+fn off_diagonal(sqr: usize) -> i32 {
+    (sqr / 8) as i32 - (sqr % 8) as i32
+}
+
+fn flip_diagonal(sqr: usize) -> usize {
+    ((sqr >> 3) | (sqr << 3)) & 63
+}
+
+/// Index tables shared by all files.
+struct Indices {
+    // Squares below the a1-h8 diagonal to 0..28
+    map_b1h1h7: [u64; 64],
+    // Squares of the a1-d1-d4 triangle to 0..10, diagonal last
+    map_a1d1d4: [u64; 64],
+    // The 462 placements of two kings with the first in the a1-d1-d4 triangle
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 6],
+    // a2-h7 to 0..48, higher for pawns nearer the edge and lower ranks
+    map_pawns: [u64; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+static INDICES: LazyLock<Indices> = LazyLock::new(|| {
+    let mut indices = Indices {
+        map_b1h1h7: [0; 64],
+        map_a1d1d4: [0; 64],
+        map_kk: [[0; 64]; 10],
+        binomial: [[0; 64]; 6],
+        map_pawns: [0; 64],
+        lead_pawn_idx: [[0; 64]; 6],
+        lead_pawns_size: [[0; 4]; 6],
+    };
+
+    let mut code = 0;
+    for sqr in 0..64 {
+        if off_diagonal(sqr) < 0 {
+            indices.map_b1h1h7[sqr] = code;
+            code += 1;
+        }
+    }
+
+    let mut diagonal = Vec::new();
+    code = 0;
+    for sqr in 0..=Square::D4.index() {
+        if off_diagonal(sqr) < 0 && sqr % 8 <= 3 {
+            indices.map_a1d1d4[sqr] = code;
+            code += 1;
+        } else if off_diagonal(sqr) == 0 && sqr % 8 <= 3 {
+            diagonal.push(sqr);
+        }
+    }
+    for sqr in diagonal {
+        indices.map_a1d1d4[sqr] = code;
+        code += 1;
+    }
+
+    let mut both_on_diagonal = Vec::new();
+    code = 0;
+    for idx in 0..10 {
+        for s1 in 0..=Square::D4.index() {
+            // b1 is the only square mapped to 0 that is in the triangle
+            if indices.map_a1d1d4[s1] != idx as u64 || (idx == 0 && s1 != Square::B1.index()) {
+                continue;
+            }
+            for s2 in 0..64 {
+                if s1 == s2 || KING_MOVES[Square::new(s1 as u8)].contains_square(Square::new(s2 as u8)) {
+                    continue;
+                }
+                if off_diagonal(s1) == 0 && off_diagonal(s2) > 0 {
+                    continue;
+                }
+                if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                    both_on_diagonal.push((idx, s2));
+                } else {
+                    indices.map_kk[idx][s2] = code;
+                    code += 1;
+                }
+            }
+        }
+    }
+    for (idx, s2) in both_on_diagonal {
+        indices.map_kk[idx][s2] = code;
+        code += 1;
+    }
+
+    indices.binomial[0][0] = 1;
+    for n in 1..64 {
+        for k in 0..6.min(n + 1) {
+            indices.binomial[k][n] = if k > 0 { indices.binomial[k - 1][n - 1] } else { 0 } + if k < n { indices.binomial[k][n - 1] } else { 0 };
+        }
+    }
+
+    let mut available: i64 = 47;
+    for lead_pawns in 1..=5 {
+        for file in 0..4 {
+            let mut idx = 0;
+            for rank in 1..7 {
+                let sqr = rank * 8 + file;
+                if lead_pawns == 1 {
+                    indices.map_pawns[sqr] = available as u64;
+                    indices.map_pawns[sqr ^ 7] = (available - 1) as u64;
+                    available -= 2;
+                }
+                indices.lead_pawn_idx[lead_pawns][sqr] = idx;
+                idx += indices.binomial[lead_pawns - 1][indices.map_pawns[sqr] as usize];
+            }
+            indices.lead_pawns_size[lead_pawns][file] = idx;
+        }
+    }
+    indices
+});
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    data.get(offset..offset + 2).map_or(0, |b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4).map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4).map_or(0, |b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Decoding information for one table of a file: there is one per side to
+/// move, and with pawns one per file of the leading pawn.
+#[derive(Default, Clone)]
+struct PairsData {
+    flags: u8,
+    block_size: usize,
+    span: u64,
+    num_blocks: usize,
+    min_sym_len: u8,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    // Piece codes in encoding order: 1..6 white pawn..king, 9..14 black
+    pieces: [u8; MAX_PIECES],
+    group_idx: [u64; MAX_PIECES + 1],
+    group_len: [usize; MAX_PIECES + 1],
+    map_idx: [usize; 4],
+}
+
+impl PairsData {
+    // Each btree entry holds two 12 bit symbols
+    fn left(&self, data: &[u8], sym: usize) -> usize {
+        let entry = self.btree + 3 * sym;
+        ((data[entry + 1] as usize & 0xF) << 8) | data[entry] as usize
+    }
+
+    fn right(&self, data: &[u8], sym: usize) -> usize {
+        let entry = self.btree + 3 * sym;
+        ((data[entry + 2] as usize) << 4) | (data[entry + 1] as usize >> 4)
+    }
+
+    fn table_size(&self) -> u64 {
+        let groups = self.group_len.iter().position(|len| *len == 0).unwrap_or(MAX_PIECES);
+        self.group_idx[groups]
+    }
+}
+
+/// A loaded .rtbw or .rtbz file.
+pub struct TableFile {
+    kind: TableKind,
+    data: Vec<u8>,
+    material: Material,
+    // [side to move][leading pawn file]
+    pairs: [[PairsData; 4]; 2],
+    map: usize,
+}
+
+/// Result of looking up a position in a file.
+pub enum Lookup {
+    Value(i32),
+    /// DTZ files store one side to move, the other one needs a search
+    ChangeSideToMove,
+}
+
+impl TableFile {
+    pub fn load(path: &Path, kind: TableKind, material: &Material) -> Option<TableFile> {
+        let data = std::fs::read(path).ok()?;
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+        if data.len() < 5 || data[0..4] != magic {
+            return None;
+        }
+        let mut table = TableFile {
+            kind,
+            data,
+            material: material.clone(),
+            pairs: Default::default(),
+            map: 0,
+        };
+        table.parse()?;
+        Some(table)
+    }
+
+    fn sides(&self) -> usize {
+        if self.kind.sides() == 2 && !self.material.symmetric { 2 } else { 1 }
+    }
+
+    fn files(&self) -> usize {
+        if self.material.has_pawns { 4 } else { 1 }
+    }
+
+    fn get(&self, stm: usize, file: usize) -> &PairsData {
+        &self.pairs[stm % self.kind.sides()][if self.material.has_pawns { file } else { 0 }]
+    }
+
+    // Offsets are relative to the start of the file, which is what the
+    // alignment rules of the format refer to.
+    fn parse(&mut self) -> Option<()> {
+        let mut pos = 4;
+        let flags = *self.data.get(pos)?;
+        if (flags & FILE_HAS_PAWNS != 0) != self.material.has_pawns {
+            return None;
+        }
+        pos += 1;
+
+        let sides = self.sides();
+        let both_pawns = self.material.has_pawns && self.material.pawn_count[1] > 0;
+        for file in 0..self.files() {
+            let first = *self.data.get(pos)?;
+            let second = if both_pawns { *self.data.get(pos + 1)? } else { 0xFF };
+            let order = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            pos += 1 + both_pawns as usize;
+            for k in 0..self.material.piece_count {
+                let byte = *self.data.get(pos)?;
+                for side in 0..sides {
+                    self.pairs[side][file].pieces[k] = if side == 0 { byte & 0xF } else { byte >> 4 };
+                }
+                pos += 1;
+            }
+            for (side, side_order) in order.iter().enumerate().take(sides) {
+                set_groups(&self.material, &mut self.pairs[side][file], *side_order, file);
+            }
+        }
+        pos += pos & 1;
+
+        for file in 0..self.files() {
+            for side in 0..sides {
+                pos = self.set_sizes(side, file, pos)?;
+            }
+        }
+        if self.kind == TableKind::Dtz {
+            pos = self.set_dtz_map(pos)?;
+        }
+        for file in 0..self.files() {
+            for side in 0..sides {
+                let d = &mut self.pairs[side][file];
+                d.sparse_index = pos;
+                pos += d.sparse_index_size * 6;
+            }
+        }
+        for file in 0..self.files() {
+            for side in 0..sides {
+                let d = &mut self.pairs[side][file];
+                d.block_length = pos;
+                pos += d.block_length_size * 2;
+            }
+        }
+        for file in 0..self.files() {
+            for side in 0..sides {
+                pos = (pos + 0x3F) & !0x3F;
+                let d = &mut self.pairs[side][file];
+                d.data = pos;
+                pos += d.num_blocks * d.block_size;
+                // Single value tables have no blocks and may end before the alignment
+                if d.num_blocks > 0 && pos > self.data.len() {
+                    return None;
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn set_sizes(&mut self, side: usize, file: usize, mut pos: usize) -> Option<usize> {
+        let data = &self.data;
+        let d = &mut self.pairs[side][file];
+        d.flags = *data.get(pos)?;
+        pos += 1;
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            // The only value is stored in place of the minimum symbol length
+            d.min_sym_len = *data.get(pos)?;
+            return Some(pos + 1);
+        }
+
+        let table_size = d.table_size();
+        d.block_size = 1 << *data.get(pos)?;
+        d.span = 1 << *data.get(pos + 1)?;
+        d.sparse_index_size = table_size.div_ceil(d.span) as usize;
+        let padding = *data.get(pos + 2)? as usize;
+        d.num_blocks = read_u32(data, pos + 3) as usize;
+        // Padded so that the sparse index never points past the end
+        d.block_length_size = d.num_blocks + padding;
+        let max_sym_len = *data.get(pos + 7)?;
+        d.min_sym_len = *data.get(pos + 8)?;
+        if max_sym_len < d.min_sym_len || max_sym_len > 64 {
+            return None;
+        }
+        pos += 9;
+        d.lowest_sym = pos;
+
+        // Canonical Huffman codes: longer codes have lower values. base64[i]
+        // is the lowest code of length min_sym_len + i, left aligned.
+        let lengths = (max_sym_len - d.min_sym_len) as usize + 1;
+        d.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = read_u16(data, d.lowest_sym + 2 * i) as u64;
+            let next_lowest = read_u16(data, d.lowest_sym + 2 * (i + 1)) as u64;
+            d.base64[i] = (d.base64[i + 1] + lowest).wrapping_sub(next_lowest) / 2;
+        }
+        for (i, base) in d.base64.iter_mut().enumerate() {
+            let shift = 64 - i as u32 - d.min_sym_len as u32;
+            *base = if shift >= 64 { 0 } else { *base << shift };
+        }
+        pos += lengths * 2;
+
+        let symbols = read_u16(data, pos) as usize;
+        pos += 2;
+        d.btree = pos;
+        if data.len() < pos + 3 * symbols {
+            return None;
+        }
+
+        // Recursive pairing: every symbol is a leaf or stands for a pair of
+        // symbols. symlen counts the values a symbol expands to, minus one.
+        d.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                d.symlen[sym] = set_symlen(d, data, sym, &mut visited)?;
+            }
+        }
+        Some(pos + symbols * 3 + (symbols & 1))
+    }
+
+    fn set_dtz_map(&mut self, mut pos: usize) -> Option<usize> {
+        self.map = pos;
+        for file in 0..self.files() {
+            let flags = self.pairs[0][file].flags;
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+            if flags & FLAG_WIDE != 0 {
+                pos += pos & 1;
+                for i in 0..4 {
+                    self.pairs[0][file].map_idx[i] = (pos - self.map) / 2 + 1;
+                    pos += 2 * read_u16(&self.data, pos) as usize + 2;
+                }
+            } else {
+                for i in 0..4 {
+                    self.pairs[0][file].map_idx[i] = pos - self.map + 1;
+                    pos += *self.data.get(pos)? as usize + 1;
+                }
+            }
+        }
+        Some(pos + (pos & 1))
+    }
+
+    /// Looks up a position given as (piece code, square) pairs with white
+    /// being the first side of the table name. `wdl` is the position's WDL
+    /// value, needed to decode DTZ values.
+    pub fn probe(&self, pieces: &[(u8, usize)], white_to_move: bool, wdl: i32) -> Option<Lookup> {
+        let indices = &*INDICES;
+        let size = pieces.len();
+        let mut squares = [0usize; MAX_PIECES];
+        let mut codes = [0u8; MAX_PIECES];
+        let stm = !white_to_move as usize;
+        let mut lead_pawns = 0;
+        let mut tb_file = 0;
+        let mut rest = 0;
+
+        if self.material.has_pawns {
+            // Pawns of the leading colour come first in every table
+            let lead = self.get(0, 0).pieces[0];
+            for (code, sqr) in pieces.iter().filter(|(code, _)| *code == lead) {
+                codes[lead_pawns] = *code;
+                squares[lead_pawns] = *sqr;
+                lead_pawns += 1;
+            }
+            if lead_pawns == 0 {
+                return None;
+            }
+            let lead_index = (0..lead_pawns).max_by_key(|i| indices.map_pawns[squares[*i]]).unwrap();
+            squares.swap(0, lead_index);
+            tb_file = squares[0] % 8;
+            if tb_file > 3 {
+                tb_file = 7 - tb_file;
+            }
+        }
+
+        if self.kind == TableKind::Dtz {
+            let flags = self.get(stm, tb_file).flags;
+            if (flags & FLAG_STM) as usize != stm && (self.material.has_pawns || !self.material.symmetric) {
+                return Some(Lookup::ChangeSideToMove);
+            }
+        }
+
+        let lead = codes[0];
+        for (code, sqr) in pieces.iter().filter(|(code, _)| lead_pawns == 0 || *code != lead) {
+            codes[lead_pawns + rest] = *code;
+            squares[lead_pawns + rest] = *sqr;
+            rest += 1;
+        }
+        if lead_pawns + rest != size || size != self.material.piece_count {
+            return None;
+        }
+
+        let d = self.get(stm, tb_file);
+        // Reorder the pieces into the sequence the table was encoded with
+        for i in lead_pawns..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|j| d.pieces[i] == codes[*j]) {
+                codes.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+
+        if squares[0] % 8 > 3 {
+            for sqr in squares.iter_mut().take(size) {
+                *sqr ^= 7;
+            }
+        }
+
+        let mut idx;
+        if self.material.has_pawns {
+            idx = indices.lead_pawn_idx[lead_pawns][squares[0]];
+            squares[1..lead_pawns].sort_by_key(|sqr| indices.map_pawns[*sqr]);
+            for (i, sqr) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                idx += indices.binomial[i][indices.map_pawns[*sqr] as usize];
+            }
+        } else {
+            if squares[0] / 8 > 3 {
+                for sqr in squares.iter_mut().take(size) {
+                    *sqr ^= 56;
+                }
+            }
+            // The first piece of the leading group off the a1-h8 diagonal
+            // goes below it
+            for i in 0..d.group_len[0] {
+                if off_diagonal(squares[i]) == 0 {
+                    continue;
+                }
+                if off_diagonal(squares[i]) > 0 {
+                    for sqr in squares.iter_mut().take(size).skip(i) {
+                        *sqr = flip_diagonal(*sqr);
+                    }
+                }
+                break;
+            }
+            idx = if self.material.has_unique_pieces {
+                encode_unique_pieces(indices, &squares)
+            } else {
+                indices.map_kk[indices.map_a1d1d4[squares[0]] as usize][squares[1]]
+            };
+        }
+
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = self.material.has_pawns && self.material.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let group_end = group_start + d.group_len[next];
+            squares[group_start..group_end].sort_unstable();
+            let mut n = 0;
+            for i in group_start..group_end {
+                // Squares taken by earlier groups are skipped
+                let adjust = squares[..group_start].iter().filter(|sqr| squares[i] > **sqr).count();
+                let free = squares[i] as i64 - adjust as i64 - 8 * remaining_pawns as i64;
+                if !(0..64).contains(&free) {
+                    return None;
+                }
+                n += indices.binomial[i - group_start + 1][free as usize];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start = group_end;
+            next += 1;
+        }
+
+        let value = self.decompress(d, idx)?;
+        Some(Lookup::Value(self.map_score(tb_file, value, wdl)))
+    }
+
+    fn decompress(&self, d: &PairsData, idx: u64) -> Option<i32> {
+        let data = &self.data;
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(d.min_sym_len as i32);
+        }
+        if d.span == 0 || idx >= d.table_size() {
+            return None;
+        }
+
+        // The sparse index points into the block list every `span` values
+        let k = (idx / d.span) as usize;
+        let entry = d.sparse_index + 6 * k;
+        let mut block = read_u32(data, entry) as usize;
+        let mut offset = read_u16(data, entry + 4) as i64;
+        offset += (idx % d.span) as i64 - (d.span / 2) as i64;
+
+        let block_length = |block: usize| read_u16(data, d.block_length + 2 * block) as i64;
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block) + 1;
+        }
+        while offset > block_length(block) {
+            offset -= block_length(block) + 1;
+            block += 1;
+            if block >= d.block_length_size {
+                return None;
+            }
+        }
+
+        let mut ptr = d.data + block * d.block_size;
+        let mut buf64 = ((read_u32_be(data, ptr) as u64) << 32) | read_u32_be(data, ptr + 4) as u64;
+        ptr += 8;
+        let mut buf64_size = 64;
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while len + 1 < d.base64.len() && buf64 < d.base64[len] {
+                len += 1;
+            }
+            let shift = 64 - len as u32 - d.min_sym_len as u32;
+            sym = ((buf64 - d.base64[len]).checked_shr(shift).unwrap_or(0) as usize) + read_u16(data, d.lowest_sym + 2 * len) as usize;
+            let count = *d.symlen.get(sym)? as i64 + 1;
+            if offset < count {
+                break;
+            }
+            offset -= count;
+            let len = len as u32 + d.min_sym_len as u32;
+            buf64 = buf64.checked_shl(len).unwrap_or(0);
+            buf64_size -= len as i32;
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (read_u32_be(data, ptr) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        // Expand pairs until reaching the leaf holding the value
+        while *d.symlen.get(sym)? != 0 {
+            let left = d.left(data, sym);
+            let left_count = *d.symlen.get(left)? as i64 + 1;
+            if offset < left_count {
+                sym = left;
+            } else {
+                offset -= left_count;
+                sym = d.right(data, sym);
+            }
+        }
+        Some(d.left(data, sym) as i32)
+    }
+
+    fn map_score(&self, file: usize, value: i32, wdl: i32) -> i32 {
+        if self.kind == TableKind::Wdl {
+            return value - 2;
+        }
+        // Values are stored per WDL outcome, ordered by frequency
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+        let d = self.get(0, file);
+        let mut value = value;
+        if d.flags & FLAG_MAPPED != 0 {
+            let idx = d.map_idx[WDL_MAP[(wdl + 2) as usize]];
+            value = if d.flags & FLAG_WIDE != 0 {
+                read_u16(&self.data, self.map + 2 * (idx + value as usize)) as i32
+            } else {
+                self.data.get(self.map + idx + value as usize).copied().unwrap_or(0) as i32
+            };
+        }
+        // Stored in moves unless the flags say plies
+        let in_moves = (wdl == 2 && d.flags & FLAG_WIN_PLIES == 0) || (wdl == -2 && d.flags & FLAG_LOSS_PLIES == 0) || wdl.abs() == 1;
+        if in_moves {
+            value *= 2;
+        }
+        value + 1
+    }
+}
+
+fn set_symlen(d: &mut PairsData, data: &[u8], sym: usize, visited: &mut [bool]) -> Option<u8> {
+    visited[sym] = true;
+    let right = d.right(data, sym);
+    if right == 0xFFF {
+        return Some(0);
+    }
+    let left = d.left(data, sym);
+    for child in [left, right] {
+        if !*visited.get(child)? {
+            d.symlen[child] = set_symlen(d, data, child, visited)?;
+        }
+    }
+    Some(d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1))
+}
+
+// Splits the pieces into groups encoded together: the leading group (three
+// unique pieces, the two kings, or the leading pawns), then runs of equal
+// pieces. `order` gives the position of the leading group and the other
+// side's pawns in the mixed radix index.
+fn set_groups(material: &Material, d: &mut PairsData, order: [u8; 2], file: usize) {
+    let indices = &*INDICES;
+    let mut n = 0;
+    let mut first_len: i32 = if material.has_pawns { 0 } else if material.has_unique_pieces { 3 } else { 2 };
+    d.group_len[n] = 1;
+    for i in 1..material.piece_count {
+        first_len -= 1;
+        if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+            d.group_len[n] += 1;
+        } else {
+            n += 1;
+            d.group_len[n] = 1;
+        }
+    }
+    n += 1;
+    d.group_len[n] = 0;
+
+    let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+    let mut next = if both_pawns { 2 } else { 1 };
+    let mut free_squares = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+    let mut idx: u64 = 1;
+    let mut k = 0;
+    while next < n || k == order[0] as usize || k == order[1] as usize {
+        if k == order[0] as usize {
+            d.group_idx[0] = idx;
+            idx *= if material.has_pawns {
+                indices.lead_pawns_size[d.group_len[0]][file]
+            } else if material.has_unique_pieces {
+                31332
+            } else {
+                462
+            };
+        } else if k == order[1] as usize {
+            d.group_idx[1] = idx;
+            idx *= indices.binomial[d.group_len[1]][48 - d.group_len[0]];
+        } else {
+            d.group_idx[next] = idx;
+            idx *= indices.binomial[d.group_len[next]][free_squares];
+            free_squares -= d.group_len[next];
+            next += 1;
+        }
+        k += 1;
+    }
+    d.group_idx[n] = idx;
+}
+
+// Three unique leading pieces, the first in the a1-d1-d4 triangle
+fn encode_unique_pieces(indices: &Indices, squares: &[usize]) -> u64 {
+    let adjust1 = (squares[1] > squares[0]) as u64;
+    let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+    let rank = |sqr: usize| (sqr / 8) as u64;
+    let (s0, s1, s2) = (squares[0], squares[1], squares[2]);
+    if off_diagonal(s0) != 0 {
+        (indices.map_a1d1d4[s0] * 63 + (s1 as u64 - adjust1)) * 62 + s2 as u64 - adjust2
+    } else if off_diagonal(s1) != 0 {
+        (6 * 63 + rank(s0) * 28 + indices.map_b1h1h7[s1]) * 62 + s2 as u64 - adjust2
+    } else if off_diagonal(s2) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + rank(s0) * 7 * 28 + (rank(s1) - adjust1) * 28 + indices.map_b1h1h7[s2]
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(s0) * 7 * 6 + (rank(s1) - adjust1) * 6 + (rank(s2) - adjust2)
+    }
+}
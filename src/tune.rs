@@ -0,0 +1,164 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use crate::board::board::{Board, FenError};
+use crate::eval::evaluate_white;
+use crate::eval::params::EvalParams;
+
+/// Range searched for the sigmoid scale, and the precision it is fitted to.
+const K_RANGE: (f64, f64) = (0.0, 4.0);
+const K_PRECISION: f64 = 0.001;
+
+/// Reason a dataset could not be loaded.
+#[derive(Debug)]
+pub enum DatasetError {
+    Io(io::Error),
+    /// Line number, from 1, without a result
+    MissingResult(usize),
+    InvalidFen(usize, FenError),
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io(e) => write!(f, "{}", e),
+            DatasetError::MissingResult(line) => write!(f, "line {} has no game result", line),
+            DatasetError::InvalidFen(line, e) => write!(f, "line {}: {}", line, e),
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+impl From<io::Error> for DatasetError {
+    fn from(e: io::Error) -> Self {
+        DatasetError::Io(e)
+    }
+}
+
+/// A quiet position and the result of the game it was taken from.
+pub struct LabelledPosition {
+    pub board: Board,
+    /// 1 for a white win, 0.5 for a draw and 0 for a black win
+    pub result: f64,
+}
+
+/// Splits a dataset line into its FEN and result. The result follows the
+/// FEN either in brackets, as in `[0.5]`, or as a PGN result such as
+/// `"1-0";`, optionally after an EPD opcode. FENs without move counters
+/// get `0 1`.
+pub fn parse_line(line: &str) -> Option<(String, f64)> {
+    let line = line.trim().trim_end_matches(';').trim_end();
+    let (fen, result) = if let Some(open) = line.rfind('[').filter(|_| line.ends_with(']')) {
+        (&line[..open], line[open + 1..line.len() - 1].trim().parse::<f64>().ok().filter(|r| (0.0..=1.0).contains(r))?)
+    } else {
+        let (fen, result) = line.rsplit_once(char::is_whitespace)?;
+        let result = match result.trim_matches('"') {
+            "1-0" => 1.0,
+            "0-1" => 0.0,
+            "1/2-1/2" => 0.5,
+            _ => return None,
+        };
+        let fen = fen.trim_end();
+        (fen.strip_suffix(" c9").unwrap_or(fen), result)
+    };
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let fen = if fields.len() == 4 { format!("{} 0 1", fields.join(" ")) } else { fields.join(" ") };
+    Some((fen, result))
+}
+
+/// Reads one labelled position per line, skipping blank lines.
+pub fn load_dataset(path: impl AsRef<Path>) -> Result<Vec<LabelledPosition>, DatasetError> {
+    let mut positions = Vec::new();
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (fen, result) = parse_line(line).ok_or(DatasetError::MissingResult(i + 1))?;
+        let board = Board::try_from_fen(&fen).map_err(|e| DatasetError::InvalidFen(i + 1, e))?;
+        positions.push(LabelledPosition { board, result });
+    }
+    Ok(positions)
+}
+
+/// Expected score for white of a position evaluated at `score` centipawns.
+pub fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+/// Mean squared difference between the results and the expected scores of
+/// the evaluations, computed on all cores.
+pub fn mean_error(positions: &[LabelledPosition], params: &EvalParams, k: f64) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = positions.len().div_ceil(threads);
+    let total: f64 = thread::scope(|scope| {
+        let workers: Vec<_> = positions
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk.iter().map(|position| (position.result - sigmoid(evaluate_white(&position.board, params) as f64, k)).powi(2)).sum::<f64>()
+                })
+            })
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap()).sum()
+    });
+    total / positions.len() as f64
+}
+
+/// Sigmoid scale that makes `params` predict the results best, found by
+/// narrowing the search interval around the best value.
+pub fn fit_k(positions: &[LabelledPosition], params: &EvalParams) -> f64 {
+    let (mut low, mut high) = K_RANGE;
+    let mut step = (high - low) / 10.0;
+    let mut best = (low + high) / 2.0;
+    while step >= K_PRECISION {
+        let mut k = low;
+        let mut best_error = f64::MAX;
+        while k <= high {
+            let error = mean_error(positions, params, k);
+            if error < best_error {
+                (best, best_error) = (k, error);
+            }
+            k += step;
+        }
+        (low, high) = ((best - step).max(K_RANGE.0), (best + step).min(K_RANGE.1));
+        step /= 10.0;
+    }
+    best
+}
+
+/// Texel's local search: every weight in turn is moved up or down by one
+/// while that lowers the error, until a whole pass changes nothing or
+/// `max_passes` is reached. `report` receives the pass number, the error
+/// and the weights after every pass. Returns the final error.
+pub fn tune(positions: &[LabelledPosition], params: &mut EvalParams, k: f64, max_passes: usize, mut report: impl FnMut(usize, f64, &EvalParams)) -> f64 {
+    let mut values = params.to_vec();
+    let mut best_error = mean_error(positions, params, k);
+    for pass in 1..=max_passes {
+        let mut improved = false;
+        for i in 0..values.len() {
+            for delta in [1, -1] {
+                values[i] += delta;
+                params.set_from_slice(&values);
+                let error = mean_error(positions, params, k);
+                if error < best_error {
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+                values[i] -= delta;
+            }
+        }
+        params.set_from_slice(&values);
+        report(pass, best_error, params);
+        if !improved {
+            break;
+        }
+    }
+    best_error
+}
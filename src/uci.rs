@@ -0,0 +1,480 @@
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::board::board::Board;
+use crate::book::polyglot::{BookSelection, PolyglotBook};
+use crate::eval::evaluate_cached;
+use crate::eval::nnue::Network;
+use crate::eval::params::EvalParams;
+use crate::eval::pawns::PawnTable;
+use crate::eval::trace::trace;
+use crate::movegen::generate::generate_moves;
+use crate::movegen::movedata::MoveData;
+use crate::search::limits::{SearchControl, SearchLimits};
+use crate::search::tt::{DEFAULT_HASH_MB, MAX_HASH_MB};
+use crate::search::{SearchConfig, SearchResult, Searcher};
+use crate::tablebase::dtm::probe::DtmTablebases;
+use crate::tablebase::syzygy::probe::Tablebases;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_BOOK_DEPTH: u32 = 20;
+const MAX_BOOK_DEPTH: u32 = 200;
+const MAX_MULTI_PV: usize = 256;
+// How often `run` looks for output of a search in the background
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+// Search techniques that can be switched off for A/B testing
+const SEARCH_TOGGLES: [&str; 13] = [
+    "PVS",
+    "Aspiration",
+    "NullMove",
+    "LateMoveReductions",
+    "ReverseFutility",
+    "Futility",
+    "Razoring",
+    "LateMovePruning",
+    "TranspositionTable",
+    "CheckExtension",
+    "SingularExtension",
+    "RecaptureExtension",
+    "PawnPushExtension",
+];
+
+/// Engine settings changed through `setoption`.
+pub struct UciOptions {
+    pub own_book: bool,
+    pub book_file: String,
+    /// Number of full moves from the start of the game the book is used for
+    pub book_depth: u32,
+    pub book_selection: BookSelection,
+    pub syzygy_path: String,
+    pub dtm_path: String,
+    /// File of evaluation weights written by the tuner, empty for the defaults
+    pub eval_params_file: String,
+    /// Network weights file, empty if none is loaded
+    pub eval_file: String,
+    /// Whether the network replaces the hand-crafted evaluation
+    pub use_nnue: bool,
+    /// Chess960 castling, written as king takes rook
+    pub chess960: bool,
+}
+
+impl Default for UciOptions {
+    fn default() -> Self {
+        UciOptions {
+            own_book: false,
+            book_file: String::new(),
+            book_depth: DEFAULT_BOOK_DEPTH,
+            book_selection: BookSelection::WeightedRandom,
+            syzygy_path: String::new(),
+            dtm_path: String::new(),
+            eval_params_file: String::new(),
+            eval_file: String::new(),
+            use_nnue: false,
+            chess960: false,
+        }
+    }
+}
+
+/// A search started by `go`, running on its own thread.
+struct BackgroundSearch {
+    control: Arc<SearchControl>,
+    output: Receiver<String>,
+    handle: JoinHandle<()>,
+}
+
+pub struct Uci {
+    board: Board,
+    options: UciOptions,
+    book: Option<PolyglotBook>,
+    tablebases: Option<Arc<Tablebases>>,
+    dtm_tables: Option<DtmTablebases>,
+    searcher: Arc<Mutex<Searcher>>,
+    background: Option<BackgroundSearch>,
+    // Copy of the searcher's weights, for `eval` while a search runs
+    eval_params: EvalParams,
+    network: Option<Arc<Network>>,
+    // Hashes of the positions before the current one
+    game_history: Vec<u64>,
+    // Plies since the start of the game, taken from the FEN move number
+    game_ply: u32,
+}
+
+impl Default for Uci {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Uci {
+            board: Board::from_fen(START_FEN),
+            options: UciOptions::default(),
+            book: None,
+            tablebases: None,
+            dtm_tables: None,
+            searcher: Arc::default(),
+            background: None,
+            eval_params: EvalParams::default(),
+            network: None,
+            game_history: Vec::new(),
+            game_ply: 0,
+        }
+    }
+
+    /// Reads commands from stdin until `quit` or end of input.
+    pub fn run(&mut self) {
+        // Commands are read on their own thread, so that the output of a
+        // search in the background is written while waiting for them
+        let (sender, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut stdout = io::stdout();
+        loop {
+            let responses = match commands.recv_timeout(POLL_INTERVAL) {
+                Ok(line) if line.trim() == "quit" => break,
+                Ok(line) => self.handle_command(&line),
+                Err(RecvTimeoutError::Timeout) => self.poll(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            for response in responses {
+                let _ = writeln!(stdout, "{}", response);
+            }
+            let _ = stdout.flush();
+        }
+        self.stop_search();
+    }
+
+    /// Returns the lines sent by a search in the background since the last
+    /// call, its best move included once it is done.
+    pub fn poll(&mut self) -> Vec<String> {
+        let Some(background) = &self.background else { return vec![] };
+        let mut output: Vec<String> = background.output.try_iter().collect();
+        if background.handle.is_finished() {
+            output.extend(self.stop_search());
+        }
+        output
+    }
+
+    /// Executes one command and returns the lines to send back to the GUI.
+    /// `go` returns at once, with the best move only for a book or
+    /// tablebase move, and searches in the background until its limits are
+    /// reached or `stop`, and `go ponder` until `ponderhit` as well; `poll`
+    /// collects the output meanwhile and `wait` blocks for it.
+    pub fn handle_command(&mut self, line: &str) -> Vec<String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        // The searcher belongs to the background search while it runs
+        let mut output = match tokens.first() {
+            Some(&"stop" | &"go" | &"position" | &"setoption" | &"ucinewgame") => self.stop_search(),
+            _ => self.poll(),
+        };
+        output.extend(match tokens.first() {
+            Some(&"uci") => vec![
+                format!("id name RookBot {}", env!("CARGO_PKG_VERSION")),
+                "id author the RookBot authors".to_string(),
+                "option name OwnBook type check default false".to_string(),
+                "option name BookFile type string default <empty>".to_string(),
+                format!("option name BookDepth type spin default {} min 0 max {}", DEFAULT_BOOK_DEPTH, MAX_BOOK_DEPTH),
+                "option name BookBestMove type check default false".to_string(),
+                "option name SyzygyPath type string default <empty>".to_string(),
+                "option name DtmPath type string default <empty>".to_string(),
+                "option name EvalParams type string default <empty>".to_string(),
+                "option name EvalFile type string default <empty>".to_string(),
+                "option name UseNNUE type check default false".to_string(),
+                format!("option name Hash type spin default {} min 1 max {}", DEFAULT_HASH_MB, MAX_HASH_MB),
+                format!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTI_PV),
+                "option name Ponder type check default false".to_string(),
+                "option name UCI_Chess960 type check default false".to_string(),
+            ]
+            .into_iter()
+            .chain(SEARCH_TOGGLES.iter().map(|name| format!("option name {} type check default true", name)))
+            .chain(["uciok".to_string()])
+            .collect(),
+            Some(&"isready") => vec!["readyok".to_string()],
+            Some(&"ucinewgame") => {
+                self.searcher.lock().unwrap().clear();
+                self.set_position(Board::from_fen(START_FEN), &[]);
+                vec![]
+            }
+            Some(&"setoption") => self.set_option(&tokens[1..]).into_iter().collect(),
+            Some(&"position") => self.position(&tokens[1..]).into_iter().collect(),
+            Some(&"go") => self.go(&tokens[1..]),
+            Some(&"eval") => self.eval(&tokens[1..]),
+            Some(&"ponderhit") => {
+                if let Some(background) = &self.background {
+                    background.control.ponderhit();
+                }
+                vec![]
+            }
+            _ => vec![],
+        });
+        output
+    }
+
+    /// Blocks until the search in the background has sent its best move and
+    /// returns its remaining output. Never returns for a `go infinite` or a
+    /// `go ponder` that is not released.
+    pub fn wait(&mut self) -> Vec<String> {
+        let Some(background) = self.background.take() else { return vec![] };
+        let _ = background.handle.join();
+        background.output.try_iter().collect()
+    }
+
+    /// Stops a search in the background and returns its remaining output.
+    fn stop_search(&mut self) -> Vec<String> {
+        let Some(background) = self.background.take() else { return vec![] };
+        background.control.stop();
+        let _ = background.handle.join();
+        background.output.try_iter().collect()
+    }
+
+    fn set_option(&mut self, tokens: &[&str]) -> Option<String> {
+        // setoption name <id> [value <x>], both parts may contain spaces
+        let value_at = tokens.iter().position(|t| *t == "value").unwrap_or(tokens.len());
+        let name = tokens.get(1..value_at)?.join(" ");
+        let value = tokens.get(value_at + 1..).map(|v| v.join(" ")).unwrap_or_default();
+        match name.to_ascii_lowercase().as_str() {
+            "ownbook" => self.options.own_book = value == "true",
+            "bookbestmove" => {
+                self.options.book_selection = if value == "true" { BookSelection::BestWeight } else { BookSelection::WeightedRandom }
+            }
+            "bookdepth" => match value.parse::<u32>() {
+                Ok(depth) => self.options.book_depth = depth.min(MAX_BOOK_DEPTH),
+                Err(_) => return Some(format!("info string invalid BookDepth {}", value)),
+            },
+            "bookfile" => {
+                self.book = None;
+                self.options.book_file = if value == "<empty>" { String::new() } else { value };
+                if !self.options.book_file.is_empty() {
+                    match PolyglotBook::open(&self.options.book_file) {
+                        Ok(book) => self.book = Some(book),
+                        Err(e) => return Some(format!("info string cannot open book {}: {}", self.options.book_file, e)),
+                    }
+                }
+            }
+            "syzygypath" => {
+                self.options.syzygy_path = if value == "<empty>" { String::new() } else { value };
+                let tablebases = Tablebases::new(&self.options.syzygy_path);
+                self.tablebases = (!tablebases.is_empty()).then(|| Arc::new(tablebases));
+                self.searcher.lock().unwrap().set_tablebases(self.tablebases.clone());
+                let max_pieces = self.tablebases.as_ref().map_or(0, |tb| tb.max_pieces());
+                return Some(format!("info string found {}-piece tablebases", max_pieces));
+            }
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) => self.searcher.lock().unwrap().resize_tt(megabytes),
+                Err(_) => return Some(format!("info string invalid Hash {}", value)),
+            },
+            // The GUI decides when to send `go ponder`, nothing to set up
+            "ponder" => {}
+            // Applies from the next `position`
+            "uci_chess960" => self.options.chess960 = value == "true",
+            "multipv" => match value.parse::<usize>() {
+                Ok(lines) => self.searcher.lock().unwrap().multi_pv = lines.clamp(1, MAX_MULTI_PV),
+                Err(_) => return Some(format!("info string invalid MultiPV {}", value)),
+            },
+            "evalparams" => {
+                self.options.eval_params_file = if value == "<empty>" { String::new() } else { value };
+                let params = if self.options.eval_params_file.is_empty() { Ok(EvalParams::default()) } else { EvalParams::load(&self.options.eval_params_file) };
+                match params {
+                    Ok(params) => {
+                        self.searcher.lock().unwrap().set_eval_params(params.clone());
+                        self.eval_params = params;
+                    }
+                    Err(e) => return Some(format!("info string cannot load evaluation parameters {}: {}", self.options.eval_params_file, e)),
+                }
+            }
+            "evalfile" => {
+                self.network = None;
+                self.options.eval_file = if value == "<empty>" { String::new() } else { value };
+                let loaded = if self.options.eval_file.is_empty() {
+                    None
+                } else {
+                    match Network::load(&self.options.eval_file) {
+                        Ok(network) => {
+                            self.network = Some(Arc::new(network));
+                            Some(format!("info string loaded network {}", self.options.eval_file))
+                        }
+                        Err(e) => Some(format!("info string cannot load network {}: {}", self.options.eval_file, e)),
+                    }
+                };
+                self.update_network();
+                return loaded;
+            }
+            "usennue" => {
+                self.options.use_nnue = value == "true";
+                self.update_network();
+                if self.options.use_nnue && self.network.is_none() {
+                    return Some("info string no network loaded, set EvalFile first".to_string());
+                }
+            }
+            "dtmpath" => {
+                self.options.dtm_path = if value == "<empty>" { String::new() } else { value };
+                let tables = DtmTablebases::load(&self.options.dtm_path);
+                let count = tables.len();
+                self.dtm_tables = (!tables.is_empty()).then_some(tables);
+                return Some(format!("info string found {} DTM tables", count));
+            }
+            _ => match search_toggle(&mut self.searcher.lock().unwrap().config, &name.to_ascii_lowercase()) {
+                Some(toggle) => *toggle = value == "true",
+                None => return Some(format!("info string unknown option {}", name)),
+            },
+        }
+        None
+    }
+
+    // Gives the searcher the network if it is enabled
+    fn update_network(&mut self) {
+        let network = if self.options.use_nnue { self.network.clone() } else { None };
+        self.searcher.lock().unwrap().set_network(network);
+    }
+
+    // Breaks down the evaluation of the current board, or of `fen <FEN>`.
+    // The network has no breakdown, only its score is reported.
+    fn eval(&self, tokens: &[&str]) -> Vec<String> {
+        let mut board = match tokens.first() {
+            None => self.board.clone(),
+            Some(&"fen") => match Board::try_parse_fen(&tokens[1..].join(" "), self.options.chess960) {
+                Ok(board) => board,
+                Err(e) => return vec![format!("info string invalid fen {}: {}", tokens[1..].join(" "), e)],
+            },
+            _ => return vec!["info string expected fen".to_string()],
+        };
+        match &self.network {
+            Some(network) if self.options.use_nnue => {
+                board.set_network(Some(network.clone()));
+                let score = evaluate_cached(&board, &mut PawnTable::new(), &self.eval_params);
+                vec![format!("info string NNUE evaluation {} cp, no breakdown available", score)]
+            }
+            _ => vec![trace(&board, &self.eval_params).to_json()],
+        }
+    }
+
+    fn position(&mut self, tokens: &[&str]) -> Option<String> {
+        let moves_at = tokens.iter().position(|t| *t == "moves").unwrap_or(tokens.len());
+        let moves = tokens.get(moves_at + 1..).unwrap_or(&[]);
+        let fen = match tokens.first() {
+            Some(&"startpos") => START_FEN.to_string(),
+            Some(&"fen") => tokens[1..moves_at].join(" "),
+            _ => return Some("info string expected startpos or fen".to_string()),
+        };
+        match Board::try_parse_fen(&fen, self.options.chess960) {
+            Ok(board) => self.set_position(board, moves),
+            Err(e) => Some(format!("info string invalid fen {}: {}", fen, e)),
+        }
+    }
+
+    fn set_position(&mut self, board: Board, moves: &[&str]) -> Option<String> {
+        self.board = board;
+        self.game_history.clear();
+        self.game_ply = 2 * (self.board.game_state.fullmove_clock.max(1) as u32 - 1) + self.board.turn as u32;
+        for uci in moves {
+            match self.board.parse_uci_move(uci) {
+                Ok(mv) => {
+                    self.game_history.push(self.board.get_hash());
+                    self.board.make_move(&mv);
+                    self.game_ply += 1;
+                }
+                Err(e) => return Some(format!("info string illegal move {}: {}", uci, e)),
+            }
+        }
+        None
+    }
+
+    fn go(&mut self, tokens: &[&str]) -> Vec<String> {
+        let mut limits = SearchLimits::parse(tokens);
+        let mut output = Vec::new();
+        // No book or tablebase move when pondering or infinite, nothing may
+        // be played before the search is released
+        if !limits.ponder && !limits.infinite {
+            let mut best = None;
+            if let Some(result) = self.book_move() {
+                match result {
+                    Ok(mv) => best = mv,
+                    Err(e) => output.push(format!("info string book read failed: {}", e)),
+                }
+            }
+            if best.is_none() {
+                best = self.dtm_move();
+            }
+            if let Some(mv) = best {
+                output.push(format!("bestmove {}", mv.to_uci(self.board.chess960)));
+                return output;
+            }
+        }
+        // The tables leave the search the moves that keep the best result
+        limits.root_moves = self.tablebase_root_moves();
+
+        let control = Arc::new(SearchControl::new(limits.ponder));
+        self.searcher.lock().unwrap().set_control(control.clone());
+        let (sender, receiver) = mpsc::channel();
+        let (searcher, mut board, history) = (self.searcher.clone(), self.board.clone(), self.game_history.clone());
+        let release = control.clone();
+        let handle = thread::spawn(move || {
+            let chess960 = board.chess960;
+            let result = searcher.lock().unwrap().search(&mut board, &limits, &history, |info| {
+                let _ = sender.send(info.to_uci(chess960));
+            });
+            release.wait_for_release(limits.infinite);
+            let _ = sender.send(bestmove(&mut board, &result));
+        });
+        self.background = Some(BackgroundSearch { control, output: receiver, handle });
+        output
+    }
+
+    fn book_move(&mut self) -> Option<io::Result<Option<MoveData>>> {
+        if !self.options.own_book || self.game_ply >= 2 * self.options.book_depth {
+            return None;
+        }
+        let book = self.book.as_mut()?;
+        Some(book.probe(&mut self.board, self.options.book_selection))
+    }
+
+    fn dtm_move(&mut self) -> Option<MoveData> {
+        self.dtm_tables.as_ref()?.best_move(&mut self.board).map(|(mv, _)| mv)
+    }
+
+    // Root moves keeping the best DTZ rank, for the search to choose from.
+    // Empty when the position is not in the tables.
+    fn tablebase_root_moves(&mut self) -> Vec<MoveData> {
+        let Some(tablebases) = &self.tablebases else { return Vec::new() };
+        let mut moves: Vec<MoveData> = generate_moves(&mut self.board).iter().copied().collect();
+        if tablebases.filter_root_moves(&mut self.board, &mut moves) { moves } else { Vec::new() }
+    }
+}
+
+/// The `bestmove` line of a search, with the reply to ponder on when the PV
+/// has one.
+fn bestmove(board: &mut Board, result: &SearchResult) -> String {
+    let Some(best) = result.best_move else { return "bestmove 0000".to_string() };
+    match result.ponder_move(board) {
+        Some(reply) => format!("bestmove {} ponder {}", best.to_uci(board.chess960), reply.to_uci(board.chess960)),
+        None => format!("bestmove {}", best.to_uci(board.chess960)),
+    }
+}
+
+fn search_toggle<'a>(config: &'a mut SearchConfig, name: &str) -> Option<&'a mut bool> {
+    match name {
+        "pvs" => Some(&mut config.pvs),
+        "aspiration" => Some(&mut config.aspiration),
+        "nullmove" => Some(&mut config.null_move),
+        "latemovereductions" => Some(&mut config.late_move_reductions),
+        "reversefutility" => Some(&mut config.reverse_futility),
+        "futility" => Some(&mut config.futility),
+        "razoring" => Some(&mut config.razoring),
+        "latemovepruning" => Some(&mut config.late_move_pruning),
+        "transpositiontable" => Some(&mut config.transposition_table),
+        "checkextension" => Some(&mut config.check_extension),
+        "singularextension" => Some(&mut config.singular_extension),
+        "recaptureextension" => Some(&mut config.recapture_extension),
+        "pawnpushextension" => Some(&mut config.pawn_push_extension),
+        _ => None,
+    }
+}
@@ -0,0 +1,84 @@
+use rookbot::board::bitboard::{Bitboard, Direction};
+use rookbot::board::piece::PieceColor;
+use rookbot::board::square::{File, Rank, Square};
+
+fn squares(squares: &[Square]) -> Bitboard {
+    squares.iter().copied().collect()
+}
+
+#[test]
+fn iteration_visits_squares_lowest_first() {
+    let bitboard = squares(&[Square::H8, Square::A1, Square::E4]);
+    assert_eq!(bitboard.iter().collect::<Vec<_>>(), vec![Square::A1, Square::E4, Square::H8]);
+    assert_eq!(bitboard.iter().len(), 3);
+    assert_eq!(bitboard.into_iter().count(), bitboard.pop_count() as usize);
+    assert_eq!((bitboard.lsb(), bitboard.msb()), (Some(Square::A1), Some(Square::H8)));
+    assert_eq!((Bitboard::new(0).lsb(), Bitboard::new(0).msb()), (None, None));
+    assert!(Bitboard::new(0).iter().next().is_none());
+    assert!(bitboard.more_than_one());
+    assert!(!Square::E4.bitboard().more_than_one());
+    assert_eq!(Bitboard::new(u64::MAX).iter().collect::<Vec<_>>(), Square::all().collect::<Vec<_>>());
+}
+
+#[test]
+fn shifts_drop_squares_off_the_edge() {
+    let edges = squares(&[Square::A1, Square::H1, Square::A8, Square::H8, Square::D4]);
+    let cases = [
+        (Direction::North, vec![Square::A2, Square::H2, Square::D5]),
+        (Direction::South, vec![Square::D3, Square::A7, Square::H7]),
+        (Direction::East, vec![Square::B1, Square::E4, Square::B8]),
+        (Direction::West, vec![Square::G1, Square::C4, Square::G8]),
+        (Direction::NorthEast, vec![Square::B2, Square::E5]),
+        (Direction::NorthWest, vec![Square::G2, Square::C5]),
+        (Direction::SouthEast, vec![Square::E3, Square::B7]),
+        (Direction::SouthWest, vec![Square::C3, Square::G7]),
+    ];
+    for (direction, expected) in cases {
+        assert_eq!(edges.shift(direction), squares(&expected), "{:?}", direction);
+    }
+    assert_eq!(edges.north_east(), edges.shift(Direction::NorthEast));
+    assert_eq!(edges.south_west(), edges.shift(Direction::SouthWest));
+}
+
+#[test]
+fn fills_and_pawn_attacks() {
+    let pawn = Square::D4.bitboard();
+    assert_eq!(pawn.north_fill(), squares(&[Square::D4, Square::D5, Square::D6, Square::D7, Square::D8]));
+    assert_eq!(pawn.south_fill(), squares(&[Square::D1, Square::D2, Square::D3, Square::D4]));
+    assert_eq!(pawn.file_fill(), Bitboard::file_mask(File::D));
+    assert_eq!(pawn.forward_fill(PieceColor::BLACK), pawn.south_fill());
+    assert_eq!(pawn.pawn_attacks(PieceColor::WHITE), squares(&[Square::C5, Square::E5]));
+    assert_eq!(Square::A7.bitboard().pawn_attacks(PieceColor::BLACK), Square::B6.bitboard());
+}
+
+#[test]
+fn file_and_rank_masks() {
+    assert_eq!(Bitboard::file_mask(File::A).pop_count(), 8);
+    assert_eq!(Bitboard::file_mask(File::H), squares(&Rank::ALL.map(|rank| Square::from_file_rank(File::H, rank))));
+    assert_eq!(Bitboard::rank_mask(Rank::Eighth), squares(&File::ALL.map(|file| Square::from_file_rank(file, Rank::Eighth))));
+    assert_eq!(Bitboard::file_of(Square::C6) & Bitboard::rank_of(Square::C6), Square::C6.bitboard());
+    let all = Rank::ALL.into_iter().fold(Bitboard::new(0), |mask, rank| mask | Bitboard::rank_mask(rank));
+    assert_eq!(all, Bitboard::new(u64::MAX));
+}
+
+#[test]
+fn between_and_line() {
+    assert_eq!(Bitboard::between(Square::A1, Square::D4), squares(&[Square::B2, Square::C3]));
+    assert_eq!(Bitboard::between(Square::D4, Square::A1), Bitboard::between(Square::A1, Square::D4));
+    assert_eq!(Bitboard::between(Square::E1, Square::E8).pop_count(), 6);
+    assert_eq!(Bitboard::between(Square::B1, Square::C1), Bitboard::new(0));
+    assert_eq!(Bitboard::between(Square::A1, Square::B3), Bitboard::new(0));
+
+    assert_eq!(Bitboard::line(Square::C3, Square::E5), Bitboard::line(Square::A1, Square::H8));
+    assert_eq!(Bitboard::line(Square::A1, Square::H8).pop_count(), 8);
+    assert_eq!(Bitboard::line(Square::B2, Square::B7), Bitboard::file_mask(File::B));
+    assert_eq!(Bitboard::line(Square::A1, Square::B3), Bitboard::new(0));
+    for a in Square::all() {
+        for b in Square::all().filter(|&b| b != a) {
+            let line = Bitboard::line(a, b);
+            // The squares between lie on the line, which holds both ends
+            assert_eq!(Bitboard::between(a, b) & !line, Bitboard::new(0));
+            assert_eq!(line.is_empty(), !(line.contains_square(a) && line.contains_square(b)));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use rookbot::board::board::Board;
+use rookbot::board::castling::types::CastlingSide;
+use rookbot::board::invariants::InvariantError;
+use rookbot::board::piece::{Piece, PieceColor, PieceType};
+use rookbot::board::square::Square;
+use rookbot::movegen::generate::generate_moves;
+use rookbot::movegen::movedata::{MoveData, MoveType};
+use rookbot::perft::BENCH_POSITIONS;
+
+// xorshift, so that the games are the same on every run
+fn random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn play(board: &mut Board, moves: &[&str]) {
+    for uci in moves {
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.make_move(&mv);
+    }
+}
+
+#[test]
+fn incremental_hash_matches_recomputed_hash() {
+    let mut state = 1;
+    for fen in BENCH_POSITIONS {
+        let mut board = Board::from_fen(fen);
+        let start = board.get_hash();
+        assert_eq!(start, board.compute_hash());
+        let mut played = Vec::new();
+        for _ in 0..40 {
+            let moves = generate_moves(&mut board);
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves[random(&mut state) as usize % moves.len()];
+            board.make_move(&mv);
+            played.push(mv);
+            assert_eq!(board.get_hash(), board.compute_hash(), "{} after {}", fen, mv.to_uci(false));
+            assert_eq!(board.get_pawn_hash(), board.compute_pawn_hash());
+        }
+        for mv in played.iter().rev() {
+            board.unmake_move(mv);
+        }
+        assert_eq!(board.get_hash(), start, "{}", fen);
+    }
+}
+
+#[test]
+fn hash_depends_on_the_whole_position() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let (mut first, mut second) = (Board::from_fen(start), Board::from_fen(start));
+    play(&mut first, &["g1f3", "g8f6", "b1c3"]);
+    play(&mut second, &["b1c3", "g8f6", "g1f3"]);
+    assert_eq!(first.get_hash(), second.get_hash());
+
+    let hash = |fen: &str| Board::from_fen(fen).get_hash();
+    let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    assert_ne!(hash(after_e4), hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+    assert_ne!(hash(after_e4), hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1"));
+    assert_ne!(hash(after_e4), hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Kkq e3 0 1"));
+}
+
+#[test]
+fn null_move_passes_the_turn_and_unmakes() {
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let mut board = Board::from_fen(fen);
+    let hash = board.get_hash();
+    assert!(board.make_null_move());
+    assert_eq!(board.turn, PieceColor::WHITE);
+    assert_eq!(board.game_state.en_passant_square, None);
+    assert_ne!(board.get_hash(), hash);
+    assert_eq!(board.get_hash(), board.compute_hash());
+    board.unmake_null_move();
+    assert_eq!((board.to_fen(), board.get_hash()), (fen.to_string(), hash));
+
+    // Not allowed in check
+    let fen = "R3k3/8/8/8/8/8/8/4K3 b - - 0 1";
+    let mut board = Board::from_fen(fen);
+    assert!(!board.make_null_move());
+    assert_eq!(board.to_fen(), fen);
+}
+
+#[test]
+fn validate_catches_corrupted_boards() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(Board::from_fen(start).validate(), Ok(()));
+
+    let mut board = Board::from_fen(start);
+    board.squares[Square::E4] = Some(Piece::new(PieceColor::WHITE, PieceType::PAWN));
+    assert_eq!(board.validate(), Err(InvariantError::MailboxMismatch(Square::E4)));
+
+    let mut board = Board::from_fen(start);
+    board.turn = PieceColor::BLACK;
+    assert_eq!(board.validate(), Err(InvariantError::HashMismatch));
+
+    let cases = [
+        ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", InvariantError::KingCount(PieceColor::WHITE, 2)),
+        ("8/8/8/8/8/8/8/4K3 w - - 0 1", InvariantError::KingCount(PieceColor::BLACK, 0)),
+        ("r3k2r/8/8/8/8/8/8/R3K1R1 w K - 0 1", InvariantError::CastlingPiecesMissing(PieceColor::WHITE, CastlingSide::Kingside)),
+        ("4k3/8/8/8/8/8/8/4K3 b - e3 0 1", InvariantError::InvalidEnPassant),
+    ];
+    for (fen, error) in cases {
+        assert_eq!(Board::from_fen(fen).validate(), Err(error), "{}", fen);
+    }
+}
+
+// make_move validates the board in debug builds, a move onto an own
+// piece leaves it in two piece bitboards
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "after make_move e2d1")]
+fn make_move_validates_in_debug_builds() {
+    let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let pawn = Piece::new(PieceColor::WHITE, PieceType::PAWN);
+    board.make_move(&MoveData::new(Square::E2, Square::D1, pawn, MoveType::Normal));
+}
@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use rookbot::board::board::Board;
+use rookbot::book::keys::polyglot_key;
+use rookbot::book::polyglot::{BookEntry, BookSelection, PolyglotBook};
+use rookbot::uci::Uci;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// Test positions from the Polyglot book format specification
+const SPEC_KEYS: [(&str, u64); 9] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c),
+    ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 0x823c9b50fd114196),
+    ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756b94461c50fb0),
+    ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a607ca3f242c1),
+    ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fdd303c946bdd9),
+    ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637),
+    ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f9b829b279560),
+];
+
+fn raw_move(uci: &str) -> u16 {
+    let square = |s: &[u8]| (s[0] - b'a') as u16 | ((s[1] - b'1') as u16) << 3;
+    let bytes = uci.as_bytes();
+    let promotion = match bytes.get(4) {
+        Some(b'n') => 1,
+        Some(b'b') => 2,
+        Some(b'r') => 3,
+        Some(b'q') => 4,
+        _ => 0,
+    };
+    square(&bytes[2..4]) | square(&bytes[0..2]) << 6 | promotion << 12
+}
+
+fn entry(fen: &str, uci: &str, weight: u16) -> BookEntry {
+    BookEntry { key: polyglot_key(&Board::from_fen(fen)), raw_move: raw_move(uci), weight, learn: 0 }
+}
+
+fn write_book(name: &str, mut entries: Vec<BookEntry>) -> PathBuf {
+    entries.sort_by_key(|entry| entry.key);
+    let path = std::env::temp_dir().join(format!("rookbot-{}-{}.bin", name, std::process::id()));
+    std::fs::write(&path, entries.iter().flat_map(|entry| entry.to_bytes()).collect::<Vec<u8>>()).unwrap();
+    path
+}
+
+#[test]
+fn keys_match_specification() {
+    for (fen, key) in SPEC_KEYS {
+        assert_eq!(polyglot_key(&Board::from_fen(fen)), key, "{}", fen);
+    }
+}
+
+#[test]
+fn entries_decode_to_generated_moves() {
+    let castling_fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let mut board = Board::from_fen(castling_fen);
+    let kingside = entry(castling_fen, "e1h1", 1).decode_move(&mut board).unwrap();
+    assert_eq!(kingside.to_uci(false), "e1g1");
+    assert!(kingside.is_castling());
+    let queenside = entry(castling_fen, "e1a1", 1).decode_move(&mut board).unwrap();
+    assert_eq!(queenside.to_uci(false), "e1c1");
+
+    let promotion_fen = "8/1P6/8/8/8/8/8/k6K w - - 0 1";
+    let mut board = Board::from_fen(promotion_fen);
+    assert_eq!(entry(promotion_fen, "b7b8n", 1).decode_move(&mut board).unwrap().to_uci(false), "b7b8n");
+    assert_eq!(entry(promotion_fen, "b7c8q", 1).decode_move(&mut board), None);
+}
+
+#[test]
+fn book_lookup_and_selection() {
+    let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let mut entries = vec![
+        entry(START_FEN, "e2e4", 10),
+        entry(START_FEN, "d2d4", 30),
+        entry(START_FEN, "g1f3", 0),
+        entry(after_e4, "c7c5", 5),
+        // Collides with the start position but is not legal there
+        entry(START_FEN, "e1e8", 100),
+    ];
+    entries.extend((0..200).map(|i| BookEntry { key: i * 0x0123_4567_89AB_CDEF, raw_move: 0, weight: 1, learn: 0 }));
+    let path = write_book("lookup", entries);
+    let mut book = PolyglotBook::open(&path).unwrap();
+    assert_eq!(book.len(), 205);
+
+    let mut board = Board::from_fen(START_FEN);
+    assert_eq!(book.entries(polyglot_key(&board)).unwrap().len(), 4);
+    let best = book.probe(&mut board, BookSelection::BestWeight).unwrap().unwrap();
+    assert_eq!(best.to_uci(false), "d2d4");
+
+    book.set_seed(7);
+    let mut picks = std::collections::HashMap::new();
+    for _ in 0..400 {
+        let mv = book.probe(&mut board, BookSelection::WeightedRandom).unwrap().unwrap();
+        *picks.entry(mv.to_uci(false)).or_insert(0) += 1;
+    }
+    assert_eq!(picks.len(), 2, "{:?}", picks);
+    assert!(picks["d2d4"] > picks["e2e4"], "{:?}", picks);
+
+    let mut board = Board::from_fen(after_e4);
+    assert_eq!(book.probe(&mut board, BookSelection::BestWeight).unwrap().unwrap().to_uci(false), "c7c5");
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(book.probe(&mut board, BookSelection::BestWeight).unwrap(), None);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn uci_plays_book_moves() {
+    let path = write_book("uci", vec![entry(START_FEN, "g1f3", 1), entry("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1", "g8f6", 1)]);
+    let mut uci = Uci::new();
+    assert!(uci.handle_command("uci").contains(&"option name OwnBook type check default false".to_string()));
+    assert!(uci.handle_command(&format!("setoption name BookFile value {}", path.display())).is_empty());
+    assert!(uci.handle_command("setoption name OwnBook value true").is_empty());
+    uci.handle_command("position startpos");
+    assert_eq!(uci.handle_command("go"), vec!["bestmove g1f3"]);
+    uci.handle_command("position startpos moves g1f3");
+    assert_eq!(uci.handle_command("go"), vec!["bestmove g8f6"]);
+
+    uci.handle_command("setoption name BookDepth value 0");
+    uci.handle_command("position startpos");
+    assert_ne!(uci.handle_command("go"), vec!["bestmove g1f3"]);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+use rookbot::board::board::Board;
+use rookbot::movegen::generate::generate_moves;
+use rookbot::tablebase::dtm::generate::generate;
+use rookbot::tablebase::dtm::probe::DtmTablebases;
+use rookbot::tablebase::dtm::table::{Dtm, DtmError, DtmTable, Material};
+use rookbot::uci::Uci;
+
+fn probe(tables: &DtmTablebases, fen: &str) -> Option<Dtm> {
+    tables.probe(&Board::from_fen(fen))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rookbot-dtm-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn material_names() {
+    let kbnk = Material::from_name("KBNK").unwrap();
+    assert_eq!(kbnk.name(), "KBNvK");
+    assert_eq!(Material::from_name("KNvKR").unwrap().name(), "KNvKR");
+    assert!(!Material::from_name("KNvKR").unwrap().is_canonical());
+    assert_eq!(Material::from_name("KNvKR").unwrap().mirrored().name(), "KRvKN");
+    assert!(Material::from_name("KPvKP").unwrap().is_canonical());
+    assert_eq!(Material::from_board(&Board::from_fen("8/8/3k4/8/2p5/8/1B6/K7 w - - 0 1")).unwrap().name(), "KBvKP");
+    for name in ["KQRBvK", "QvK", "KXvK", "K"] {
+        assert!(matches!(Material::from_name(name), Err(DtmError::InvalidMaterial(_))), "{}", name);
+    }
+}
+
+#[test]
+fn queen_endings_are_solved() {
+    let mut tables = DtmTablebases::new();
+    generate("KQK", &mut tables).unwrap();
+    assert!(tables.contains("KvK") && tables.contains("KQvK"));
+
+    assert_eq!(probe(&tables, "k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(probe(&tables, "k5Q1/8/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Loss(0)));
+    assert_eq!(probe(&tables, "K7/8/1k6/8/8/8/8/6q1 b - - 0 1"), Some(Dtm::Win(1)));
+    // Stalemate and a hanging queen
+    assert_eq!(probe(&tables, "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(probe(&tables, "8/8/8/8/8/8/1Q6/k3K3 b - - 0 1"), Some(Dtm::Draw));
+    // One of the longest mates, ten moves
+    assert_eq!(probe(&tables, "8/8/8/5k2/8/8/1Q6/K7 w - - 0 1"), Some(Dtm::Win(19)));
+    assert_eq!(probe(&tables, "8/8/8/5k2/8/8/1Q6/K7 w K - 0 1"), None);
+    assert_eq!(probe(&tables, "8/8/8/5k2/8/8/1R6/K7 w - - 0 1"), None);
+
+    // Following the best moves mates in exactly the stored number of plies
+    let mut board = Board::from_fen("8/8/8/5k2/8/8/1Q6/K7 w - - 0 1");
+    for plies in (1..=19).rev() {
+        let (mv, value) = tables.best_move(&mut board).unwrap();
+        assert_eq!(value.score().abs(), 1000 - plies);
+        board.make_move(&mv);
+    }
+    assert!(generate_moves(&mut board).is_empty() && board.is_check);
+}
+
+#[test]
+fn pawn_endings_use_promotion_tables() {
+    let mut tables = DtmTablebases::new();
+    generate("KPvK", &mut tables).unwrap();
+    for name in ["KQvK", "KRvK", "KBvK", "KNvK"] {
+        assert!(tables.contains(name), "{}", name);
+    }
+    assert!(matches!(probe(&tables, "8/8/8/8/8/8/P7/K6k w - - 0 1"), Some(Dtm::Win(_))));
+    assert_eq!(probe(&tables, "k7/8/8/8/8/8/P7/K7 w - - 0 1"), Some(Dtm::Draw));
+    assert!(matches!(probe(&tables, "k6K/8/8/8/8/8/7p/8 b - - 0 1"), Some(Dtm::Win(_))));
+    assert_eq!(probe(&tables, "8/p7/8/8/8/8/8/K6k b - - 0 1"), Some(Dtm::Draw));
+}
+
+#[test]
+fn tables_round_trip_through_files() {
+    let mut tables = DtmTablebases::new();
+    generate("KRvK", &mut tables).unwrap();
+    let dir = temp_dir("files");
+    for table in tables.tables() {
+        table.write(&dir).unwrap();
+    }
+    std::fs::write(dir.join("KQvK.rdtm"), b"RDTM\x01\x03Kkq").unwrap();
+    let loaded = DtmTablebases::load(dir.to_str().unwrap());
+    assert_eq!(loaded.len(), 2);
+    let fen = "8/8/8/8/8/2k5/1R6/K7 w - - 0 1";
+    assert_eq!(probe(&loaded, fen), Some(Dtm::Win(31)));
+    assert_eq!(probe(&loaded, fen), probe(&tables, fen));
+    assert!(matches!(DtmTable::read(&dir.join("KQvK.rdtm")), Err(DtmError::InvalidFile)));
+    assert!(matches!(DtmTable::read(&dir.join("KPvK.rdtm")), Err(DtmError::Io(_))));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn uci_plays_dtm_moves() {
+    let mut tables = DtmTablebases::new();
+    generate("KQvK", &mut tables).unwrap();
+    let dir = temp_dir("uci");
+    for table in tables.tables() {
+        table.write(&dir).unwrap();
+    }
+    let mut uci = Uci::new();
+    assert_eq!(uci.handle_command(&format!("setoption name DtmPath value {}", dir.display())), vec!["info string found 2 DTM tables"]);
+    uci.handle_command("position fen k7/8/1K6/8/8/8/8/6Q1 w - - 0 1");
+    assert_eq!(uci.handle_command("go"), vec!["bestmove g1g8"]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
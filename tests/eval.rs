@@ -0,0 +1,181 @@
+use rookbot::board::board::Board;
+use rookbot::board::piece::{Piece, PieceColor, PieceType};
+use rookbot::board::square::Square;
+use rookbot::eval::attacks::AttackMaps;
+use rookbot::eval::endgame::{SCALE_DRAW, SCALE_NORMAL};
+use rookbot::eval::params::{EvalParams, DEFAULT_PARAMS};
+use rookbot::eval::pawns::PawnTable;
+use rookbot::eval::trace::trace;
+use rookbot::eval::{evaluate, evaluate_cached, king, kpk, pieces, Score, KNOWN_WIN};
+use rookbot::tablebase::dtm::generate::generate;
+use rookbot::tablebase::dtm::probe::DtmTablebases;
+use rookbot::tablebase::dtm::table::Dtm;
+
+#[test]
+fn kpk_bitbase_matches_generated_tables() {
+    let mut tables = DtmTablebases::new();
+    generate("KPvK", &mut tables).unwrap();
+    let (white_king, black_king, white_pawn) = (Piece::new(PieceColor::WHITE, PieceType::KING), Piece::new(PieceColor::BLACK, PieceType::KING), Piece::new(PieceColor::WHITE, PieceType::PAWN));
+    let mut checked = 0;
+    for pawn in 8..56 {
+        for wk in 0..64 {
+            for bk in 0..64 {
+                for turn in [PieceColor::WHITE, PieceColor::BLACK] {
+                    if wk == bk || pawn == wk || pawn == bk {
+                        continue;
+                    }
+                    let board = Board::from_pieces(&[(white_king, Square::new(wk)), (black_king, Square::new(bk)), (white_pawn, Square::new(pawn))], turn);
+                    let their_king = Square::new(if turn == PieceColor::WHITE { bk } else { wk });
+                    if board.is_square_attacked_by(their_king, turn) {
+                        continue;
+                    }
+                    let dtm = tables.probe(&board).unwrap();
+                    let expected = matches!((turn, dtm), (PieceColor::WHITE, Dtm::Win(_)) | (PieceColor::BLACK, Dtm::Loss(_)));
+                    assert_eq!(kpk::probe(wk as usize, pawn as usize, bk as usize, turn == PieceColor::WHITE), expected, "{}", board.to_fen());
+                    checked += 1;
+                }
+            }
+        }
+    }
+    assert!(checked > 300_000);
+}
+
+#[test]
+fn kpk_positions_are_scored_exactly() {
+    // Won for the side with the pawn, whichever colour it is
+    assert!(evaluate(&Board::from_fen("8/8/8/8/8/8/P7/K6k w - - 0 1")) > KNOWN_WIN);
+    assert!(evaluate(&Board::from_fen("8/8/8/8/8/8/P7/K6k b - - 0 1")) < -KNOWN_WIN);
+    assert!(evaluate(&Board::from_fen("k6K/p7/8/8/8/8/8/8 b - - 0 1")) > KNOWN_WIN);
+    // Drawn despite the extra pawn
+    assert_eq!(evaluate(&Board::from_fen("k7/8/8/8/8/8/P7/K7 w - - 0 1")), 0);
+    assert_eq!(evaluate(&Board::from_fen("8/p7/8/8/8/8/8/K6k b - - 0 1")), 0);
+    assert_eq!(evaluate(&Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1")), -(KNOWN_WIN + 104));
+    assert_eq!(evaluate(&Board::from_fen("4k3/8/4P3/4K3/8/8/8/8 b - - 0 1")), 0);
+    // Other material is scored normally
+    assert_eq!(evaluate(&Board::from_fen("4k3/8/8/8/8/8/PP6/4K3 w - - 0 1")), 224);
+}
+
+#[test]
+fn specialised_endgames() {
+    let eval = |fen: &str| evaluate(&Board::from_fen(fen));
+    // Bare king driven to the edge, and to the bishop's corner with KBNK
+    assert!(eval("7k/8/5K2/8/8/8/8/R7 w - - 0 1") > eval("8/8/8/4k3/8/2K5/8/R7 w - - 0 1"));
+    assert!(eval("8/8/8/4k3/8/2K5/8/R7 w - - 0 1") > KNOWN_WIN);
+    assert!(eval("8/8/8/8/8/2K5/8/k1B1N3 w - - 0 1") > eval("8/8/8/8/8/5K2/8/2B1N2k w - - 0 1"));
+    // Rook against pawn: won with the king in front, close with an advanced supported pawn
+    assert!(eval("k7/8/8/8/4p3/8/4K3/7R w - - 0 1") > 400);
+    assert!(eval("7K/8/8/8/8/8/2pk4/7R w - - 0 1") < 100);
+    // Queen against a supported bishop pawn or knight pawn on the seventh
+    assert!(eval("8/8/8/8/8/8/1kp5/5Q1K w - - 0 1") < 200);
+    assert!(eval("8/8/8/8/8/8/kp6/5Q1K w - - 0 1") >= 800);
+    assert!((0..100).contains(&eval("8/8/8/4k3/8/8/8/KR4b1 w - - 0 1")));
+    assert!(eval("8/8/8/4k3/8/8/8/KR4n1 b - - 0 1") < 0);
+}
+
+#[test]
+fn drawish_endings_are_scaled() {
+    let eval = |fen: &str| evaluate(&Board::from_fen(fen));
+    let scale = |fen: &str| trace(&Board::from_fen(fen), &DEFAULT_PARAMS).scale;
+    // Opposite-coloured bishops, against bishops of the same colour
+    assert_eq!(scale("4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1"), DEFAULT_PARAMS.opposite_bishops_scale);
+    assert_eq!(scale("4k3/8/8/8/8/8/PP1b4/2B4K w - - 0 1"), SCALE_NORMAL);
+    assert!(eval("4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1") > 0);
+    assert_eq!(eval("4k3/8/8/8/8/8/PP2b3/2B4K b - - 0 1"), -eval("4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1"));
+    assert!(eval("4k3/8/8/8/8/8/PP1b4/2B4K w - - 0 1") > 2 * eval("4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1"));
+    let unscaled = EvalParams { opposite_bishops_scale: SCALE_NORMAL, ..DEFAULT_PARAMS };
+    let board = Board::from_fen("4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1");
+    assert_eq!(trace(&board, &unscaled).score, trace(&board, &unscaled).tapered);
+    // Rook pawn with the wrong bishop
+    assert_eq!(scale("7k/8/8/7P/8/8/8/K2B4 w - - 0 1"), SCALE_DRAW);
+    assert_eq!(eval("7k/8/8/7P/8/8/8/K2B4 w - - 0 1"), 0);
+    assert_eq!(scale("7k/8/8/7P/8/8/8/K1B5 w - - 0 1"), SCALE_NORMAL);
+    assert!(eval("7k/8/8/7P/8/8/8/K1B5 w - - 0 1") > 0);
+}
+
+#[test]
+fn pawn_structure_terms() {
+    let eval = |fen: &str| evaluate(&Board::from_fen(fen));
+    // Doubled and isolated pawns are worse than a phalanx
+    assert!(eval("4k3/2pp4/8/8/8/2P5/2P5/4K3 w - - 0 1") < eval("4k3/2pp4/8/8/8/8/2PP4/4K3 w - - 0 1"));
+    assert!(eval("4k3/ppp5/8/8/8/8/P1P5/4K3 w - - 0 1") < eval("4k3/ppp5/8/8/8/8/PP6/4K3 w - - 0 1"));
+    // A backward pawn that cannot advance
+    assert!(eval("4k3/8/3p4/8/2P5/8/1P6/4K3 w - - 0 1") < eval("4k3/8/3p4/8/2P5/1P6/8/4K3 w - - 0 1"));
+    // Passed pawns are worth more further up, with the king close and nothing in front
+    assert!(eval("r5k1/8/4P3/8/8/8/8/R3K3 w - - 0 1") > eval("r5k1/8/8/8/8/4P3/8/R3K3 w - - 0 1"));
+    assert!(eval("r5k1/8/4P3/3K4/8/8/8/R7 w - - 0 1") > eval("r5k1/8/4P3/8/8/8/8/R3K3 w - - 0 1"));
+    assert!(eval("r5k1/3n4/4P3/8/8/8/8/R3K3 w - - 0 1") > eval("r5k1/4n3/4P3/8/8/8/8/R3K3 w - - 0 1"));
+    // Symmetric structures cancel out
+    assert_eq!(eval("4k3/pp3ppp/2p5/8/8/2P5/PP3PPP/4K3 w - - 0 1"), 0);
+}
+
+#[test]
+fn pawn_table_matches_direct_evaluation() {
+    let mut board = Board::from_fen("r1bqkbnr/pp1p1ppp/2n5/2p1p3/4P3/3P1N2/PPP2PPP/RNBQKB1R w KQkq - 0 4");
+    let mut pawn_table = PawnTable::new();
+    for uci in ["c1g5", "d8g5", "f3g5", "c5c4", "d3c4", "b7b5", "c4b5", "g8f6"] {
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.make_move(&mv);
+        assert_eq!(board.get_pawn_hash(), board.compute_pawn_hash());
+        // Probed twice, the second time from the table
+        assert_eq!(evaluate_cached(&board, &mut pawn_table, &DEFAULT_PARAMS), evaluate(&board), "{}", board.to_fen());
+        assert_eq!(evaluate_cached(&board, &mut pawn_table, &DEFAULT_PARAMS), evaluate(&board), "{}", board.to_fen());
+    }
+    // Moves of other pieces leave the pawn hash alone
+    let before = board.get_pawn_hash();
+    let mv = board.parse_uci_move("f1e2").unwrap();
+    board.make_move(&mv);
+    assert_eq!(board.get_pawn_hash(), before);
+}
+
+#[test]
+fn mobility_king_safety_and_threats() {
+    let term = |fen: &str, f: fn(&Board, PieceColor, &AttackMaps, &EvalParams) -> Score| {
+        let board = Board::from_fen(fen);
+        let attacks = AttackMaps::new(&board);
+        (f(&board, PieceColor::WHITE, &attacks, &DEFAULT_PARAMS), f(&board, PieceColor::BLACK, &attacks, &DEFAULT_PARAMS))
+    };
+    // A centralised knight reaches more squares, minus those covered by pawns
+    let (centre, _) = term("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", pieces::mobility);
+    let (corner, _) = term("4k3/8/8/8/8/8/8/N3K3 w - - 0 1", pieces::mobility);
+    let (covered, _) = term("4k3/8/2p1p3/8/3N4/8/8/4K3 w - - 0 1", pieces::mobility);
+    assert!(centre.mg > covered.mg && covered.mg > corner.mg);
+
+    // Shelter pawns at home, pushed, and gone with the file open
+    let (home, _) = term("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", king::king_safety);
+    let (pushed, _) = term("6k1/5ppp/8/8/8/6P1/5P1P/6K1 w - - 0 1", king::king_safety);
+    let (open, _) = term("6k1/5p1p/8/8/8/8/5P1P/6K1 w - - 0 1", king::king_safety);
+    assert!(home.mg > pushed.mg && pushed.mg > open.mg);
+    // Two pieces hitting the king zone
+    let (attacked, _) = term("6k1/5ppp/8/8/6n1/8/5PPq/6K1 w - - 0 1", king::king_safety);
+    let (quiet, _) = term("q5k1/5ppp/n7/8/8/8/5PPP/6K1 w - - 0 1", king::king_safety);
+    assert!(attacked.mg < quiet.mg - 50);
+
+    // A knight attacked by a pawn and not defended, then defended
+    let (hanging, none) = term("4k3/8/8/3n4/4P3/8/8/4K3 w - - 0 1", pieces::threats);
+    let (defended, _) = term("4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1", pieces::threats);
+    assert_eq!(none, Score::default());
+    assert!(hanging.mg > defended.mg && defended.mg > 0);
+}
+
+#[test]
+fn trace_matches_evaluation() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R b KQkq - 0 5",
+        "4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1",
+        "8/8/8/8/8/8/P7/K6k b - - 0 1",
+    ] {
+        let board = Board::from_fen(fen);
+        let trace = trace(&board, &DEFAULT_PARAMS);
+        assert_eq!(trace.relative_score, evaluate(&board), "{}", fen);
+        let json = trace.to_json();
+        for key in ["\"material\":{\"white\":{\"mg\":", "\"psqt\"", "\"pawns\"", "\"mobility\"", "\"king_safety\"", "\"threats\"", "\"phase\":", "\"tapered\":"] {
+            assert!(json.contains(key), "{}", json);
+        }
+    }
+    // Pieces of both colours are counted on their own side
+    let trace = trace(&Board::from_fen("4k3/8/8/8/8/8/PP2b3/2B4K w - - 0 1"), &DEFAULT_PARAMS);
+    assert_eq!(trace.terms.material[PieceColor::WHITE as usize], Score::new(530, 530));
+    assert_eq!(trace.terms.material[PieceColor::BLACK as usize], Score::new(330, 330));
+    assert_eq!((trace.phase, trace.scale, trace.endgame), (2, 22, None));
+}
@@ -0,0 +1,93 @@
+use rookbot::board::board::Board;
+use rookbot::movegen::generate::generate_moves;
+use rookbot::perft::BENCH_POSITIONS;
+use rookbot::search::mate::solve_mate;
+use rookbot::search::{format_score, MATE};
+use rookbot::uci::Uci;
+
+fn uci_line(moves: &[rookbot::movegen::movedata::MoveData]) -> String {
+    moves.iter().map(|mv| mv.to_uci(false)).collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn mate_scores_are_reported_in_moves() {
+    assert_eq!(format_score(MATE - 1), "mate 1");
+    assert_eq!(format_score(MATE - 7), "mate 4");
+    assert_eq!(format_score(-(MATE - 2)), "mate -1");
+    assert_eq!(format_score(-MATE), "mate 0");
+    assert_eq!(format_score(-35), "cp -35");
+}
+
+#[test]
+fn checking_moves_are_found_without_making_them() {
+    let extra = [
+        "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+        "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+        "8/8/8/2k5/3Pp3/8/8/4KB2 b - d3 0 1",
+        "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+        "3n2k1/4P3/8/8/8/8/8/4K3 w - - 0 1",
+        "r1r4k/6pp/8/6N1/8/1Q6/8/6K1 w - - 0 1",
+        // Chess960 castling, checking with the rook
+        "3k4/8/8/8/8/8/8/RK6 w A - 0 1",
+        "5k2/8/8/8/8/8/8/5KR1 w G - 0 1",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1",
+    ];
+    for fen in BENCH_POSITIONS.iter().chain(extra.iter()) {
+        let mut board = Board::from_fen(fen);
+        for mv in generate_moves(&mut board).iter() {
+            let gives_check = board.gives_check(mv);
+            board.make_move(mv);
+            assert_eq!(gives_check, board.in_check(), "{} {}", fen, mv.to_uci(false));
+            board.unmake_move(mv);
+        }
+    }
+}
+
+#[test]
+fn proof_number_solver_finds_checking_mates() {
+    let mut board = Board::from_fen("r1r4k/6pp/8/6N1/8/1Q6/8/6K1 w - - 0 1");
+    let fen = board.to_fen();
+    assert_eq!(solve_mate(&mut board, 3, 100_000), None);
+    let line = solve_mate(&mut board, 6, 100_000).unwrap();
+    assert_eq!(uci_line(&line), "g5f7 h8g8 f7h6 g8h8 b3g8 c8g8 h6f7");
+    assert_eq!(board.to_fen(), fen);
+    // Mate in two starting with a quiet move
+    assert_eq!(solve_mate(&mut Board::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1"), 2, 100_000), None);
+}
+
+#[test]
+fn go_mate_finds_the_shortest_mate() {
+    let mut uci = Uci::new();
+    uci.handle_command("position fen r1r4k/6pp/8/6N1/8/1Q6/8/6K1 w - - 0 1");
+    uci.handle_command("go mate 6");
+    let output = uci.wait();
+    assert!(output.iter().any(|line| line.contains("score mate 4 ")), "{:?}", output);
+    assert_eq!(output.last().unwrap(), "bestmove g5f7 ponder h8g8");
+    // A back rank mate found by the solver directly
+    uci.handle_command("position fen 6k1/5ppp/8/8/8/8/1R3PPP/1R4K1 w - - 0 1");
+    uci.handle_command("go mate 3");
+    let output = uci.wait();
+    assert!(output.iter().any(|line| line.contains("score mate 1 ")), "{:?}", output);
+    assert_eq!(output.last().unwrap(), "bestmove b2b8");
+    // The only mate made of checks takes three moves, a quiet move mates in two
+    let fen = "5k2/8/7K/3N3B/8/8/6R1/8 w - - 0 1";
+    assert_eq!(uci_line(&solve_mate(&mut Board::from_fen(fen), 3, 100_000).unwrap()), "g2f2 f8g8 d5e7 g8h8 f2f8");
+    uci.handle_command(&format!("position fen {}", fen));
+    uci.handle_command("go mate 3");
+    let output = uci.wait();
+    assert!(output.iter().any(|line| line.contains("score mate 2 ")), "{:?}", output);
+    let best = output.last().unwrap().split_whitespace().nth(1).unwrap();
+    let mut board = Board::from_fen(fen);
+    let mv = board.parse_uci_move(best).unwrap();
+    assert!(!board.gives_check(&mv), "{:?}", output);
+    uci.handle_command("position fen 7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+    uci.handle_command("go mate 2");
+    let output = uci.wait();
+    assert!(output.iter().any(|line| line.contains("score mate 2 ")), "{:?}", output);
+    // Getting mated
+    uci.handle_command("position fen 7k/R7/8/8/8/8/8/1R4K1 b - - 0 1");
+    uci.handle_command("go depth 3");
+    let output = uci.wait();
+    assert!(output[output.len() - 2].contains("score mate -1 "), "{:?}", output);
+}
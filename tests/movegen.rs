@@ -0,0 +1,167 @@
+use rookbot::board::board::Board;
+use rookbot::board::castling::types::CastlingSide;
+use rookbot::board::bitboard::Bitboard;
+use rookbot::board::piece::{Piece, PieceColor, PieceType};
+use rookbot::board::square::Square;
+use rookbot::movegen::legality::MoveError;
+use rookbot::movegen::movedata::{MoveData, MoveType};
+use rookbot::perft::{perft, perft_bulk, perft_pseudo_legal, BENCH_POSITIONS};
+use rookbot::uci::Uci;
+
+#[test]
+fn pseudo_legal_perft_matches_legal_perft() {
+    for fen in BENCH_POSITIONS {
+        let mut board = Board::from_fen(fen);
+        assert_eq!(perft_bulk(&mut board, 3), perft_pseudo_legal(&mut board, 3), "{}", fen);
+    }
+}
+
+#[test]
+fn en_passant_must_answer_check() {
+    // f7-f5 uncovered the g8 bishop, e5xf6 does not block it
+    let mut board = Board::from_fen("6b1/8/8/4Pp2/8/8/K7/7k w - f6 0 2");
+    assert_eq!(perft_bulk(&mut board, 1), 5);
+    assert_eq!(perft_bulk(&mut board, 2), 51);
+    assert_eq!(perft_pseudo_legal(&mut board, 2), 51);
+    assert!(!perft(&mut board, 1, true).contains("e5f6"));
+
+    // Taking the checking pawn en passant is allowed
+    let mut board = Board::from_fen("8/8/8/3k4/4Pp2/8/8/4K3 b - e3 0 1");
+    assert!(perft(&mut board, 1, false).contains("f4e3"));
+
+    // A pinned pawn may take en passant along the pin
+    let mut board = Board::from_fen("2b5/8/8/4pP2/8/7K/8/k7 w - e6 0 1");
+    assert!(perft(&mut board, 1, false).contains("f5e6"));
+    assert_eq!(perft_bulk(&mut board, 3), perft_pseudo_legal(&mut board, 3));
+}
+
+#[test]
+fn uci_moves_are_parsed() {
+    let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let mv = board.parse_uci_move("e2e4").unwrap();
+    assert_eq!((mv.from, mv.to, mv.to_uci(false)), (Square::E2, Square::E4, "e2e4".to_string()));
+    let cases = [
+        ("e2", MoveError::InvalidFormat),
+        ("e2e4qq", MoveError::InvalidFormat),
+        ("i2e4", MoveError::InvalidFormat),
+        ("e2e4x", MoveError::InvalidPromotion),
+        ("e2e4q", MoveError::InvalidPromotion),
+        ("e3e4", MoveError::NoPiece(Square::E3)),
+        ("e7e5", MoveError::WrongColor(Square::E7)),
+        ("e2e5", MoveError::IllegalPieceMove),
+        ("g1g3", MoveError::IllegalPieceMove),
+    ];
+    for (uci, error) in cases {
+        assert_eq!(board.parse_uci_move(uci), Err(error), "{}", uci);
+    }
+}
+
+#[test]
+fn promotions_need_a_valid_piece() {
+    let mut board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(board.parse_uci_move("b7b8"), Err(MoveError::MissingPromotion));
+    let mv = board.parse_uci_move("b7b8N").unwrap();
+    assert_eq!(mv.get_promoted_piece().map(|piece| piece.piece_type), Some(PieceType::KNIGHT));
+    assert_eq!((mv.to_uci(false), mv.to_algebraic()), ("b7b8n".to_string(), "b7b8=N".to_string()));
+    assert_eq!(board.parse_uci_move("b7b8q").unwrap().to_algebraic(), "b7b8=Q");
+}
+
+#[test]
+fn castling_en_passant_pins_and_checks_are_diagnosed() {
+    let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let kingside = board.parse_uci_move("e1g1").unwrap();
+    assert_eq!(kingside.get_castling_side(), Some(CastlingSide::Kingside));
+    assert_eq!(board.parse_uci_move("e1h1"), Ok(kingside));
+    assert_eq!(board.parse_uci_move("e1c1").unwrap().get_castling_side(), Some(CastlingSide::Queenside));
+    assert_eq!(board.parse_uci_move("e1g1q"), Err(MoveError::InvalidPromotion));
+
+    let cases = [
+        ("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1", "e1c1", MoveError::CastlingNotAllowed(CastlingSide::Queenside)),
+        ("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1", "e1g1", MoveError::KingInCheck),
+        ("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1", "e5d6", MoveError::Pinned),
+        ("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1", "e2d3", MoveError::Pinned),
+        ("r3k3/8/8/8/8/8/3N4/K7 w - - 0 1", "d2f3", MoveError::KingInCheck),
+        ("r3k3/8/8/8/8/8/3N4/K7 w - - 0 1", "a1a2", MoveError::KingInCheck),
+    ];
+    for (fen, uci, error) in cases {
+        assert_eq!(Board::from_fen(fen).parse_uci_move(uci), Err(error), "{} {}", fen, uci);
+    }
+
+    let mut board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    let mv = board.parse_uci_move("e5d6").unwrap();
+    assert_eq!(mv.get_capture_square(), Some(Square::D5));
+}
+
+#[test]
+fn is_legal_plays_the_move() {
+    let mut board = Board::from_fen("6b1/8/8/4Pp2/8/8/K7/7k w - f6 0 2");
+    let white_pawn = Piece::new(PieceColor::WHITE, PieceType::PAWN);
+    let black_pawn = Piece::new(PieceColor::BLACK, PieceType::PAWN);
+    let en_passant = MoveData::new(Square::E5, Square::F6, white_pawn, MoveType::EnPassant(black_pawn, Square::F5));
+    assert!(!board.is_legal(&en_passant));
+    assert_eq!(board.parse_uci_move("e5f6"), Err(MoveError::KingInCheck));
+    let block = board.parse_uci_move("e5e6").unwrap();
+    assert!(board.is_legal(&block));
+}
+
+#[test]
+fn chess960_perft() {
+    let cases = [
+        ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", [21, 528, 12189]),
+        ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", [21, 807, 18002]),
+        ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", [20, 479, 10471]),
+        ("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", [22, 593, 13440]),
+        ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", [28, 1120, 31058]),
+        ("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", [29, 899, 26578]),
+    ];
+    for (fen, nodes) in cases {
+        let mut board = Board::from_fen(fen);
+        assert!(board.chess960, "{}", fen);
+        for (depth, &count) in nodes.iter().enumerate() {
+            assert_eq!(perft_bulk(&mut board, depth as u32 + 1), count, "{} depth {}", fen, depth + 1);
+        }
+    }
+}
+
+#[test]
+fn standard_castling_letters_stay_standard() {
+    // The black king is off the e-file but has no castling rights
+    let mut board = Board::from_fen("r4k1r/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert!(!board.chess960);
+    let kingside = board.parse_uci_move("e1g1").unwrap();
+    assert_eq!(kingside.get_castling_side(), Some(CastlingSide::Kingside));
+    assert_eq!(kingside.to_uci(board.chess960), "e1g1");
+
+    // With UCI_Chess960 castling is king takes rook, in and out
+    let mut uci = Uci::new();
+    uci.handle_command("setoption name UCI_Chess960 value true");
+    let position = "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves";
+    assert_eq!(uci.handle_command(&format!("{} e1h1", position)), Vec::<String>::new());
+    assert_eq!(uci.handle_command(&format!("{} e1g1", position)), vec!["info string illegal move e1g1: piece cannot move to that square"]);
+    // Only castling puts the rook on d1 with mate
+    uci.handle_command("position fen 2rkr3/2p1p3/8/8/8/8/8/RK6 w Q - 0 1");
+    uci.handle_command("go depth 2");
+    assert_eq!(uci.wait().last().unwrap(), "bestmove b1a1");
+}
+
+#[test]
+fn attackers_see_through_removed_blockers() {
+    let board = Board::from_fen("4k2q/4r3/2n2p2/8/3P4/5N2/8/Q3R1K1 w - - 0 1");
+    let squares = |squares: &[Square]| squares.iter().copied().collect::<Bitboard>();
+    let occupancy = board.get_all_pieces_bitboard();
+    let attackers = squares(&[Square::D4, Square::F3, Square::E1, Square::F6, Square::C6, Square::E7]);
+    assert_eq!(board.attackers_to(Square::E5, occupancy), attackers);
+    assert_eq!(board.attackers_to(Square::E5, occupancy) & board.get_color_bitboard(PieceColor::WHITE), squares(&[Square::D4, Square::F3, Square::E1]));
+
+    // Without the pawns the queens behind them attack too
+    let mut without_pawns = occupancy;
+    without_pawns.clear_square(Square::D4);
+    without_pawns.clear_square(Square::F6);
+    assert_eq!(board.attackers_to(Square::E5, without_pawns), attackers | squares(&[Square::A1, Square::H8]));
+
+    assert!(board.is_square_attacked_by(Square::E5, PieceColor::BLACK));
+    assert!(board.is_square_attacked_by(Square::H1, PieceColor::BLACK));
+    assert!(board.is_square_attacked_by(Square::A8, PieceColor::WHITE));
+    assert!(!board.is_square_attacked_by(Square::A8, PieceColor::BLACK));
+    assert!(!board.is_square_attacked_by(Square::H6, PieceColor::WHITE));
+}
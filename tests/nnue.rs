@@ -0,0 +1,131 @@
+use std::fs;
+use std::sync::Arc;
+use rookbot::board::board::Board;
+use rookbot::eval::evaluate_cached;
+use rookbot::eval::nnue::simd::{output, output_scalar};
+use rookbot::eval::nnue::{Accumulator, Network, NnueError, HIDDEN};
+use rookbot::eval::params::DEFAULT_PARAMS;
+use rookbot::eval::pawns::PawnTable;
+use rookbot::movegen::generate::generate_moves;
+use rookbot::uci::Uci;
+
+// xorshift, so that the weights are the same on every run
+fn random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn random_network(seed: u64) -> Network {
+    let mut state = seed;
+    let mut weight = |range: u64| (random(&mut state) % (2 * range + 1)) as i16 - range as i16;
+    let mut network = Network::zeroed();
+    for row in network.feature_weights.iter_mut().chain([&mut network.feature_bias]) {
+        row.iter_mut().for_each(|value| *value = weight(32));
+    }
+    for row in network.output_weights.iter_mut() {
+        row.iter_mut().for_each(|value| *value = weight(64));
+    }
+    network.output_bias = 100;
+    network
+}
+
+#[test]
+fn accumulators_follow_make_and_unmake() {
+    let network = Arc::new(random_network(0x9e37_79b9_7f4a_7c15));
+    // Castling both ways, en passant, promotions and king walks
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "rnbqkbnr/pp1ppppp/8/2pP4/8/8/PPP1PPPP/RNBQKBNR w KQkq c6 0 2",
+    ];
+    let mut state = 7;
+    for fen in fens {
+        let mut board = Board::from_fen(fen);
+        board.set_network(Some(network.clone()));
+        let start = *board.nnue().unwrap().accumulator();
+        let mut played = Vec::new();
+        for _ in 0..60 {
+            let moves = generate_moves(&mut board);
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves[random(&mut state) as usize % moves.len()];
+            board.make_move(&mv);
+            played.push(mv);
+            assert_eq!(*board.nnue().unwrap().accumulator(), Accumulator::new(&network, &board), "{} after {}", fen, mv.to_uci(false));
+        }
+        for mv in played.iter().rev() {
+            board.unmake_move(mv);
+            assert_eq!(*board.nnue().unwrap().accumulator(), Accumulator::new(&network, &board));
+        }
+        assert_eq!(*board.nnue().unwrap().accumulator(), start);
+    }
+}
+
+#[test]
+fn network_replaces_the_hand_crafted_evaluation() {
+    let mut board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let mut pawn_table = PawnTable::new();
+    board.set_network(Some(Arc::new(Network::zeroed())));
+    assert_eq!(evaluate_cached(&board, &mut pawn_table, &DEFAULT_PARAMS), 0);
+
+    // Both perspectives count from the side to move
+    let network = Arc::new(random_network(42));
+    let mut flipped = Board::from_fen("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3");
+    board.set_network(Some(network.clone()));
+    flipped.set_network(Some(network));
+    assert_eq!(evaluate_cached(&board, &mut pawn_table, &DEFAULT_PARAMS), evaluate_cached(&flipped, &mut pawn_table, &DEFAULT_PARAMS));
+
+    board.set_network(None);
+    assert_ne!(evaluate_cached(&board, &mut pawn_table, &DEFAULT_PARAMS), 0);
+}
+
+#[test]
+fn simd_output_matches_scalar() {
+    let network = random_network(3);
+    let mut state = 11;
+    for _ in 0..100 {
+        let mut accumulator = [0i16; HIDDEN];
+        accumulator.iter_mut().for_each(|value| *value = (random(&mut state) % 700) as i16 - 200);
+        for weights in &network.output_weights {
+            assert_eq!(output(&accumulator, weights), output_scalar(&accumulator, weights));
+        }
+    }
+}
+
+#[test]
+fn network_files_are_loaded() {
+    let network = random_network(5);
+    let bytes = network.to_bytes();
+    assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+    assert!(matches!(Network::from_bytes(b"NNUE"), Err(NnueError::InvalidHeader)));
+    let mut wrong_size = bytes.clone();
+    wrong_size[8] = 128;
+    assert!(matches!(Network::from_bytes(&wrong_size), Err(NnueError::UnsupportedSize(128))));
+    assert!(matches!(Network::from_bytes(&bytes[..bytes.len() - 1]), Err(NnueError::InvalidLength(_))));
+
+    let dir = std::env::temp_dir().join(format!("rookbot-nnue-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("net.bin");
+    network.save(&path).unwrap();
+
+    let mut uci = Uci::new();
+    assert_eq!(uci.handle_command("setoption name UseNNUE value true"), vec!["info string no network loaded, set EvalFile first"]);
+    assert!(uci.handle_command("setoption name EvalFile value missing.bin")[0].starts_with("info string cannot load network missing.bin"));
+    assert_eq!(uci.handle_command(&format!("setoption name EvalFile value {}", path.display())), vec![format!("info string loaded network {}", path.display())]);
+    // The network has no breakdown of its score
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+    let mut board = Board::from_fen(fen);
+    board.set_network(Some(Arc::new(network.clone())));
+    let expected = evaluate_cached(&board, &mut PawnTable::new(), &DEFAULT_PARAMS);
+    let output = uci.handle_command(&format!("eval fen {}", fen));
+    assert_eq!(output, vec![format!("info string NNUE evaluation {} cp, no breakdown available", expected)]);
+    uci.handle_command("position startpos moves e2e4 e7e5");
+    uci.handle_command("go depth 3");
+    let output = uci.wait();
+    assert!(output.last().unwrap().starts_with("bestmove "), "{:?}", output);
+    fs::remove_dir_all(&dir).unwrap();
+}
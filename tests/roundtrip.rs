@@ -0,0 +1,85 @@
+use rookbot::board::board::{Board, FenError};
+use rookbot::roundtrip::{check_fuzz_input, check_random_games, is_playable};
+
+#[test]
+fn random_games_make_and_unmake_cleanly() {
+    for seed in 1..=3 {
+        check_random_games(1, 60, seed).unwrap();
+    }
+}
+
+#[test]
+fn malformed_fens_are_rejected() {
+    let cases = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -", FenError::MissingFields),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1", FenError::InvalidPlacement),
+        ("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidPiece('9')),
+        ("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidPlacement),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", FenError::InvalidColor),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkz - 0 1", FenError::InvalidCastling),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1", FenError::InvalidEnPassant),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1", FenError::InvalidClock),
+    ];
+    for (fen, error) in cases {
+        assert_eq!(Board::try_from_fen(fen).err(), Some(error), "{}", fen);
+    }
+}
+
+#[test]
+fn fuzz_inputs_do_not_fail() {
+    let inputs: [&[u8]; 4] = [
+        b"",
+        b"\xff\xfe\n\x01",
+        b"4k3/8/8/8/8/8/8/4K3 w KQ - 0 1\n\x01\x02",
+        b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1\n\x00\x05\x09\x11\x2a\x07\x63\xff",
+    ];
+    for input in inputs {
+        check_fuzz_input(input).unwrap();
+    }
+}
+
+#[test]
+fn en_passant_squares_must_explain_the_check() {
+    let cases = [
+        // d7-d5 cannot have let the rook give check
+        ("k7/8/8/3pP3/8/8/8/r3K3 w - - 0 1", true),
+        ("k7/8/8/3pP3/8/8/8/r3K3 w - d6 0 1", false),
+        ("k7/7b/8/3p1K2/8/8/8/8 w - d6 0 1", false),
+        // Checks by the pushed pawn and by a slider it uncovered
+        ("k7/8/8/3p4/4K3/8/8/8 w - d6 0 1", true),
+        ("8/8/8/3k4/4Pp2/8/8/4K3 b - e3 0 1", true),
+        ("k1b5/8/8/3p1K2/8/8/8/8 w - d6 0 1", true),
+    ];
+    for (fen, playable) in cases {
+        assert_eq!(is_playable(&Board::from_fen(fen)), playable, "{}", fen);
+    }
+}
+
+fn play(board: &mut Board, moves: &[&str]) {
+    for uci in moves {
+        let mv = board.parse_uci_move(uci).unwrap();
+        board.make_move(&mv);
+    }
+}
+
+#[test]
+fn moves_advance_the_clocks() {
+    let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let clocks = |board: &Board| (board.game_state.halfmove_clock, board.game_state.fullmove_clock);
+    play(&mut board, &["g1f3", "g8f6"]);
+    assert_eq!(clocks(&board), (2, 2));
+    play(&mut board, &["b1c3"]);
+    assert_eq!(clocks(&board), (3, 2));
+    // Pawn moves and captures restart the halfmove clock
+    play(&mut board, &["e7e5"]);
+    assert_eq!(clocks(&board), (0, 3));
+    play(&mut board, &["f3e5", "b8c6"]);
+    assert_eq!(clocks(&board), (1, 4));
+    play(&mut board, &["e5c6"]);
+    let fen = board.to_fen();
+    let recapture = board.parse_uci_move("d7c6").unwrap();
+    board.make_move(&recapture);
+    assert_eq!(clocks(&board), (0, 5));
+    board.unmake_move(&recapture);
+    assert_eq!(board.to_fen(), fen);
+}
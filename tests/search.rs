@@ -0,0 +1,237 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use rookbot::board::board::Board;
+use rookbot::board::piece::PieceColor;
+use rookbot::movegen::generate::generate_moves;
+use rookbot::perft::BENCH_POSITIONS;
+use rookbot::search::limits::SearchLimits;
+use rookbot::search::tt::{Bound, TranspositionTable};
+use rookbot::search::{SearchConfig, Searcher, MATE, MATE_BOUND};
+use rookbot::uci::Uci;
+
+fn search(fen: &str, depth: u32, config: SearchConfig) -> (String, i32, Vec<String>) {
+    let mut board = Board::from_fen(fen);
+    let result = Searcher::new(config).search(&mut board, &SearchLimits::depth(depth), &[], |_| {});
+    let pv = result.pv.iter().map(|mv| mv.to_uci(false)).collect();
+    (result.best_move.unwrap().to_uci(false), result.score, pv)
+}
+
+#[test]
+fn finds_mates() {
+    let (best, score, pv) = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3, SearchConfig::default());
+    assert_eq!((best.as_str(), score), ("a1a8", MATE - 1));
+    assert_eq!(pv, vec!["a1a8"]);
+    let (_, score, pv) = search("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 4, SearchConfig::default());
+    assert_eq!((score, pv.len()), (MATE - 3, 3), "{:?}", pv);
+    // Mated next move whatever black plays
+    let (_, score, _) = search("7k/R7/8/8/8/8/8/1R4K1 b - - 0 1", 3, SearchConfig::default());
+    assert_eq!(score, -(MATE - 2));
+}
+
+#[test]
+fn principal_variations_are_legal() {
+    for fen in BENCH_POSITIONS {
+        let mut board = Board::from_fen(fen);
+        let result = Searcher::default().search(&mut board, &SearchLimits::depth(4), &[], |info| assert!(!info.pv.is_empty()));
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+        for mv in &result.pv {
+            assert!(generate_moves(&mut board).is_move_in_list(mv), "{} {}", fen, mv.to_uci(false));
+            board.make_move(mv);
+        }
+    }
+}
+
+#[test]
+fn pvs_and_aspiration_keep_the_alpha_beta_score() {
+    let plain = SearchConfig::plain();
+    let pvs = SearchConfig { pvs: true, aspiration: true, ..plain };
+    for fen in BENCH_POSITIONS {
+        assert_eq!(search(fen, 4, pvs).1, search(fen, 4, plain).1, "{}", fen);
+    }
+}
+
+#[test]
+fn uci_go_searches() {
+    let mut uci = Uci::new();
+    uci.handle_command("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    assert!(uci.handle_command("go depth 2").is_empty());
+    let output = uci.wait();
+    assert!(output[0].starts_with("info depth 1 score mate 1 "), "{:?}", output);
+    assert!(output[1].ends_with("pv a1a8"), "{:?}", output);
+    assert_eq!(output.last().unwrap(), "bestmove a1a8");
+    // Repeating the position a third time is a draw, avoided when ahead
+    uci.handle_command("position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1");
+    uci.handle_command("go depth 2");
+    let output = uci.wait();
+    assert!(!output.last().unwrap().starts_with("bestmove f6g8 "), "{:?}", output);
+}
+
+#[test]
+fn each_pruning_technique_keeps_finding_mates() {
+    let plain = SearchConfig::plain();
+    let configs = [
+        SearchConfig { null_move: true, ..plain },
+        SearchConfig { late_move_reductions: true, ..plain },
+        SearchConfig { reverse_futility: true, ..plain },
+        SearchConfig { futility: true, ..plain },
+        SearchConfig { razoring: true, ..plain },
+        SearchConfig { late_move_pruning: true, ..plain },
+        SearchConfig::default(),
+    ];
+    for config in configs {
+        assert_eq!(search("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 4, config).1, MATE - 3, "{:?}", config);
+        assert_eq!(search("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", 3, config).0, "h5f7", "{:?}", config);
+    }
+}
+
+#[test]
+fn pruning_searches_fewer_nodes() {
+    let nodes = |config| {
+        let mut board = Board::from_fen(BENCH_POSITIONS[1]);
+        Searcher::new(config).search(&mut board, &SearchLimits::depth(4), &[], |_| {}).nodes
+    };
+    assert!(nodes(SearchConfig::default()) < nodes(SearchConfig::plain()) / 2);
+}
+
+#[test]
+fn non_pawn_pieces_and_uci_toggles() {
+    let board = Board::from_fen("4k3/pp6/8/8/8/8/PP6/1N2K3 w - - 0 1");
+    assert_eq!(board.non_pawn_pieces(PieceColor::WHITE).pop_count(), 1);
+    assert!(board.non_pawn_pieces(PieceColor::BLACK).is_empty());
+    let mut uci = Uci::new();
+    assert!(uci.handle_command("uci").contains(&"option name NullMove type check default true".to_string()));
+    assert!(uci.handle_command("setoption name NullMove value false").is_empty());
+}
+
+#[test]
+fn check_extension_sees_mates_beyond_the_horizon() {
+    // Smothered mate in four, every white move a check
+    let fen = "r1r4k/6pp/8/6N1/8/1Q6/8/6K1 w - - 0 1";
+    let plain = SearchConfig::plain();
+    assert!(search(fen, 4, plain).1 < MATE_BOUND);
+    let (_, score, pv) = search(fen, 4, SearchConfig { check_extension: true, ..plain });
+    assert_eq!((score, pv.join(" ").as_str()), (MATE - 7, "g5f7 h8g8 f7h6 g8h8 b3g8 c8g8 h6f7"));
+    assert_eq!(search(fen, 6, SearchConfig::default()).1, MATE - 7);
+}
+
+#[test]
+fn transposition_table_stores_mates_relative_to_the_node() {
+    let mut tt = TranspositionTable::new(1);
+    let mv = generate_moves(&mut Board::from_fen(BENCH_POSITIONS[0])).iter().next().copied();
+    tt.store(42, mv, MATE - 7, 3, Bound::Lower, 4);
+    let entry = tt.probe(42, 2).unwrap();
+    assert_eq!((entry.mv, entry.score, entry.depth, entry.bound), (mv, MATE - 5, 3, Bound::Lower));
+    tt.store(42, None, 15, 5, Bound::Upper, 0);
+    assert_eq!(tt.probe(42, 0).unwrap().mv, mv);
+    assert!(tt.probe(43, 0).is_none());
+    tt.clear();
+    assert!(tt.probe(42, 0).is_none());
+}
+
+#[test]
+fn multi_pv_reports_distinct_root_moves() {
+    let mut uci = Uci::new();
+    assert!(uci.handle_command("setoption name MultiPV value 3").is_empty());
+    uci.handle_command("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    uci.handle_command("go depth 3");
+    let output = uci.wait();
+    let last: Vec<&String> = output.iter().filter(|line| line.starts_with("info depth 3 multipv")).collect();
+    assert_eq!(last.len(), 3, "{:?}", output);
+    for (k, line) in last.iter().enumerate() {
+        assert!(line.starts_with(&format!("info depth 3 multipv {} score ", k + 1)), "{}", line);
+    }
+    assert!(last[0].contains("score mate 1 ") && last[0].ends_with("pv a1a8"), "{}", last[0]);
+    let first_moves: Vec<&str> = last.iter().map(|line| line.split(" pv ").nth(1).unwrap().split(' ').next().unwrap()).collect();
+    assert!(first_moves[1] != first_moves[2] && !first_moves[1..].contains(&"a1a8"), "{:?}", first_moves);
+    assert_eq!(output.last().unwrap(), "bestmove a1a8");
+
+    // No more lines than legal moves
+    let mut board = Board::from_fen("7k/8/8/8/8/8/6q1/7K w - - 0 1");
+    let mut searcher = Searcher::default();
+    searcher.multi_pv = 4;
+    let result = searcher.search(&mut board, &SearchLimits::depth(2), &[], |_| {});
+    assert_eq!(result.lines.len(), 1);
+    let mut board = Board::from_fen(BENCH_POSITIONS[0]);
+    let result = searcher.search(&mut board, &SearchLimits::depth(3), &[], |_| {});
+    assert_eq!(result.lines.len(), 4);
+    assert!(result.lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn root_moves_restrict_the_search() {
+    let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let root_moves: Vec<_> = ["a1a2", "g1f1"].iter().map(|uci| board.parse_uci_move(uci).unwrap()).collect();
+    let mut searcher = Searcher::default();
+    searcher.multi_pv = 3;
+    let limits = SearchLimits { root_moves: root_moves.clone(), ..SearchLimits::depth(3) };
+    let result = searcher.search(&mut board, &limits, &[], |_| {});
+    assert_eq!(result.lines.len(), 2);
+    assert!(result.lines.iter().all(|line| root_moves.contains(&line.pv[0])));
+    assert!(result.score < MATE_BOUND);
+    // Nor does the mate solver play a move outside them
+    searcher.multi_pv = 1;
+    let limits = SearchLimits { root_moves, mate: Some(1), ..Default::default() };
+    let result = searcher.search(&mut board, &limits, &[], |_| {});
+    assert!(result.best_move.unwrap().to_uci(false) != "a1a8" && result.score < MATE_BOUND);
+}
+
+fn wait_for_bestmove(uci: &mut Uci) -> String {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while Instant::now() < deadline {
+        if let Some(line) = uci.poll().into_iter().find(|line| line.starts_with("bestmove")) {
+            return line;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("no bestmove");
+}
+
+#[test]
+fn pondering_waits_for_ponderhit_and_then_uses_the_clock() {
+    let mut uci = Uci::new();
+    uci.handle_command("position startpos moves e2e4 e7e5");
+    assert!(uci.handle_command("go ponder movetime 300").is_empty());
+    thread::sleep(Duration::from_millis(500));
+    assert!(!uci.poll().iter().any(|line| line.starts_with("bestmove")));
+    assert_eq!(uci.handle_command("isready").last().unwrap(), "readyok");
+
+    // The move time only runs from the ponderhit
+    let ponderhit = Instant::now();
+    assert!(!uci.handle_command("ponderhit").iter().any(|line| line.starts_with("bestmove")));
+    let line = wait_for_bestmove(&mut uci);
+    assert!(ponderhit.elapsed() >= Duration::from_millis(250), "{:?}", ponderhit.elapsed());
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    assert_eq!((tokens.len(), tokens[2]), (4, "ponder"), "{}", line);
+    let mut board = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+    let best = board.parse_uci_move(tokens[1]).unwrap();
+    board.make_move(&best);
+    assert!(generate_moves(&mut board).iter().any(|mv| mv.to_uci(false) == tokens[3]), "{}", line);
+}
+
+#[test]
+fn stop_ends_every_search() {
+    let mut uci = Uci::new();
+    uci.handle_command("position startpos");
+    uci.handle_command("go ponder wtime 1000 btime 1000");
+    thread::sleep(Duration::from_millis(50));
+    let output = uci.handle_command("stop");
+    assert!(output.last().unwrap().starts_with("bestmove "), "{:?}", output);
+
+    // An infinite search waits for stop even when it has nothing left to do
+    uci.handle_command("position fen 7k/8/8/8/8/8/6q1/7K w - - 0 1");
+    assert!(uci.handle_command("go infinite").is_empty());
+    thread::sleep(Duration::from_millis(100));
+    assert!(!uci.poll().iter().any(|line| line.starts_with("bestmove")));
+    let output = uci.handle_command("stop");
+    assert!(output.last().unwrap().starts_with("bestmove h1g2"), "{:?}", output);
+    assert!(uci.poll().is_empty());
+
+    // So does an ordinary search, which answers isready meanwhile
+    uci.handle_command("position startpos");
+    assert!(uci.handle_command("go depth 60").is_empty());
+    assert_eq!(uci.handle_command("isready").last().unwrap(), "readyok");
+    let output = uci.handle_command("stop");
+    assert!(output.last().unwrap().starts_with("bestmove "), "{:?}", output);
+}
@@ -0,0 +1,55 @@
+use rookbot::board::piece::PieceColor;
+use rookbot::board::square::{File, ParseSquareError, Rank, Square};
+
+#[test]
+fn squares_are_parsed_and_printed() {
+    assert_eq!("e4".parse::<Square>(), Ok(Square::E4));
+    assert_eq!("H8".parse::<Square>(), Ok(Square::H8));
+    for bad in ["", "e", "e9", "i1", "e44", "4e"] {
+        assert_eq!(bad.parse::<Square>(), Err(ParseSquareError), "{}", bad);
+    }
+    for square in Square::all() {
+        assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+    }
+    assert_eq!(Square::A1.to_string(), "a1");
+    assert_eq!(format!("{}{}", File::G, Rank::Seventh), "g7");
+
+    assert_eq!((File::from_char('c'), File::from_char('i')), (Some(File::C), None));
+    assert_eq!((Rank::from_char('1'), Rank::from_char('0')), (Some(Rank::First), None));
+    assert_eq!((File::from_index(7), File::from_index(8)), (Some(File::H), None));
+    assert_eq!((Rank::from_index(0), Rank::from_index(8)), (Some(Rank::First), None));
+    assert_eq!((Square::from_index(63), Square::from_index(64)), (Some(Square::H8), None));
+}
+
+#[test]
+fn square_indices_match_file_and_rank() {
+    for (index, square) in Square::all().enumerate() {
+        assert_eq!(square.index(), index);
+        assert_eq!(Square::from_file_rank(square.file(), square.rank()), square);
+        assert_eq!(square.file().index() as usize, index % 8);
+        assert_eq!(square.rank().index() as usize, index / 8);
+    }
+    assert_eq!((Square::C6.file(), Square::C6.rank()), (File::C, Rank::Sixth));
+}
+
+#[test]
+fn square_arithmetic() {
+    assert_eq!(Square::E4.offset(1, 2), Some(Square::F6));
+    assert_eq!(Square::A1.offset(-1, 0), None);
+    assert_eq!(Square::H8.offset(0, 1), None);
+    assert_eq!(Square::H4.offset(1, 0), None);
+    assert_eq!(Square::E2.forward(PieceColor::WHITE), Some(Square::E3));
+    assert_eq!(Square::E7.forward(PieceColor::BLACK), Some(Square::E6));
+    assert_eq!(Square::E1.forward(PieceColor::BLACK), None);
+
+    assert_eq!((Square::B3.flip_rank(), Square::B3.flip_file()), (Square::B6, Square::G3));
+    assert_eq!(Square::B3.relative(PieceColor::BLACK), Square::B6);
+    assert_eq!(Square::B3.relative(PieceColor::WHITE), Square::B3);
+    assert_eq!((File::B.flip(), Rank::Second.flip()), (File::G, Rank::Seventh));
+    assert_eq!(Rank::First.relative(PieceColor::BLACK), Rank::Eighth);
+
+    assert_eq!((Square::A1.file_distance(Square::H3), Square::A1.rank_distance(Square::H3)), (7, 2));
+    assert_eq!(Square::A1.distance(Square::H3), 7);
+    assert_eq!(Square::A1.manhattan_distance(Square::H3), 9);
+    assert_eq!(Square::D4.distance(Square::D4), 0);
+}
@@ -0,0 +1,572 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use rookbot::board::board::Board;
+use rookbot::board::piece::PieceColor;
+use rookbot::board::square::Square;
+use rookbot::movegen::generate::generate_moves;
+use rookbot::search::limits::SearchLimits;
+use rookbot::search::{Searcher, TB_WIN};
+use rookbot::tablebase::dtm::generate::generate;
+use rookbot::tablebase::dtm::probe::DtmTablebases;
+use rookbot::tablebase::dtm::table::Dtm;
+use rookbot::tablebase::syzygy::probe::{Tablebases, Wdl};
+use rookbot::tablebase::syzygy::table::{Lookup, Material, TableFile, TableKind};
+use rookbot::uci::Uci;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+const SINGLE_VALUE: u8 = 0x80;
+const HAS_PAWNS: u8 = 2;
+const FILE_SPLIT: u8 = 1;
+const WIN_PLIES: u8 = 4;
+
+// Builds a table file in which every position of a (side, file) table has
+// the same value. `pieces` is the encoding order, `values` is indexed by file
+// then side to move.
+fn single_value_table(magic: [u8; 4], flags: u8, pieces: &[u8], values: &[Vec<(u8, u8)>]) -> Vec<u8> {
+    let mut data = magic.to_vec();
+    data.push(flags);
+    for _ in values {
+        data.push(0x00);
+        data.extend(pieces.iter().map(|code| code | code << 4));
+    }
+    if data.len() % 2 == 1 {
+        data.push(0);
+    }
+    for sides in values {
+        for (table_flags, value) in sides {
+            data.extend([SINGLE_VALUE | table_flags, *value]);
+        }
+    }
+    data
+}
+
+// Positions of a table with three unique pieces: the first in the a1-d1-d4
+// triangle, then the other two
+const UNIQUE_PIECES_SIZE: usize = 31332;
+const BLOCK_SIZE_LOG2: u8 = 5;
+const SPAN_LOG2: u8 = 6;
+
+// Huffman code length of every value, by merging the two rarest subtrees
+fn code_lengths(counts: &BTreeMap<u16, u64>) -> BTreeMap<u16, u8> {
+    let mut heap: BinaryHeap<Reverse<(u64, Vec<u16>)>> = counts.iter().map(|(value, count)| Reverse((*count, vec![*value]))).collect();
+    let mut lengths: BTreeMap<u16, u8> = counts.keys().map(|value| (*value, 0)).collect();
+    while heap.len() > 1 {
+        let Reverse((first, mut first_values)) = heap.pop().unwrap();
+        let Reverse((second, second_values)) = heap.pop().unwrap();
+        first_values.extend(second_values);
+        for value in &first_values {
+            *lengths.get_mut(value).unwrap() += 1;
+        }
+        heap.push(Reverse((first + second, first_values)));
+    }
+    lengths
+}
+
+// The per table part of a compressed table: the header read by the probing
+// code, the sparse index, the block lengths and the Huffman coded blocks.
+// Every value is its own symbol, nothing is paired.
+struct Compressed {
+    header: Vec<u8>,
+    sparse_index: Vec<u8>,
+    block_lengths: Vec<u8>,
+    blocks: Vec<u8>,
+}
+
+fn compress(values: &[u16]) -> Compressed {
+    let mut counts = BTreeMap::new();
+    for value in values {
+        *counts.entry(*value).or_insert(0) += 1;
+    }
+    // A code needs two symbols, the spare one is never used
+    if counts.len() == 1 {
+        let spare = (0..).find(|value| !counts.contains_key(value)).unwrap();
+        counts.insert(spare, 0);
+    }
+    let lengths = code_lengths(&counts);
+    let min_len = *lengths.values().min().unwrap();
+    let max_len = *lengths.values().max().unwrap();
+
+    // Symbols are numbered from the longest codes, and longer codes have
+    // lower values: base[i] is the first code of length min_len + i
+    let mut symbols: Vec<u16> = lengths.keys().copied().collect();
+    symbols.sort_by_key(|value| (Reverse(lengths[value]), *value));
+    let levels = (max_len - min_len) as usize + 1;
+    let count_of = |i: usize| symbols.iter().filter(|value| lengths[*value] == min_len + i as u8).count() as u64;
+    let (mut lowest, mut base) = (vec![0u64; levels], vec![0u64; levels]);
+    for i in (0..levels - 1).rev() {
+        lowest[i] = lowest[i + 1] + count_of(i + 1);
+        base[i] = (base[i + 1] + count_of(i + 1)) / 2;
+    }
+    let code_of = |value: u16| {
+        let i = (lengths[&value] - min_len) as usize;
+        let number = symbols.iter().position(|symbol| *symbol == value).unwrap() as u64;
+        (base[i] + number - lowest[i], lengths[&value] as u32)
+    };
+    let codes: BTreeMap<u16, (u64, u32)> = symbols.iter().map(|value| (*value, code_of(*value))).collect();
+
+    // Blocks are filled with whole codes, most significant bit first
+    let block_bits = 8usize << BLOCK_SIZE_LOG2;
+    let (mut blocks, mut block_counts) = (Vec::new(), Vec::new());
+    let (mut bits, mut count) = (Vec::new(), 0);
+    for value in values {
+        let (code, len) = codes[value];
+        if bits.len() + len as usize > block_bits {
+            blocks.push(std::mem::take(&mut bits));
+            block_counts.push(std::mem::take(&mut count));
+        }
+        bits.extend((0..len).rev().map(|bit| (code >> bit) & 1 == 1));
+        count += 1;
+    }
+    blocks.push(bits);
+    block_counts.push(count);
+
+    // Every `span` values the sparse index holds the block and offset of
+    // the value in the middle of the span
+    let span = 1usize << SPAN_LOG2;
+    let mut sparse_index = Vec::new();
+    for k in 0..values.len().div_ceil(span) {
+        let mut position = k * span + span / 2;
+        let mut block = 0;
+        while block + 1 < block_counts.len() && position >= block_counts[block] {
+            position -= block_counts[block];
+            block += 1;
+        }
+        sparse_index.extend((block as u32).to_le_bytes());
+        sparse_index.extend((position as u16).to_le_bytes());
+    }
+
+    let mut header = vec![0, BLOCK_SIZE_LOG2, SPAN_LOG2, 0];
+    header.extend((blocks.len() as u32).to_le_bytes());
+    header.extend([max_len, min_len]);
+    header.extend(lowest.iter().flat_map(|sym| (*sym as u16).to_le_bytes()));
+    header.extend((symbols.len() as u16).to_le_bytes());
+    // Leaves of the pairing tree have 0xFFF as their right symbol
+    for value in &symbols {
+        header.extend([*value as u8, (*value >> 8) as u8 | 0xF0, 0xFF]);
+    }
+    if symbols.len() % 2 == 1 {
+        header.push(0);
+    }
+    let block_lengths = block_counts.iter().flat_map(|count| (*count as u16 - 1).to_le_bytes()).collect();
+    let bytes = blocks
+        .iter()
+        .flat_map(|bits| {
+            let mut block = vec![0u8; 1 << BLOCK_SIZE_LOG2];
+            for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+                block[i / 8] |= 0x80 >> (i % 8);
+            }
+            block
+        })
+        .collect();
+    Compressed { header, sparse_index, block_lengths, blocks: bytes }
+}
+
+// A compressed table file with `pieces` in encoding order. `files` holds
+// the flags and the values of each stored side, for every leading pawn file.
+fn compressed_table(magic: [u8; 4], file_flags: u8, pieces: &[u8], files: &[Vec<(u8, Vec<u16>)>]) -> Vec<u8> {
+    let mut data = magic.to_vec();
+    data.push(file_flags);
+    for _ in files {
+        data.push(0x00);
+        data.extend(pieces.iter().map(|code| code | code << 4));
+    }
+    if data.len() % 2 == 1 {
+        data.push(0);
+    }
+    let parts: Vec<Compressed> = files
+        .iter()
+        .flatten()
+        .map(|(flags, values)| {
+            let mut part = compress(values);
+            part.header[0] = *flags;
+            part
+        })
+        .collect();
+    for part in &parts {
+        data.extend(&part.header);
+    }
+    // DTZ files have their value maps here, aligned, and these have none
+    if magic == DTZ_MAGIC && data.len() % 2 == 1 {
+        data.push(0);
+    }
+    for part in &parts {
+        data.extend(&part.sparse_index);
+    }
+    for part in &parts {
+        data.extend(&part.block_lengths);
+    }
+    for part in &parts {
+        data.resize(data.len().next_multiple_of(64), 0);
+        data.extend(&part.blocks);
+    }
+    data
+}
+
+fn table_dir(name: &str, files: &[(&str, Vec<u8>)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rookbot-syzygy-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file_name, data) in files {
+        std::fs::write(dir.join(file_name), data).unwrap();
+    }
+    dir
+}
+
+// KQvK: won with white to move, lost with black to move. DTZ is stored for
+// white to move as 5 moves.
+fn kqk_tables() -> Vec<(&'static str, Vec<u8>)> {
+    let pieces = [6, 5, 14];
+    vec![
+        ("KQvK.rtbw", single_value_table(WDL_MAGIC, 1, &pieces, &[vec![(0, 4), (0, 0)]])),
+        ("KQvK.rtbz", single_value_table(DTZ_MAGIC, 1, &pieces, &[vec![(0, 5)]])),
+    ]
+}
+
+fn fen(pieces: &[(char, usize)], white_to_move: bool) -> String {
+    let mut placement = String::new();
+    for rank in (0..8).rev() {
+        let mut empty = 0;
+        for file in 0..8 {
+            match pieces.iter().find(|(_, sqr)| *sqr == rank * 8 + file) {
+                Some((piece, _)) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                    }
+                    placement.push(*piece);
+                    empty = 0;
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if rank > 0 {
+            placement.push('/');
+        }
+    }
+    format!("{} {} - - 0 1", placement, if white_to_move { 'w' } else { 'b' })
+}
+
+// Legal positions of three pieces, given as FEN letters in encoding order,
+// with white to move and with black to move. The first piece only goes on
+// `first_squares`.
+fn legal_positions(pieces: [char; 3], first_squares: impl Iterator<Item = usize>) -> Vec<([usize; 3], Board)> {
+    let mut positions = Vec::new();
+    for first in first_squares {
+        for second in (0..64).filter(|sqr| *sqr != first) {
+            for third in (0..64).filter(|sqr| *sqr != first && *sqr != second) {
+                let squares = [first, second, third];
+                let on_back_rank = |(piece, sqr): (&char, &usize)| piece.eq_ignore_ascii_case(&'p') && (sqr / 8 == 0 || sqr / 8 == 7);
+                if pieces.iter().zip(&squares).any(on_back_rank) {
+                    continue;
+                }
+                let king = |king: char| squares[pieces.iter().position(|piece| *piece == king).unwrap()];
+                if Square::new(king('K') as u8).distance(Square::new(king('k') as u8)) < 2 {
+                    continue;
+                }
+                let placed: Vec<(char, usize)> = pieces.iter().copied().zip(squares).collect();
+                for white_to_move in [true, false] {
+                    let board = Board::from_fen(&fen(&placed, white_to_move));
+                    let (waiting_king, mover) = if white_to_move { (king('k'), PieceColor::WHITE) } else { (king('K'), PieceColor::BLACK) };
+                    if !board.is_square_attacked_by(Square::new(waiting_king as u8), mover) {
+                        positions.push((squares, board));
+                    }
+                }
+            }
+        }
+    }
+    positions
+}
+
+// The a1-d1-d4 triangle the first piece of a table without pawns is moved to
+fn triangle() -> impl Iterator<Item = usize> {
+    (0..64).filter(|sqr| sqr % 8 <= 3 && sqr / 8 <= sqr % 8)
+}
+
+fn dtm_wdl(tables: &DtmTablebases, board: &Board) -> Wdl {
+    match tables.probe(board).unwrap() {
+        Dtm::Win(_) => Wdl::Win,
+        Dtm::Loss(_) => Wdl::Loss,
+        Dtm::Draw => Wdl::Draw,
+    }
+}
+
+// The WDL file and, without pawns, the DTZ file of a three piece table
+// holding the values of the retrograde DTM solver. Files of the same layout
+// storing the low and the high bits of each index find out where the values
+// go. Without pawns the winning side has no zeroing move but the mate, so
+// DTZ is the distance to mate, stored in plies for white to move.
+fn solved_tables(tables: &DtmTablebases, name: &str, pieces: [char; 3]) -> Vec<(String, Vec<u8>)> {
+    let material = Material::from_name(name).unwrap();
+    let codes: Vec<u8> = pieces.iter().map(|piece| piece_code(*piece)).collect();
+    let (files, size, first_squares): (usize, usize, Vec<usize>) = if material.has_pawns {
+        // The leading pawn on one of 6 squares of its file, then two other pieces
+        (4, 6 * 63 * 62, (8..56).filter(|sqr| sqr % 8 <= 3).collect())
+    } else {
+        (1, UNIQUE_PIECES_SIZE, triangle().collect())
+    };
+    let wdl_flags = FILE_SPLIT | if material.has_pawns { HAS_PAWNS } else { 0 };
+
+    let dir = table_dir(&format!("{}-index", name), &[]);
+    let [low, high] = [0, 11].map(|shift| {
+        let bits: Vec<u16> = (0..size).map(|idx| (idx >> shift & 0x7FF) as u16).collect();
+        let path = dir.join(format!("{}.rtbw", name));
+        std::fs::write(&path, compressed_table(WDL_MAGIC, wdl_flags, &codes, &vec![vec![(0, bits.clone()), (0, bits)]; files])).unwrap();
+        TableFile::load(&path, TableKind::Wdl, &material).unwrap()
+    });
+    std::fs::remove_dir_all(dir).unwrap();
+
+    // Illegal positions are stored as draws
+    let mut wdl = vec![[vec![None; size], vec![None; size]]; files];
+    let mut dtz = vec![vec![0; size]; files];
+    for (squares, board) in legal_positions(pieces, first_squares.into_iter()) {
+        let white_to_move = board.turn == PieceColor::WHITE;
+        let placed: Vec<(u8, usize)> = codes.iter().copied().zip(squares).collect();
+        let idx = stored_value(&low, &placed, white_to_move) as usize + ((stored_value(&high, &placed, white_to_move) as usize) << 11);
+        let file = if material.has_pawns { squares[0] % 8 } else { 0 };
+        let dtm = tables.probe(&board).unwrap();
+        let value = Some((dtm_wdl(tables, &board) as i32 + 2) as u16);
+        let stored = &mut wdl[file][!white_to_move as usize][idx];
+        // Symmetric positions share an index
+        assert!(stored.is_none() || *stored == value, "{}", board.to_fen());
+        *stored = value;
+        if let (true, Dtm::Win(plies)) = (white_to_move, dtm) {
+            dtz[file][idx] = plies as u16 - 1;
+        }
+    }
+    let wdl: Vec<Vec<(u8, Vec<u16>)>> = wdl.into_iter().map(|sides| sides.into_iter().map(|side| (0, side.iter().map(|value| value.unwrap_or(2)).collect())).collect()).collect();
+    let mut files = vec![(format!("{}.rtbw", name), compressed_table(WDL_MAGIC, wdl_flags, &codes, &wdl))];
+    if !material.has_pawns {
+        let dtz: Vec<Vec<(u8, Vec<u16>)>> = dtz.into_iter().map(|values| vec![(WIN_PLIES, values)]).collect();
+        files.push((format!("{}.rtbz", name), compressed_table(DTZ_MAGIC, 0, &codes, &dtz)));
+    }
+    files
+}
+
+fn piece_code(piece: char) -> u8 {
+    let code = "PNBRQK".find(piece.to_ascii_uppercase()).unwrap() as u8 + 1;
+    if piece.is_ascii_lowercase() { code | 8 } else { code }
+}
+
+fn stored_value(file: &TableFile, pieces: &[(u8, usize)], white_to_move: bool) -> u16 {
+    match file.probe(pieces, white_to_move, 0) {
+        Some(Lookup::Value(value)) => (value + 2) as u16,
+        _ => panic!("no value for {:?}", pieces),
+    }
+}
+
+// Every placement of the rook and the black king for a few white kings
+fn assert_agrees_with_solver(tablebases: &Tablebases, tables: &DtmTablebases) {
+    for (_, mut board) in legal_positions(['K', 'R', 'k'], [Square::A1, Square::E4, Square::H7, Square::C8].map(|sqr| sqr.index()).into_iter()) {
+        assert_eq!(tablebases.probe_wdl(&mut board), Some(dtm_wdl(tables, &board)), "{}", board.to_fen());
+    }
+}
+
+fn probe_wdl(tablebases: &Tablebases, fen: &str) -> Option<Wdl> {
+    tablebases.probe_wdl(&mut Board::from_fen(fen))
+}
+
+#[test]
+fn material_from_table_names() {
+    let krpvkr = Material::from_name("KRPvKR").unwrap();
+    assert_eq!((krpvkr.piece_count, krpvkr.has_pawns, krpvkr.pawn_count, krpvkr.symmetric), (5, true, [1, 0], false));
+    assert!(krpvkr.has_unique_pieces);
+    let kpvkpp = Material::from_name("KPvKPP").unwrap();
+    assert_eq!(kpvkpp.pawn_count, [1, 2]);
+    let kppvkp = Material::from_name("KPPvKP").unwrap();
+    assert_eq!(kppvkp.pawn_count, [1, 2]);
+    let knnvk = Material::from_name("KNNvK").unwrap();
+    assert!(!knnvk.has_unique_pieces);
+    assert!(Material::from_name("KRvKR").unwrap().symmetric);
+    for name in ["KRK", "RvK", "KXvK", "KQQQQvKQQ"] {
+        assert_eq!(Material::from_name(name), None, "{}", name);
+    }
+}
+
+#[test]
+fn wdl_probes_use_colour_flips_and_captures() {
+    let dir = table_dir("wdl", &kqk_tables());
+    let tablebases = Tablebases::new(dir.to_str().unwrap());
+    assert_eq!(tablebases.max_pieces(), 3);
+
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/8/Q3K3 w - - 0 1"), Some(Wdl::Win));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/8/Q3K3 b - - 0 1"), Some(Wdl::Loss));
+    assert_eq!(probe_wdl(&tablebases, "4K3/8/8/8/8/8/8/q3k3 b - - 0 1"), Some(Wdl::Win));
+    // The queen can be taken, leaving a draw
+    assert_eq!(probe_wdl(&tablebases, "8/8/8/8/8/8/1Q6/k3K3 b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/8/4K3 w - - 0 1"), Some(Wdl::Draw));
+    // Too many pieces, or no table for the material
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/PPPPPPPP/4K3 w - - 0 1"), None);
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), None);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pawn_tables_are_split_by_file() {
+    let pieces = [1, 6, 14];
+    let won = vec![(0, 4), (0, 0)];
+    let drawn = vec![(0, 2), (0, 2)];
+    let wdl = single_value_table(WDL_MAGIC, 1 | HAS_PAWNS, &pieces, &[won.clone(), drawn, won.clone(), won]);
+    let dir = table_dir("pawns", &[("KPvK.rtbw", wdl)]);
+    let tablebases = Tablebases::new(dir.to_str().unwrap());
+
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/P7/4K3 w - - 0 1"), Some(Wdl::Win));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/7P/4K3 w - - 0 1"), Some(Wdl::Win));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/1P6/4K3 w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/6P1/4K3 w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/P7/4K3 b - - 0 1"), Some(Wdl::Loss));
+    assert_eq!(probe_wdl(&tablebases, "4k3/1p6/8/8/8/8/8/4K3 b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "4k3/p7/8/8/8/8/8/4K3 b - - 0 1"), Some(Wdl::Win));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn dtz_ranks_root_moves() {
+    let dir = table_dir("dtz", &kqk_tables());
+    let tablebases = Tablebases::new(dir.to_str().unwrap());
+
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+    assert_eq!(tablebases.probe_dtz(&mut board), Some(11));
+    // Stored for the other side to move: one ply further
+    assert_eq!(tablebases.probe_dtz(&mut Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1")), Some(-12));
+
+    let ranked = tablebases.rank_root_moves(&mut board).unwrap();
+    let hanging: Vec<String> = ranked.iter().filter(|root| root.rank == 0).map(|root| root.mv.to_uci(false)).collect();
+    assert_eq!(hanging.len(), 2, "{:?}", hanging);
+    assert!(hanging.contains(&"d1d7".to_string()) && hanging.contains(&"d1d8".to_string()));
+    assert!(ranked.iter().filter(|root| root.rank != 0).all(|root| root.rank == 1000 && root.dtz == 13));
+
+    // A high halfmove clock turns certain wins into ranked ones
+    let mut late = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 95 80");
+    assert!(tablebases.rank_root_moves(&mut late).unwrap().iter().all(|root| root.rank < 1000));
+
+    let mut moves: Vec<_> = generate_moves(&mut board).iter().copied().collect();
+    let count = moves.len();
+    assert!(tablebases.filter_root_moves(&mut board, &mut moves));
+    assert_eq!(moves.len(), count - 2);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn corrupt_or_missing_tables_are_not_probed() {
+    let dir = table_dir("corrupt", &[("KRvK.rtbw", vec![0; 40]), ("notes.txt", vec![1, 2, 3])]);
+    let tablebases = Tablebases::new(dir.to_str().unwrap());
+    assert_eq!(tablebases.max_pieces(), 3);
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), None);
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let none = Tablebases::new("/nonexistent/syzygy");
+    assert!(none.is_empty());
+    assert!(!none.can_probe(&Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1")));
+}
+
+#[test]
+fn uci_searches_tablebase_root_moves() {
+    let dir = table_dir("uci", &kqk_tables());
+    let mut uci = Uci::new();
+    assert_eq!(uci.handle_command(&format!("setoption name SyzygyPath value {}", dir.display())), vec!["info string found 3-piece tablebases"]);
+    uci.handle_command("position fen 4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+    // The moves that throw the win away are left out of the search
+    uci.handle_command("setoption name MultiPV value 40");
+    uci.handle_command("go depth 2");
+    let output = uci.wait();
+    let lines: Vec<&String> = output.iter().filter(|line| line.starts_with("info depth 2 ")).collect();
+    assert_eq!(lines.len(), generate_moves(&mut Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1")).len() - 2, "{:?}", output);
+    assert!(lines.iter().all(|line| !line.contains(" pv d1d7") && !line.contains(" pv d1d8")), "{:?}", output);
+    let best = output.last().unwrap();
+    assert!(best.starts_with("bestmove ") && !best.starts_with("bestmove d1d7") && !best.starts_with("bestmove d1d8"), "{:?}", output);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// Committed KRvK, KQvK and KPvK tables, built by `solved_tables`
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/syzygy")
+}
+
+#[test]
+fn fixture_tables_hold_known_values() {
+    let tablebases = Tablebases::new(fixture_dir().to_str().unwrap());
+    assert_eq!(tablebases.max_pieces(), 3);
+    let dtz = |fen: &str| tablebases.probe_dtz(&mut Board::from_fen(fen));
+
+    // Mate on the back rank, or the rook taken
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/4K3/8/8/8/8/R7 w - - 0 1"), Some(Wdl::Win));
+    assert_eq!(dtz("4k3/8/4K3/8/8/8/8/R7 w - - 0 1"), Some(1));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/4K3/8/8/8/8/R7 b - - 0 1"), Some(Wdl::Loss));
+    assert!(dtz("4k3/8/4K3/8/8/8/8/R7 b - - 0 1").unwrap() < 0);
+    assert_eq!(probe_wdl(&tablebases, "8/8/8/8/8/8/1Rk5/7K b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(dtz("8/8/8/8/8/8/1Rk5/7K b - - 0 1"), Some(0));
+    assert_eq!(probe_wdl(&tablebases, "k7/8/K7/8/8/8/8/1R6 b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "4K3/8/4k3/8/8/8/8/r7 b - - 0 1"), Some(Wdl::Win));
+    assert_eq!(dtz("4K3/8/4k3/8/8/8/8/r7 b - - 0 1"), Some(1));
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/4K3/8/8/8/8/Q7 w - - 0 1"), Some(Wdl::Win));
+    assert_eq!(dtz("4k3/8/4K3/8/8/8/8/Q7 w - - 0 1"), Some(1));
+    assert_eq!(probe_wdl(&tablebases, "8/8/8/8/8/8/1Qk5/7K b - - 0 1"), Some(Wdl::Draw));
+
+    // The longest wins take 16 moves with a rook and 10 with a queen
+    for (piece, longest) in [('R', 31), ('Q', 19)] {
+        let positions = legal_positions(['K', piece, 'k'], triangle());
+        let max = positions.into_iter().filter(|(_, board)| board.turn == PieceColor::WHITE).filter_map(|(_, mut board)| tablebases.probe_dtz(&mut board)).max();
+        assert_eq!(max, Some(longest), "{}", piece);
+    }
+
+    // A king on the sixth rank in front of its pawn wins, otherwise the
+    // opposition decides. A rook pawn does not win against a king in front
+    // of it.
+    assert_eq!(probe_wdl(&tablebases, "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(Wdl::Win));
+    assert_eq!(probe_wdl(&tablebases, "8/4k3/8/4K3/4P3/8/8/8 w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "8/4k3/8/4K3/4P3/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+    assert_eq!(probe_wdl(&tablebases, "8/3k4/8/3K4/3P4/8/8/8 w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "8/4P3/8/8/8/8/k7/4K3 b - - 0 1"), Some(Wdl::Loss));
+    assert_eq!(probe_wdl(&tablebases, "8/k7/8/8/8/8/P7/7K w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "7k/8/8/8/8/8/7P/7K w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "8/8/8/4p3/4k3/8/4K3/8 b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(probe_wdl(&tablebases, "8/8/8/4p3/4k3/8/4K3/8 w - - 0 1"), Some(Wdl::Loss));
+}
+
+#[test]
+fn fixture_tables_agree_with_the_solver() {
+    let mut tables = DtmTablebases::new();
+    generate("KPK", &mut tables).unwrap();
+    let tablebases = Tablebases::new(fixture_dir().to_str().unwrap());
+    assert_agrees_with_solver(&tablebases, &tables);
+    for (name, pieces) in [("KRvK", ['K', 'R', 'k']), ("KQvK", ['K', 'Q', 'k']), ("KPvK", ['P', 'K', 'k'])] {
+        for (file, data) in solved_tables(&tables, name, pieces) {
+            assert!(std::fs::read(fixture_dir().join(&file)).unwrap() == data, "{} differs from the solver", file);
+        }
+    }
+}
+
+// Checks downloaded tables against the DTM solver when ROOKBOT_SYZYGY_PATH
+// points at them, e.g. the 3-4-5 piece set from tablebase.lichess.ovh
+#[test]
+fn published_tables_agree_with_the_solver() {
+    let Ok(path) = std::env::var("ROOKBOT_SYZYGY_PATH") else {
+        return;
+    };
+    let mut tables = DtmTablebases::new();
+    generate("KRK", &mut tables).unwrap();
+    let tablebases = Tablebases::new(&path);
+    assert_agrees_with_solver(&tablebases, &tables);
+}
+
+#[test]
+fn search_scores_tablebase_wins_below_mates() {
+    let tablebases = Arc::new(Tablebases::new(fixture_dir().to_str().unwrap()));
+
+    // Taking the knight reaches a won KRvK
+    let mut board = Board::from_fen("7k/8/8/8/8/8/8/R3n1K1 w - - 0 1");
+    let mut searcher = Searcher::default();
+    searcher.set_tablebases(Some(tablebases));
+    let result = searcher.search(&mut board, &SearchLimits::depth(2), &[], |_| {});
+    assert_eq!(result.best_move.unwrap().to_uci(false), "a1e1");
+    assert_eq!(result.score, TB_WIN - 1);
+
+    // Without the tables it is only a won evaluation
+    let result = Searcher::default().search(&mut board, &SearchLimits::depth(2), &[], |_| {});
+    assert!(result.score < TB_WIN - 1000);
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use rookbot::board::board::Board;
+use rookbot::eval::endgame::SCALE_NORMAL;
+use rookbot::eval::evaluate_with_params;
+use rookbot::eval::params::{EvalParams, ParamsError, DEFAULT_PARAMS};
+use rookbot::tune::{fit_k, load_dataset, mean_error, parse_line, tune, LabelledPosition};
+use rookbot::uci::Uci;
+
+#[test]
+fn dataset_lines_are_parsed() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    assert_eq!(parse_line(&format!("{} [1.0]", fen)), Some((fen.to_string(), 1.0)));
+    assert_eq!(parse_line(&format!("{} [0.5]", fen)), Some((fen.to_string(), 0.5)));
+    assert_eq!(parse_line(&format!("{} \"0-1\";", fen)), Some((fen.to_string(), 0.0)));
+    assert_eq!(parse_line(&format!("{} 1/2-1/2", fen)), Some((fen.to_string(), 0.5)));
+    // EPD without move counters
+    assert_eq!(
+        parse_line("8/8/8/4k3/8/8/4K3/4Q3 b - - c9 \"1-0\";"),
+        Some(("8/8/8/4k3/8/8/4K3/4Q3 b - - 0 1".to_string(), 1.0))
+    );
+    assert_eq!(parse_line(fen), None);
+    assert_eq!(parse_line(&format!("{} [2.0]", fen)), None);
+}
+
+#[test]
+fn params_round_trip_through_text() {
+    let mut params = DEFAULT_PARAMS;
+    params.doubled.eg = -40;
+    params.pawn_storm[3] = -20;
+    assert_eq!(EvalParams::from_text(&params.to_text()).unwrap(), params);
+    assert_eq!(params.to_vec().len(), EvalParams::names().len());
+    // Weights the evaluation never reads are not tuned
+    let names = EvalParams::names();
+    for unused in ["material.king.mg", "mobility.pawn.mg", "mobility.king.eg", "king_attack_weights.king", "passed.7.mg", "phalanx.0.eg", "pawn_shelter.7"] {
+        assert!(!names.iter().any(|name| name == unused), "{}", unused);
+    }
+    assert!(names.iter().any(|name| name == "passed.6.mg"));
+
+    // Scale factors and caps stay within their range
+    let mut clamped = DEFAULT_PARAMS;
+    clamped.set_from_slice(&vec![-5; names.len()]);
+    assert_eq!((clamped.king_danger_max, clamped.opposite_bishops_scale), (0, 0));
+    clamped.set_from_slice(&vec![100; names.len()]);
+    assert_eq!(clamped.opposite_bishops_scale, SCALE_NORMAL);
+    assert_eq!(EvalParams::from_text("opposite_bishops_scale -3\n").unwrap().opposite_bishops_scale, 0);
+
+    // Missing weights keep their default
+    let partial = EvalParams::from_text("# tuned\n\nmaterial.knight.mg 300\n").unwrap();
+    assert_eq!((partial.material[1].mg, partial.material[1].eg), (300, 320));
+    assert!(matches!(EvalParams::from_text("doubled.mg"), Err(ParamsError::InvalidLine(1))));
+    assert!(matches!(EvalParams::from_text("tempo 10"), Err(ParamsError::UnknownParameter(name)) if name == "tempo"));
+
+    let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let mut flat = DEFAULT_PARAMS;
+    flat.set_from_slice(&vec![0; DEFAULT_PARAMS.to_vec().len()]);
+    assert_eq!(evaluate_with_params(&board, &flat), 0);
+}
+
+#[test]
+fn tuning_lowers_the_error() {
+    // White wins every game a knight up, and draws the equal ones
+    let knight_up = ["4k3/pppp4/8/8/8/2N5/PPPP4/4K3 w - - 0 1", "4k3/4pppp/8/8/8/5N2/4PPPP/4K3 b - - 0 1"];
+    let equal = ["4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1", "4k3/4pppp/8/8/8/8/4PPPP/4K3 b - - 0 1"];
+    let dir = std::env::temp_dir().join(format!("rookbot-tune-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dataset.epd");
+    let lines: Vec<String> = knight_up.iter().map(|fen| format!("{} [1.0]", fen)).chain(equal.iter().map(|fen| format!("{} [0.5]", fen))).collect();
+    fs::write(&path, lines.join("\n")).unwrap();
+    let positions: Vec<LabelledPosition> = load_dataset(&path).unwrap();
+    assert_eq!(positions.len(), 4);
+
+    let mut params = DEFAULT_PARAMS;
+    let k = fit_k(&positions, &params);
+    assert!(k > 0.0);
+    let before = mean_error(&positions, &params, k);
+    let mut passes = 0;
+    let after = tune(&positions, &mut params, k, 1, |pass, error, _| {
+        passes = pass;
+        assert!(error < before);
+    });
+    assert_eq!(passes, 1);
+    assert!(after < before);
+    assert_eq!(after, mean_error(&positions, &params, k));
+
+    // The engine loads the written weights
+    let file = dir.join("params.txt");
+    params.save(&file).unwrap();
+    assert_eq!(EvalParams::load(&file).unwrap(), params);
+    let mut uci = Uci::new();
+    assert!(uci.handle_command(&format!("setoption name EvalParams value {}", file.display())).is_empty());
+    uci.handle_command(&format!("position fen {}", knight_up[0]));
+    let output = uci.handle_command("eval");
+    let expected = evaluate_with_params(&Board::from_fen(knight_up[0]), &params);
+    assert!(output[0].contains(&format!("\"relative_score\":{}}}", expected)), "{:?}", output);
+    // Any other position is traced with `eval fen`
+    uci.handle_command("position startpos");
+    let output = uci.handle_command(&format!("eval fen {}", knight_up[0]));
+    assert!(output[0].contains(&format!("\"relative_score\":{}}}", expected)), "{:?}", output);
+    assert!(uci.handle_command("eval fen 8/8")[0].starts_with("info string invalid fen 8/8"));
+    fs::remove_dir_all(&dir).unwrap();
+}